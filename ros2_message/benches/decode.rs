@@ -0,0 +1,183 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ros2_message::dynamic::DynamicMsg;
+use ros2_message::{MessageValue, Value};
+use std::hash::RandomState;
+use std::hint::black_box;
+
+const TF_MESSAGE: &str = r#"
+geometry_msgs/TransformStamped[] transforms
+
+================================================================================
+MSG: geometry_msgs/TransformStamped
+std_msgs/Header header
+string child_frame_id
+Transform transform
+
+================================================================================
+MSG: std_msgs/Header
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Transform
+Vector3 translation
+Quaternion rotation
+
+================================================================================
+MSG: geometry_msgs/Vector3
+float64 x
+float64 y
+float64 z
+
+================================================================================
+MSG: geometry_msgs/Quaternion
+float64 x 0
+float64 y 0
+float64 z 0
+float64 w 1
+"#;
+
+const POINT_CLOUD: &str = r#"
+std_msgs/Header header
+uint32 height
+uint32 width
+PointField[] fields
+bool is_bigendian
+uint32 point_step
+uint32 row_step
+uint8[] data
+bool is_dense
+
+================================================================================
+MSG: std_msgs/Header
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: sensor_msgs/PointField
+uint8 INT8    = 1
+uint8 UINT8   = 2
+uint8 INT16   = 3
+uint8 UINT16  = 4
+uint8 INT32   = 5
+uint8 UINT32  = 6
+uint8 FLOAT32 = 7
+uint8 FLOAT64 = 8
+string name
+uint32 offset
+uint8  datatype
+uint32 count
+"#;
+
+fn message(fields: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Message(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect(),
+    )
+}
+
+fn header(frame_id: &str) -> Value {
+    message([
+        (
+            "stamp",
+            message([("sec", Value::I32(1720201117)), ("nanosec", Value::U32(42))]),
+        ),
+        ("frame_id", Value::String(frame_id.to_owned())),
+    ])
+}
+
+fn vector(names: &[&'static str]) -> Value {
+    message(names.iter().map(|name| (*name, Value::F64(0.5))))
+}
+
+fn tf_message() -> MessageValue<RandomState> {
+    let transforms = (0..100)
+        .map(|i| {
+            message([
+                ("header", header("map")),
+                ("child_frame_id", Value::String(format!("link_{i}"))),
+                (
+                    "transform",
+                    message([
+                        ("translation", vector(&["x", "y", "z"])),
+                        ("rotation", vector(&["x", "y", "z", "w"])),
+                    ]),
+                ),
+            ])
+        })
+        .collect();
+
+    [("transforms", Value::Array(transforms))]
+        .into_iter()
+        .collect::<Value>()
+        .try_into_map()
+        .unwrap()
+}
+
+fn point_cloud() -> MessageValue<RandomState> {
+    let fields = ["x", "y", "z"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            message([
+                ("name", Value::String(name.to_owned())),
+                ("offset", Value::U32(4 * i as u32)),
+                ("datatype", Value::U8(7)),
+                ("count", Value::U32(1)),
+            ])
+        })
+        .collect();
+
+    [
+        ("header", header("lidar")),
+        ("height", Value::U32(1)),
+        ("width", Value::U32(4096)),
+        ("fields", Value::Array(fields)),
+        ("is_bigendian", Value::Bool(false)),
+        ("point_step", Value::U32(12)),
+        ("row_step", Value::U32(12 * 4096)),
+        ("data", Value::Array(vec![Value::U8(7); 12 * 4096])),
+        ("is_dense", Value::Bool(true)),
+    ]
+    .into_iter()
+    .collect::<Value>()
+    .try_into_map()
+    .unwrap()
+}
+
+fn bench_message(
+    c: &mut Criterion,
+    name: &str,
+    definition: &str,
+    value: MessageValue<RandomState>,
+) {
+    let dynamic_message = DynamicMsg::<RandomState>::new(name, definition).unwrap();
+    let data = dynamic_message.encode(&value).unwrap();
+
+    c.bench_function(&format!("decode {name}"), |b| {
+        b.iter(|| dynamic_message.decode(black_box(&data[..])).unwrap())
+    });
+    c.bench_function(&format!("decode_borrowed {name}"), |b| {
+        b.iter(|| dynamic_message.decode_borrowed(black_box(&data)).unwrap())
+    });
+}
+
+fn decode(c: &mut Criterion) {
+    bench_message(c, "tf2_msgs/msg/TFMessage", TF_MESSAGE, tf_message());
+    bench_message(c, "sensor_msgs/msg/PointCloud2", POINT_CLOUD, point_cloud());
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,203 @@
+use crate::parse_msg::split_sections;
+use crate::{ActionPath, Error, Msg, Result, Srv};
+use derive_where::derive_where;
+use serde_derive::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Formatter;
+use std::hash::{BuildHasher, RandomState};
+
+/// Path and definition of the id that identifies the goals of actions.
+pub(crate) const UUID: (&str, &str) = ("unique_identifier_msgs/UUID", "uint8[16] uuid");
+
+/// A ROS action parsed from an `action` file.
+///
+/// Besides the goal, result and feedback messages of the definition, it provides the services
+/// and messages that ROS 2 derives from them to actually send goals, results and feedback.
+#[derive(Serialize, Deserialize)]
+#[derive_where(Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(into = "ActionSerde")]
+#[serde(try_from = "ActionSerde")]
+pub struct Action<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
+    path: ActionPath,
+    source: String,
+    goal: Msg<S>,
+    result: Msg<S>,
+    feedback: Msg<S>,
+    send_goal: Srv<S>,
+    get_result: Srv<S>,
+    feedback_message: Msg<S>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> fmt::Display for Action<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Action<S> {
+    /// Create an action from a passed in path and source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is an error parsing the action source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Action = ros2_message::Action<std::hash::RandomState>;
+    /// # use std::convert::TryInto;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let action = Action::new(
+    ///     "foo/Fibonacci".try_into()?,
+    ///     r#"# a comment that is ignored
+    ///     int32 order
+    /// ---
+    ///     int32[] sequence
+    /// ---
+    ///     int32[] partial_sequence
+    ///     "#,
+    /// )?;
+    ///
+    /// assert_eq!(action.path(), &"foo/Fibonacci".try_into()?);
+    /// assert_eq!(action.goal().path(), &"foo/Fibonacci_Goal".try_into()?);
+    /// assert_eq!(action.goal().fields().len(), 1);
+    /// assert_eq!(action.result().fields().len(), 1);
+    /// assert_eq!(action.feedback().fields().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(path: ActionPath, source: impl Into<String>) -> Result<Action<S>> {
+        let source = source.into();
+        let (goal, result, feedback) = match split_sections(&source).as_slice() {
+            &[goal, result, feedback] => (goal, result, feedback),
+            v => {
+                return Err(Error::BadMessageContent(format!(
+                    "Action {} needs to be split into 3 parts, but is split into {}",
+                    path,
+                    v.len()
+                )))
+            }
+        };
+        let goal = Msg::new(path.goal(), goal)?;
+        let result = Msg::new(path.result(), result)?;
+        let feedback = Msg::new(path.feedback(), feedback)?;
+
+        let send_goal = Srv::new(
+            path.send_goal(),
+            format!(
+                "{} goal_id\n{} goal\n---\nbool accepted\nbuiltin_interfaces/Time stamp",
+                UUID.0,
+                goal.path().name()
+            ),
+        )?;
+        let get_result = Srv::new(
+            path.get_result(),
+            format!(
+                "{} goal_id\n---\nint8 status\n{} result",
+                UUID.0,
+                result.path().name()
+            ),
+        )?;
+        let feedback_message = Msg::new(
+            path.feedback_message(),
+            &format!("{} goal_id\n{} feedback", UUID.0, feedback.path().name()),
+        )?;
+
+        Ok(Action {
+            path,
+            source,
+            goal,
+            result,
+            feedback,
+            send_goal,
+            get_result,
+            feedback_message,
+        })
+    }
+
+    /// Returns the path of the action.
+    pub fn path(&self) -> &ActionPath {
+        &self.path
+    }
+
+    /// Returns the original source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the goal message.
+    pub fn goal(&self) -> &Msg<S> {
+        &self.goal
+    }
+
+    /// Returns the result message.
+    pub fn result(&self) -> &Msg<S> {
+        &self.result
+    }
+
+    /// Returns the feedback message.
+    pub fn feedback(&self) -> &Msg<S> {
+        &self.feedback
+    }
+
+    /// Returns the service that sends a goal, which is identified by a
+    /// `unique_identifier_msgs/UUID`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Action = ros2_message::Action<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let action = Action::new("foo/Count".try_into()?, "int32 to\n---\n---\nint32 current")?;
+    ///
+    /// let send_goal = action.send_goal();
+    /// assert_eq!(send_goal.path(), &"foo/Count_SendGoal".try_into()?);
+    /// assert_eq!(
+    ///     send_goal.request().dependencies(),
+    ///     vec!["unique_identifier_msgs/UUID".try_into()?, "foo/Count_Goal".try_into()?],
+    /// );
+    /// assert_eq!(format!("{}", send_goal.response().fields()[0]), "bool accepted");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_goal(&self) -> &Srv<S> {
+        &self.send_goal
+    }
+
+    /// Returns the service that requests the status and result of a goal.
+    pub fn get_result(&self) -> &Srv<S> {
+        &self.get_result
+    }
+
+    /// Returns the message published on the feedback topic, which holds the id of the goal
+    /// next to the feedback.
+    pub fn feedback_message(&self) -> &Msg<S> {
+        &self.feedback_message
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ActionSerde {
+    path: ActionPath,
+    source: String,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> TryFrom<ActionSerde> for Action<S> {
+    type Error = Error;
+
+    fn try_from(src: ActionSerde) -> Result<Self> {
+        Self::new(src.path, src.source)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<Action<S>> for ActionSerde {
+    fn from(src: Action<S>) -> Self {
+        Self {
+            path: src.path,
+            source: src.source,
+        }
+    }
+}
@@ -43,6 +43,18 @@ pub enum DataType {
     F64,
     /// Represents `string`.
     String,
+    /// Represents `string<=N`, a string of at most N bytes.
+    ///
+    /// The contained number is the bound.
+    BoundedString(usize),
+    /// Represents `wstring`, a string of UTF-16 code units.
+    WString,
+    /// Represents `wstring<=N`, a wide string of at most N UTF-16 code units.
+    ///
+    /// The contained number is the bound.
+    BoundedWString(usize),
+    /// Represents `wchar`, a single UTF-16 code unit.
+    WChar,
     /// Represents `time`.
     Time,
     /// Represents `duration`.
@@ -108,6 +120,10 @@ impl fmt::Display for DataType {
             DataType::F32 => FLOAT32_KEY.fmt(f),
             DataType::F64 => FLOAT64_KEY.fmt(f),
             DataType::String => STRING_KEY.fmt(f),
+            DataType::BoundedString(bound) => write!(f, "{}<={}", STRING_KEY, bound),
+            DataType::WString => WSTRING_KEY.fmt(f),
+            DataType::BoundedWString(bound) => write!(f, "{}<={}", WSTRING_KEY, bound),
+            DataType::WChar => WCHAR_KEY.fmt(f),
             DataType::Time => TIME_KEY.fmt(f),
             DataType::Duration => DURATION_KEY.fmt(f),
             DataType::LocalMessage(ref name) => name.fmt(f),
@@ -130,6 +146,8 @@ const UINT64_KEY: &str = "uint64";
 const FLOAT32_KEY: &str = "float32";
 const FLOAT64_KEY: &str = "float64";
 const STRING_KEY: &str = "string";
+const WSTRING_KEY: &str = "wstring";
+const WCHAR_KEY: &str = "wchar";
 const TIME_KEY: &str = "time";
 const DURATION_KEY: &str = "duration";
 
@@ -146,6 +164,8 @@ impl DataType {
     /// assert_eq!(DataType::parse("int16")?, DataType::I16);
     /// assert_eq!(DataType::parse("float64")?, DataType::F64);
     /// assert_eq!(DataType::parse("byte")?, DataType::I8(I8Variant::Byte));
+    /// assert_eq!(DataType::parse("string<=10")?, DataType::BoundedString(10));
+    /// assert_eq!(DataType::parse("wstring")?, DataType::WString);
     /// assert_eq!(
     ///     DataType::parse("Header")?,
     ///     DataType::GlobalMessage("std_msgs/Header".try_into()?),
@@ -160,6 +180,7 @@ impl DataType {
     /// );
     /// assert!(DataType::parse("00bad_package/Name").is_err());
     /// assert!(DataType::parse("a/bad/type").is_err());
+    /// assert!(DataType::parse("string<=ten").is_err());
     /// # Ok(())
     /// # }
     /// ```
@@ -179,10 +200,15 @@ impl DataType {
             FLOAT32_KEY => DataType::F32,
             FLOAT64_KEY => DataType::F64,
             STRING_KEY => DataType::String,
+            WSTRING_KEY => DataType::WString,
+            WCHAR_KEY => DataType::WChar,
             TIME_KEY => DataType::Time,
             DURATION_KEY => DataType::Duration,
             "Header" => DataType::GlobalMessage(MessagePath::new("std_msgs", "Header")?),
             _ => {
+                if let Some((name, bound)) = datatype.split_once("<=") {
+                    return Self::parse_bounded(datatype, name, bound);
+                }
                 let parts = datatype.splitn(3, '/').collect::<Vec<&str>>();
                 match parts[..] {
                     [name] => DataType::LocalMessage(name.into()),
@@ -198,6 +224,21 @@ impl DataType {
         })
     }
 
+    fn parse_bounded(datatype: &str, name: &str, bound: &str) -> Result<Self> {
+        let bound = bound.parse().map_err(|_| Error::UnsupportedDataType {
+            name: datatype.into(),
+            reason: "string bounds need to be in `string<=N` format".into(),
+        })?;
+        match name {
+            STRING_KEY => Ok(DataType::BoundedString(bound)),
+            WSTRING_KEY => Ok(DataType::BoundedWString(bound)),
+            _ => Err(Error::UnsupportedDataType {
+                name: datatype.into(),
+                reason: "only `string` and `wstring` can be bounded".into(),
+            }),
+        }
+    }
+
     /// Returns true if the type is a built in type, rather than another message.
     ///
     /// # Examples
@@ -229,15 +270,44 @@ impl DataType {
             | DataType::F32
             | DataType::F64
             | DataType::String
+            | DataType::BoundedString(_)
+            | DataType::WString
+            | DataType::BoundedWString(_)
+            | DataType::WChar
             | DataType::Time
             | DataType::Duration => true,
             DataType::LocalMessage(_) | DataType::GlobalMessage(_) => false,
         }
     }
 
+    /// Returns true if the type is one of the string types, bounded or not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::DataType;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// assert!(DataType::parse("string")?.is_string());
+    /// assert!(DataType::parse("wstring<=5")?.is_string());
+    /// assert!(!DataType::parse("wchar")?.is_string());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_string(&self) -> bool {
+        matches!(
+            self,
+            DataType::String
+                | DataType::BoundedString(_)
+                | DataType::WString
+                | DataType::BoundedWString(_)
+        )
+    }
+
     /// Returns the representation of the data type when constructing the MD5 sum.
     ///
-    /// For built in types, it is the same as the data type name.
+    /// For built in types, it is the same as the data type name. Bounds of strings are not
+    /// part of the representation, as ROS1 has no bounded strings.
     ///
     /// For message types, it is that message's MD5 sum, which is passed in via the `hashes`
     /// argument.
@@ -292,7 +362,9 @@ impl DataType {
             DataType::U64 => UINT64_KEY,
             DataType::F32 => FLOAT32_KEY,
             DataType::F64 => FLOAT64_KEY,
-            DataType::String => STRING_KEY,
+            DataType::String | DataType::BoundedString(_) => STRING_KEY,
+            DataType::WString | DataType::BoundedWString(_) => WSTRING_KEY,
+            DataType::WChar => WCHAR_KEY,
             DataType::Time => TIME_KEY,
             DataType::Duration => DURATION_KEY,
             DataType::LocalMessage(ref name) => hashes
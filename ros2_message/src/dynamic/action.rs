@@ -0,0 +1,147 @@
+use super::service::{collect_dependencies, strip_suffixes};
+use super::{DecodeOptions, DynamicMsg, DynamicSrv};
+use crate::action::UUID;
+use crate::error::Result;
+use crate::message_path::{
+    EVENT_SUFFIX, FEEDBACK_MESSAGE_SUFFIX, FEEDBACK_SUFFIX, GET_RESULT_SUFFIX, GOAL_SUFFIX,
+    REQUEST_SUFFIX, RESPONSE_SUFFIX, RESULT_SUFFIX, SEND_GOAL_SUFFIX,
+};
+use crate::{Action, ActionPath, Msg};
+use std::convert::TryInto;
+use std::hash::{BuildHasher, RandomState};
+
+/// A dynamic action provides decoders for the services and messages that ROS2 derives from an
+/// action at runtime, just like [DynamicMsg] does for messages. See [Self::new()] for more.
+#[derive(Clone, Debug)]
+pub struct DynamicAction<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
+    action: Action<S>,
+    send_goal: DynamicSrv<S>,
+    get_result: DynamicSrv<S>,
+    feedback_message: DynamicMsg<S>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicAction<S> {
+    /// Create a new `DynamicAction<S>` by parsing its action definition, which is the content of
+    /// the `action` file followed by the definitions of all messages it depends upon, in the same
+    /// format that [DynamicMsg::new()] takes.
+    ///
+    /// The action name may be given as `package/action/Name` or `package/Name`. Names of the
+    /// derived services and messages like `package/action/Name_FeedbackMessage` or
+    /// `package/action/Name_SendGoal_Event`, which rosbag2 records them under, are accepted as well.
+    ///
+    /// The `unique_identifier_msgs/UUID`, `builtin_interfaces/Time` and
+    /// `service_msgs/ServiceEventInfo` messages of the derived types don't need to be part of the
+    /// definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::DynamicAction;
+    /// use ros2_message::Value;
+    ///
+    /// let action_definition = r#"
+    /// int32 order
+    /// ---
+    /// int32[] sequence
+    /// ---
+    /// int32[] partial_sequence
+    /// "#;
+    /// let dynamic_action = DynamicAction::<std::hash::RandomState>::new("package/action/Fibonacci_FeedbackMessage", action_definition)
+    ///     .expect("The action definition was invalid");
+    ///
+    /// let mut bytes = vec![0x00u8, 0x01, 0, 0];
+    /// bytes.extend([7; 16]);
+    /// bytes.extend([1, 0, 0, 0, 42, 0, 0, 0]);
+    /// let feedback_message = dynamic_action.feedback_message().decode(&bytes[..])
+    ///     .expect("The supplied bytes do not match the action definition");
+    ///
+    /// assert_eq!(
+    ///     feedback_message["feedback"].as_map().unwrap()["partial_sequence"],
+    ///     Value::Array(vec![Value::I32(42)]),
+    /// );
+    /// ```
+    pub fn new(action_name: &str, action_definition: &str) -> Result<Self> {
+        let action_name = strip_suffixes(
+            action_name,
+            &[REQUEST_SUFFIX, RESPONSE_SUFFIX, EVENT_SUFFIX],
+        );
+        let action_name = strip_suffixes(
+            action_name,
+            &[
+                SEND_GOAL_SUFFIX,
+                GET_RESULT_SUFFIX,
+                FEEDBACK_MESSAGE_SUFFIX,
+                GOAL_SUFFIX,
+                RESULT_SUFFIX,
+                FEEDBACK_SUFFIX,
+            ],
+        );
+        let (action_src, dependencies) =
+            DynamicMsg::split_definition(action_name, action_definition)?;
+        let action = Action::new(action_name.try_into()?, action_src)?;
+
+        Ok(Self::from_action(action, dependencies.into_values()))
+    }
+
+    /// Create a new `DynamicAction<S>` from an already parsed action and all messages it depends
+    /// upon, directly or indirectly.
+    ///
+    /// Just like with [Self::new()], the messages of the derived types are added if missing.
+    pub fn from_action(action: Action<S>, dependencies: impl IntoIterator<Item = Msg<S>>) -> Self {
+        let mut dependencies = collect_dependencies(dependencies, &[UUID]);
+        for msg in [action.goal(), action.result(), action.feedback()] {
+            dependencies.insert(msg.path().clone(), msg.clone());
+        }
+
+        DynamicAction {
+            send_goal: DynamicSrv::from_srv(
+                action.send_goal().clone(),
+                dependencies.values().cloned(),
+            ),
+            get_result: DynamicSrv::from_srv(
+                action.get_result().clone(),
+                dependencies.values().cloned(),
+            ),
+            feedback_message: DynamicMsg::from_parts(
+                action.feedback_message().clone(),
+                dependencies,
+            ),
+            action,
+        }
+    }
+
+    /// Replaces the [DecodeOptions] used when decoding the derived services and messages.
+    pub fn with_options(self, options: DecodeOptions) -> Self {
+        DynamicAction {
+            send_goal: self.send_goal.with_options(options.clone()),
+            get_result: self.get_result.with_options(options.clone()),
+            feedback_message: self.feedback_message.with_options(options),
+            action: self.action,
+        }
+    }
+
+    /// Returns the underlying ROS2 action definition
+    pub fn action(&self) -> &Action<S> {
+        &self.action
+    }
+
+    /// Returns the path of the action
+    pub fn path(&self) -> &ActionPath {
+        self.action.path()
+    }
+
+    /// Returns the decoders of the service that sends goals, see [Action::send_goal()]
+    pub fn send_goal(&self) -> &DynamicSrv<S> {
+        &self.send_goal
+    }
+
+    /// Returns the decoders of the service that requests results, see [Action::get_result()]
+    pub fn get_result(&self) -> &DynamicSrv<S> {
+        &self.get_result
+    }
+
+    /// Returns the decoder of the feedback topic, see [Action::feedback_message()]
+    pub fn feedback_message(&self) -> &DynamicMsg<S> {
+        &self.feedback_message
+    }
+}
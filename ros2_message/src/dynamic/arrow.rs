@@ -0,0 +1,617 @@
+use super::DynamicMsg;
+use crate::error::{Error, Result};
+use crate::{DataType, FieldCase, MessagePath, MessageValue, Msg, PrimitiveArray, Value};
+use arrow_array::builder::{
+    BooleanBuilder, Float32Builder, Float64Builder, Int16Builder, Int32Builder, Int64Builder,
+    Int8Builder, OffsetBufferBuilder, StringBuilder, UInt16Builder, UInt32Builder, UInt64Builder,
+    UInt8Builder,
+};
+use arrow_array::{
+    ArrayRef, FixedSizeListArray, ListArray, RecordBatch, RecordBatchOptions, StructArray,
+};
+use arrow_schema::{DataType as ArrowType, Field, FieldRef, Fields, Schema, SchemaRef};
+use std::fmt::Write;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Returns the Apache Arrow schema of a table with one row per message.
+    ///
+    /// Every field of the message is a column, constants are left out. Numbers, `bool` and
+    /// strings map to the corresponding Arrow types, nested messages to structs, `time` and
+    /// `duration` to structs with `sec` and `nanosec` fields, arrays like `float64[]` and
+    /// `float64[<=3]` to lists and arrays like `float64[3]` to fixed size lists. No column or
+    /// element is nullable.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::CyclicDependency] if a message contains itself, which cannot be
+    /// represented by a schema.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arrow_schema::DataType;
+    /// use ros2_message::dynamic::DynamicMsg;
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new(
+    ///     "package/msg/Reading",
+    ///     "uint8 KIND=1\nuint16 id\nfloat32[] values",
+    /// )
+    /// .expect("The message definition was invalid");
+    /// let schema = dynamic_message.arrow_schema().expect("The message contains itself");
+    ///
+    /// assert_eq!(schema.fields().len(), 2);
+    /// assert_eq!(schema.field(0).data_type(), &DataType::UInt16);
+    /// assert!(matches!(schema.field(1).data_type(), DataType::List(_)));
+    /// ```
+    pub fn arrow_schema(&self) -> Result<Schema> {
+        Ok(Schema::new(self.record_batch_builder()?.fields.clone()))
+    }
+
+    /// Returns a builder which collects decoded messages into Arrow [RecordBatch]es with the
+    /// schema returned by [Self::arrow_schema()].
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::CyclicDependency] if a message contains itself.
+    pub fn record_batch_builder(&self) -> Result<RecordBatchBuilder> {
+        let columns = ColumnBuilder {
+            msg: self,
+            stack: vec![self.msg().path().clone()],
+        }
+        .message(self.msg())?;
+
+        Ok(RecordBatchBuilder {
+            fields: columns.fields(),
+            columns,
+            rows: 0,
+        })
+    }
+}
+
+/// Collects decoded messages into Arrow [RecordBatch]es, see
+/// [DynamicMsg::record_batch_builder()].
+///
+/// Messages are appended as rows with [Self::append()] and [Self::finish()] returns them as a
+/// [RecordBatch]. Decoded messages are accepted with and without
+/// [DecodeOptions::primitive_arrays](super::DecodeOptions::primitive_arrays) and
+/// [DecodeOptions::map_builtin_time](super::DecodeOptions::map_builtin_time) set.
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::dynamic::DynamicMsg;
+///
+/// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new(
+///     "package/msg/Reading",
+///     "uint16 id\nfloat32[] values",
+/// )
+/// .expect("The message definition was invalid");
+/// let mut builder = dynamic_message
+///     .record_batch_builder()
+///     .expect("The message contains itself");
+///
+/// for bytes in [
+///     [0x00, 0x01, 0, 0, 7, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0x80, 0x3f],
+///     [0x00, 0x01, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0x00, 0x40],
+/// ] {
+///     let message = dynamic_message.decode(&bytes[..]).expect("The bytes are invalid");
+///     builder.append(&message).expect("The message does not match the schema");
+/// }
+///
+/// let batch = builder.finish().expect("The columns do not match the schema");
+/// assert_eq!(batch.num_rows(), 2);
+/// assert_eq!(batch.schema().field(0).name(), "id");
+/// assert!(builder.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct RecordBatchBuilder {
+    fields: Fields,
+    columns: Column,
+    rows: usize,
+}
+
+impl RecordBatchBuilder {
+    /// Returns the schema of the record batches.
+    pub fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(self.fields.clone()))
+    }
+
+    /// Returns the number of messages appended since the last batch was finished.
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns true if no messages were appended since the last batch was finished.
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Appends a message as a row. Fields that are not part of the schema, like constants,
+    /// are ignored.
+    ///
+    /// The message is checked completely before it is appended, so the builder is unchanged
+    /// if this fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::ArrowConversion] naming the field whose value does not match the schema.
+    pub fn append<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &mut self,
+        message: &MessageValue<S>,
+    ) -> Result<()> {
+        let Column::Struct(columns) = &mut self.columns else {
+            unreachable!("messages are built as structs");
+        };
+        let mut path = String::new();
+        columns.append_fields(message, false, &mut path)?;
+        columns.append_fields(message, true, &mut path)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Returns all appended messages as a record batch and resets the builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::ArrowConversion] if Arrow rejects the columns.
+    pub fn finish(&mut self) -> Result<RecordBatch> {
+        let Column::Struct(columns) = &mut self.columns else {
+            unreachable!("messages are built as structs");
+        };
+        let arrays = columns
+            .columns
+            .iter_mut()
+            .map(|(_, column)| column.finish())
+            .collect::<Result<_>>()?;
+        columns.len = 0;
+        let rows = std::mem::take(&mut self.rows);
+
+        RecordBatch::try_new_with_options(
+            self.schema(),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(rows)),
+        )
+        .map_err(arrow_error)
+    }
+}
+
+/// Creates the columns of a message and its dependencies
+struct ColumnBuilder<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    /// The messages that are being built, to detect messages that contain themselves
+    stack: Vec<MessagePath>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ColumnBuilder<'_, S> {
+    fn message(&mut self, msg: &Msg<S>) -> Result<Column> {
+        let columns = msg
+            .fields()
+            .iter()
+            .filter(|field| !field.is_constant())
+            .map(|field| {
+                let element = self.element(msg.path(), field.datatype())?;
+                let column = match field.case() {
+                    FieldCase::Unit | FieldCase::Default(_) | FieldCase::Const(_) => element,
+                    FieldCase::Vector | FieldCase::BoundedVector(_) => Column::List {
+                        item: element.item_field(),
+                        offsets: OffsetBufferBuilder::new(0),
+                        total: 0,
+                        values: Box::new(element),
+                    },
+                    FieldCase::Array(size) => Column::FixedSizeList {
+                        item: element.item_field(),
+                        size: *size,
+                        values: Box::new(element),
+                    },
+                };
+                Ok((field.name().to_owned(), column))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Column::Struct(StructColumn {
+            columns,
+            time: BuiltinTime::of(msg.path()),
+            len: 0,
+        }))
+    }
+
+    fn element(&mut self, parent: &MessagePath, datatype: &DataType) -> Result<Column> {
+        Ok(Column::Leaf(match datatype {
+            DataType::Bool => Leaf::Bool(BooleanBuilder::new()),
+            DataType::I8(_) => Leaf::I8(Int8Builder::new()),
+            DataType::I16 => Leaf::I16(Int16Builder::new()),
+            DataType::I32 => Leaf::I32(Int32Builder::new()),
+            DataType::I64 => Leaf::I64(Int64Builder::new()),
+            DataType::U8(_) => Leaf::U8(UInt8Builder::new()),
+            DataType::U16 | DataType::WChar => Leaf::U16(UInt16Builder::new()),
+            DataType::U32 => Leaf::U32(UInt32Builder::new()),
+            DataType::U64 => Leaf::U64(UInt64Builder::new()),
+            DataType::F32 => Leaf::F32(Float32Builder::new()),
+            DataType::F64 => Leaf::F64(Float64Builder::new()),
+            DataType::String
+            | DataType::BoundedString(_)
+            | DataType::WString
+            | DataType::BoundedWString(_) => Leaf::String(StringBuilder::new()),
+            DataType::Time => return Ok(time_column(BuiltinTime::Time)),
+            DataType::Duration => return Ok(time_column(BuiltinTime::Duration)),
+            DataType::LocalMessage(name) => return self.nested(&parent.peer(name)),
+            DataType::GlobalMessage(path) => return self.nested(path),
+        }))
+    }
+
+    fn nested(&mut self, path: &MessagePath) -> Result<Column> {
+        if let Some(position) = self.stack.iter().position(|p| p == path) {
+            let mut cycle = self.stack[position..].to_vec();
+            cycle.push(path.clone());
+            return Err(Error::CyclicDependency { cycle });
+        }
+
+        let msg = self.msg.get_dependency(path)?;
+        self.stack.push(path.clone());
+        let column = self.message(msg);
+        self.stack.pop();
+        column
+    }
+}
+
+fn time_column(time: BuiltinTime) -> Column {
+    let leaf = || match time {
+        BuiltinTime::Time => Column::Leaf(Leaf::U32(UInt32Builder::new())),
+        _ => Column::Leaf(Leaf::I32(Int32Builder::new())),
+    };
+    Column::Struct(StructColumn {
+        columns: vec![("sec".to_owned(), leaf()), ("nanosec".to_owned(), leaf())],
+        time: Some(time),
+        len: 0,
+    })
+}
+
+/// Values that can stand in for the fields of a struct
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BuiltinTime {
+    /// `time`, with unsigned `sec` and `nanosec`
+    Time,
+    /// `duration`, with signed `sec` and `nanosec`
+    Duration,
+    /// `builtin_interfaces/Time`, with a signed `sec` and an unsigned `nanosec`
+    MessageTime,
+    /// `builtin_interfaces/Duration`, with a signed `sec` and an unsigned `nanosec`
+    MessageDuration,
+}
+
+impl BuiltinTime {
+    fn of(path: &MessagePath) -> Option<Self> {
+        match (path.package(), path.name()) {
+            ("builtin_interfaces", "Time") => Some(BuiltinTime::MessageTime),
+            ("builtin_interfaces", "Duration") => Some(BuiltinTime::MessageDuration),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of a struct with this layout, if the value is of the matching type
+    fn fields<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        self,
+        value: &Value<S>,
+    ) -> Option<MessageValue<S>> {
+        let (sec, nanosec) = match (self, value) {
+            (BuiltinTime::Time, Value::Time(t)) => (Value::U32(t.sec), Value::U32(t.nsec)),
+            (BuiltinTime::Duration, Value::Duration(d)) => (Value::I32(d.sec), Value::I32(d.nsec)),
+            (BuiltinTime::MessageTime, Value::Time(_))
+            | (BuiltinTime::MessageDuration, Value::Duration(_)) => value.builtin_time_fields()?,
+            _ => return None,
+        };
+
+        let mut fields = MessageValue::with_capacity_and_hasher(2, S::default());
+        fields.insert("sec".to_owned(), sec);
+        fields.insert("nanosec".to_owned(), nanosec);
+        Some(fields)
+    }
+}
+
+/// The builder of a column, or of the values of a list
+#[derive(Debug)]
+enum Column {
+    Leaf(Leaf),
+    Struct(StructColumn),
+    List {
+        item: FieldRef,
+        offsets: OffsetBufferBuilder<i32>,
+        /// The number of elements of all lists, which `i32` offsets have to address
+        total: usize,
+        values: Box<Column>,
+    },
+    FixedSizeList {
+        item: FieldRef,
+        size: usize,
+        values: Box<Column>,
+    },
+}
+
+#[derive(Debug)]
+struct StructColumn {
+    columns: Vec<(String, Column)>,
+    /// Set if the struct can also be built from a [Value::Time] or [Value::Duration]
+    time: Option<BuiltinTime>,
+    /// The number of structs, which is needed for messages without fields
+    len: usize,
+}
+
+impl Column {
+    fn data_type(&self) -> ArrowType {
+        match self {
+            Column::Leaf(leaf) => leaf.data_type(),
+            Column::Struct(columns) => ArrowType::Struct(columns.fields()),
+            Column::List { item, .. } => ArrowType::List(item.clone()),
+            Column::FixedSizeList { item, size, .. } => {
+                ArrowType::FixedSizeList(item.clone(), *size as i32)
+            }
+        }
+    }
+
+    fn fields(&self) -> Fields {
+        match self {
+            Column::Struct(columns) => columns.fields(),
+            _ => unreachable!("only structs have fields"),
+        }
+    }
+
+    /// Returns the field of list elements built by this column
+    fn item_field(&self) -> FieldRef {
+        Arc::new(Field::new_list_field(self.data_type(), false))
+    }
+
+    /// Appends the value, or only checks that it matches the column if `apply` is false
+    fn append<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &mut self,
+        value: &Value<S>,
+        apply: bool,
+        path: &mut String,
+    ) -> Result<()> {
+        let mismatch = |path: &str, column: &Column| {
+            conversion_error(
+                path,
+                format!("expected a value of type `{}`", column.data_type()),
+            )
+        };
+
+        match self {
+            Column::Leaf(leaf) => {
+                if !leaf.append(value, apply) {
+                    return Err(mismatch(path, self));
+                }
+            }
+            Column::Struct(columns) => {
+                let time_fields = columns.time.and_then(|time| time.fields(value));
+                let fields = match (value, &time_fields) {
+                    (_, Some(fields)) => fields,
+                    (Value::Message(fields), None) => fields,
+                    _ => return Err(mismatch(path, self)),
+                };
+                columns.append_fields(fields, apply, path)?;
+            }
+            Column::List {
+                offsets,
+                total,
+                values,
+                ..
+            } => {
+                let len = values.append_all(value, apply, path)?;
+                if *total + len > i32::MAX as usize {
+                    return Err(conversion_error(
+                        path,
+                        "the lists hold more elements than a batch can address",
+                    ));
+                }
+                if apply {
+                    offsets.push_length(len);
+                    *total += len;
+                }
+            }
+            Column::FixedSizeList { size, values, .. } => {
+                let size = *size;
+                let len = values.append_all(value, apply, path)?;
+                if len != size {
+                    return Err(conversion_error(
+                        path,
+                        format!("expected an array of {size} elements, got {len}"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the elements of an array, returns the number of elements
+    fn append_all<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &mut self,
+        value: &Value<S>,
+        apply: bool,
+        path: &mut String,
+    ) -> Result<usize> {
+        match value {
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let len = path.len();
+                    write!(path, "[{index}]").expect("Writing to a string does not fail");
+                    self.append(item, apply, path)?;
+                    path.truncate(len);
+                }
+                return Ok(items.len());
+            }
+            Value::PrimitiveArray(items) => {
+                if let Column::Leaf(leaf) = self {
+                    if leaf.append_primitive(items, apply) {
+                        return Ok(items.len());
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Err(conversion_error(
+            path,
+            format!("expected an array of `{}`", self.data_type()),
+        ))
+    }
+
+    /// Returns the appended values and resets the column
+    fn finish(&mut self) -> Result<ArrayRef> {
+        Ok(match self {
+            Column::Leaf(leaf) => leaf.finish(),
+            Column::Struct(columns) => {
+                let arrays = columns
+                    .columns
+                    .iter_mut()
+                    .map(|(_, column)| column.finish())
+                    .collect::<Result<_>>()?;
+                let len = std::mem::take(&mut columns.len);
+                Arc::new(
+                    StructArray::try_new_with_length(columns.fields(), arrays, None, len)
+                        .map_err(arrow_error)?,
+                )
+            }
+            Column::List {
+                item,
+                offsets,
+                total,
+                values,
+            } => {
+                let offsets = std::mem::replace(offsets, OffsetBufferBuilder::new(0)).finish();
+                *total = 0;
+                Arc::new(
+                    ListArray::try_new(item.clone(), offsets, values.finish()?, None)
+                        .map_err(arrow_error)?,
+                )
+            }
+            Column::FixedSizeList { item, size, values } => {
+                let values = values.finish()?;
+                let len = values.len().checked_div(*size).unwrap_or_default();
+                Arc::new(
+                    FixedSizeListArray::try_new_with_length(
+                        item.clone(),
+                        *size as i32,
+                        values,
+                        None,
+                        len,
+                    )
+                    .map_err(arrow_error)?,
+                )
+            }
+        })
+    }
+}
+
+impl StructColumn {
+    fn fields(&self) -> Fields {
+        self.columns
+            .iter()
+            .map(|(name, column)| Field::new(name, column.data_type(), false))
+            .collect()
+    }
+
+    fn append_fields<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &mut self,
+        fields: &MessageValue<S>,
+        apply: bool,
+        path: &mut String,
+    ) -> Result<()> {
+        for (name, column) in &mut self.columns {
+            let len = path.len();
+            if len > 0 {
+                path.push('.');
+            }
+            path.push_str(name);
+            let Some(value) = fields.get(name.as_str()) else {
+                return Err(conversion_error(path, "the field is missing"));
+            };
+            column.append(value, apply, path)?;
+            path.truncate(len);
+        }
+        if apply {
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Defines the builders of numbers, `bool` and strings together with their values
+macro_rules! leaves {
+    ($($variant:ident($builder:ty) = $arrow:ident $(, $primitive:ident)?, |$v:ident| $arg:expr;)*) => {
+        #[derive(Debug)]
+        enum Leaf {
+            $($variant($builder),)*
+        }
+
+        impl Leaf {
+            fn data_type(&self) -> ArrowType {
+                match self {
+                    $(Leaf::$variant(_) => ArrowType::$arrow,)*
+                }
+            }
+
+            /// Returns false if the value is of a different type
+            fn append<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+                &mut self,
+                value: &Value<S>,
+                apply: bool,
+            ) -> bool {
+                match (self, value) {
+                    $((Leaf::$variant(builder), Value::$variant($v)) => {
+                        if apply {
+                            builder.append_value($arg);
+                        }
+                    })*
+                    _ => return false,
+                }
+                true
+            }
+
+            /// Returns false if the array is of a different type
+            fn append_primitive(&mut self, items: &PrimitiveArray, apply: bool) -> bool {
+                match (self, items) {
+                    $($((Leaf::$variant(builder), PrimitiveArray::$primitive(items)) => {
+                        if apply {
+                            builder.append_slice(items);
+                        }
+                    })?)*
+                    _ => return false,
+                }
+                true
+            }
+
+            fn finish(&mut self) -> ArrayRef {
+                match self {
+                    $(Leaf::$variant(builder) => Arc::new(builder.finish()),)*
+                }
+            }
+        }
+    };
+}
+
+leaves! {
+    Bool(BooleanBuilder) = Boolean, Bool, |v| *v;
+    I8(Int8Builder) = Int8, I8, |v| *v;
+    I16(Int16Builder) = Int16, I16, |v| *v;
+    I32(Int32Builder) = Int32, I32, |v| *v;
+    I64(Int64Builder) = Int64, I64, |v| *v;
+    U8(UInt8Builder) = UInt8, U8, |v| *v;
+    U16(UInt16Builder) = UInt16, U16, |v| *v;
+    U32(UInt32Builder) = UInt32, U32, |v| *v;
+    U64(UInt64Builder) = UInt64, U64, |v| *v;
+    F32(Float32Builder) = Float32, F32, |v| *v;
+    F64(Float64Builder) = Float64, F64, |v| *v;
+    String(StringBuilder) = Utf8, |v| v;
+}
+
+fn conversion_error(path: &str, reason: impl Into<String>) -> Error {
+    Error::ArrowConversion {
+        path: path.to_owned(),
+        reason: reason.into(),
+    }
+}
+
+fn arrow_error(e: arrow_schema::ArrowError) -> Error {
+    conversion_error("", e.to_string())
+}
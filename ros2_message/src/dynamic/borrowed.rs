@@ -0,0 +1,474 @@
+use super::interpreter::Decoded;
+use super::plan::Element;
+use super::reader::{is_native, to_native, CdrRead, Number, Scalar};
+use super::{DecodeOptions, DynamicMsg, Encapsulation, Endianness};
+use crate::error::Result;
+use crate::{Duration, PrimitiveArray, Time, Value};
+use derive_where::derive_where;
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::hash::{BuildHasher, RandomState};
+use std::io;
+
+/// Represents an arbitrary ROS message decoded by [DynamicMsg::decode_borrowed()].
+pub type MessageValueRef<'a, S> = IndexMap<&'a str, ValueRef<'a, S>, S>;
+
+/// Represents an arbitrary ROS message or value in it that borrows its strings and arrays from
+/// the decoded bytes wherever possible, see [DynamicMsg::decode_borrowed()].
+#[derive_where(Clone, PartialEq, Debug)]
+pub enum ValueRef<'a, S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
+    /// Represents `bool`.
+    Bool(bool),
+    /// Represents `int8` or `byte`.
+    I8(i8),
+    /// Represents `int16`.
+    I16(i16),
+    /// Represents `int32`.
+    I32(i32),
+    /// Represents `int64`.
+    I64(i64),
+    /// Represents `uint8` or `char`.
+    U8(u8),
+    /// Represents `uint16`.
+    U16(u16),
+    /// Represents `uint32`.
+    U32(u32),
+    /// Represents `uint64`.
+    U64(u64),
+    /// Represents `float32`.
+    F32(f32),
+    /// Represents `float64`.
+    F64(f64),
+    /// Represents `string`, only `wstring` needs to be converted to an owned string.
+    String(Cow<'a, str>),
+    /// Represents `time`.
+    Time(Time),
+    /// Represents `duration`.
+    Duration(Duration),
+    /// Represents an array of a non primitive type, e.g. `string[]` or `geometry_msgs/Point[]`.
+    Array(Vec<ValueRef<'a, S>>),
+    /// Represents an array of numbers or `bool`, e.g. `uint8[]` or `float32[3]`.
+    PrimitiveArray(PrimitiveArrayRef<'a>),
+    /// Represents an embedded message.
+    Message(MessageValueRef<'a, S>),
+}
+
+/// An array of a primitive type that borrows from the decoded bytes if possible.
+///
+/// Arrays of bytes are always borrowed. Arrays of larger numbers are only borrowed if the
+/// byte order of the message matches the one of the machine and the data happens to be aligned
+/// in memory, otherwise they are copied.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PrimitiveArrayRef<'a> {
+    /// Represents `bool[]`, which is always copied since the bytes are not guaranteed to be
+    /// valid `bool`s.
+    Bool(Vec<bool>),
+    /// Represents `int8[]` or `byte[]`.
+    I8(&'a [i8]),
+    /// Represents `int16[]`.
+    I16(Cow<'a, [i16]>),
+    /// Represents `int32[]`.
+    I32(Cow<'a, [i32]>),
+    /// Represents `int64[]`.
+    I64(Cow<'a, [i64]>),
+    /// Represents `uint8[]` or `char[]`.
+    U8(&'a [u8]),
+    /// Represents `uint16[]` or `wchar[]`.
+    U16(Cow<'a, [u16]>),
+    /// Represents `uint32[]`.
+    U32(Cow<'a, [u32]>),
+    /// Represents `uint64[]`.
+    U64(Cow<'a, [u64]>),
+    /// Represents `float32[]`.
+    F32(Cow<'a, [f32]>),
+    /// Represents `float64[]`.
+    F64(Cow<'a, [f64]>),
+}
+
+impl PrimitiveArrayRef<'_> {
+    /// Copies the array into an owned [PrimitiveArray].
+    pub fn to_primitive_array(&self) -> PrimitiveArray {
+        match self {
+            PrimitiveArrayRef::Bool(v) => PrimitiveArray::Bool(v.clone()),
+            PrimitiveArrayRef::I8(v) => PrimitiveArray::I8(v.to_vec()),
+            PrimitiveArrayRef::I16(v) => PrimitiveArray::I16(v.to_vec()),
+            PrimitiveArrayRef::I32(v) => PrimitiveArray::I32(v.to_vec()),
+            PrimitiveArrayRef::I64(v) => PrimitiveArray::I64(v.to_vec()),
+            PrimitiveArrayRef::U8(v) => PrimitiveArray::U8(v.to_vec()),
+            PrimitiveArrayRef::U16(v) => PrimitiveArray::U16(v.to_vec()),
+            PrimitiveArrayRef::U32(v) => PrimitiveArray::U32(v.to_vec()),
+            PrimitiveArrayRef::U64(v) => PrimitiveArray::U64(v.to_vec()),
+            PrimitiveArrayRef::F32(v) => PrimitiveArray::F32(v.to_vec()),
+            PrimitiveArrayRef::F64(v) => PrimitiveArray::F64(v.to_vec()),
+        }
+    }
+}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> ValueRef<'a, S> {
+    /// Returns the content if `ValueRef` is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        if let ValueRef::String(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `ValueRef` is an array of bytes.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        if let ValueRef::PrimitiveArray(PrimitiveArrayRef::U8(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the content if `ValueRef` is a primitive array.
+    pub fn as_primitive_array(&self) -> Option<&PrimitiveArrayRef<'a>> {
+        if let ValueRef::PrimitiveArray(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the content if `ValueRef` is an array.
+    pub fn as_slice(&self) -> Option<&[ValueRef<'a, S>]> {
+        if let ValueRef::Array(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the content if `ValueRef` is a message.
+    pub fn as_map(&self) -> Option<&MessageValueRef<'a, S>> {
+        if let ValueRef::Message(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Copies the value into an owned [Value], primitive arrays become a
+    /// [Value::PrimitiveArray].
+    pub fn to_value(&self) -> Value<S> {
+        match self {
+            ValueRef::Bool(v) => Value::Bool(*v),
+            ValueRef::I8(v) => Value::I8(*v),
+            ValueRef::I16(v) => Value::I16(*v),
+            ValueRef::I32(v) => Value::I32(*v),
+            ValueRef::I64(v) => Value::I64(*v),
+            ValueRef::U8(v) => Value::U8(*v),
+            ValueRef::U16(v) => Value::U16(*v),
+            ValueRef::U32(v) => Value::U32(*v),
+            ValueRef::U64(v) => Value::U64(*v),
+            ValueRef::F32(v) => Value::F32(*v),
+            ValueRef::F64(v) => Value::F64(*v),
+            ValueRef::String(v) => Value::String(v.clone().into_owned()),
+            ValueRef::Time(v) => Value::Time(*v),
+            ValueRef::Duration(v) => Value::Duration(*v),
+            ValueRef::Array(v) => Value::Array(v.iter().map(Self::to_value).collect()),
+            ValueRef::PrimitiveArray(v) => Value::PrimitiveArray(v.to_primitive_array()),
+            ValueRef::Message(v) => Value::Message(
+                v.iter()
+                    .map(|(k, v)| ((*k).to_owned(), v.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> From<&'a Value<S>>
+    for ValueRef<'a, S>
+{
+    fn from(value: &'a Value<S>) -> Self {
+        match value {
+            Value::Bool(v) => ValueRef::Bool(*v),
+            Value::I8(v) => ValueRef::I8(*v),
+            Value::I16(v) => ValueRef::I16(*v),
+            Value::I32(v) => ValueRef::I32(*v),
+            Value::I64(v) => ValueRef::I64(*v),
+            Value::U8(v) => ValueRef::U8(*v),
+            Value::U16(v) => ValueRef::U16(*v),
+            Value::U32(v) => ValueRef::U32(*v),
+            Value::U64(v) => ValueRef::U64(*v),
+            Value::F32(v) => ValueRef::F32(*v),
+            Value::F64(v) => ValueRef::F64(*v),
+            Value::String(v) => ValueRef::String(Cow::Borrowed(v)),
+            Value::Time(v) => ValueRef::Time(*v),
+            Value::Duration(v) => ValueRef::Duration(*v),
+            Value::Array(v) => ValueRef::Array(v.iter().map(Into::into).collect()),
+            Value::PrimitiveArray(v) => ValueRef::PrimitiveArray(match v {
+                PrimitiveArray::Bool(v) => PrimitiveArrayRef::Bool(v.clone()),
+                PrimitiveArray::I8(v) => PrimitiveArrayRef::I8(v),
+                PrimitiveArray::I16(v) => PrimitiveArrayRef::I16(Cow::Borrowed(v)),
+                PrimitiveArray::I32(v) => PrimitiveArrayRef::I32(Cow::Borrowed(v)),
+                PrimitiveArray::I64(v) => PrimitiveArrayRef::I64(Cow::Borrowed(v)),
+                PrimitiveArray::U8(v) => PrimitiveArrayRef::U8(v),
+                PrimitiveArray::U16(v) => PrimitiveArrayRef::U16(Cow::Borrowed(v)),
+                PrimitiveArray::U32(v) => PrimitiveArrayRef::U32(Cow::Borrowed(v)),
+                PrimitiveArray::U64(v) => PrimitiveArrayRef::U64(Cow::Borrowed(v)),
+                PrimitiveArray::F32(v) => PrimitiveArrayRef::F32(Cow::Borrowed(v)),
+                PrimitiveArray::F64(v) => PrimitiveArrayRef::F64(Cow::Borrowed(v)),
+            }),
+            Value::Message(v) => {
+                ValueRef::Message(v.iter().map(|(k, v)| (k.as_str(), v.into())).collect())
+            }
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<Scalar> for ValueRef<'_, S> {
+    fn from(scalar: Scalar) -> Self {
+        match scalar {
+            Scalar::Bool(v) => ValueRef::Bool(v),
+            Scalar::I8(v) => ValueRef::I8(v),
+            Scalar::I16(v) => ValueRef::I16(v),
+            Scalar::I32(v) => ValueRef::I32(v),
+            Scalar::I64(v) => ValueRef::I64(v),
+            Scalar::U8(v) => ValueRef::U8(v),
+            Scalar::U16(v) => ValueRef::U16(v),
+            Scalar::U32(v) => ValueRef::U32(v),
+            Scalar::U64(v) => ValueRef::U64(v),
+            Scalar::F32(v) => ValueRef::F32(v),
+            Scalar::F64(v) => ValueRef::F64(v),
+            Scalar::Time(v) => ValueRef::Time(v),
+            Scalar::Duration(v) => ValueRef::Duration(v),
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Decodes a message from a byte slice without copying its strings and arrays, which are
+    /// borrowed from `data` instead. Field names are borrowed from the message definition.
+    ///
+    /// This is the same as [Self::decode()] except that arrays of numbers and `bool` are always
+    /// decoded to a [ValueRef::PrimitiveArray], just like with
+    /// [DecodeOptions::primitive_arrays](super::DecodeOptions::primitive_arrays).
+    ///
+    /// # Errors
+    ///
+    /// This will error if `data` is either too long or too short, make sure it contains exactly
+    /// one message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::{DynamicMsg, ValueRef};
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/Frame", "string frame_id\nuint8[] data")
+    ///     .expect("The message definition was invalid");
+    /// let data = [0x00u8, 0x01, 0, 0, 4, 0, 0, 0, b'm', b'a', b'p', 0, 2, 0, 0, 0, 7, 9];
+    /// let message = dynamic_message.decode_borrowed(&data)
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(message["frame_id"].as_str(), Some("map"));
+    /// assert_eq!(message["data"].as_bytes(), Some(&data[16..]));
+    /// ```
+    pub fn decode_borrowed<'a>(&'a self, data: &'a [u8]) -> Result<MessageValueRef<'a, S>> {
+        let mut r = self.slice_reader(data)?;
+        let message = self.decode_values::<_, ValueRef<S>>(0, &mut r)?;
+        r.finish()?;
+
+        Ok(message)
+    }
+
+    /// Reads the encapsulation header of a payload and returns a reader for the message after it
+    pub(super) fn slice_reader<'a>(&self, data: &'a [u8]) -> Result<SliceReader<'a>> {
+        if let Some(max) = self.options().max_bytes.filter(|max| data.len() > *max) {
+            return Err(io::Error::other(format!(
+                "The message of {} bytes exceeds the limit of {max} bytes",
+                data.len()
+            ))
+            .into());
+        }
+        let Some(header) = data.first_chunk::<4>() else {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        };
+        // https://github.com/foxglove/cdr/blob/main/src/EncapsulationKind.ts
+        let Some(encapsulation) = Encapsulation::from_header(*header) else {
+            return Err(self.unsupported_encapsulation(*header, 4));
+        };
+        Ok(SliceReader {
+            data,
+            pos: 4,
+            encapsulation,
+            depth: 0,
+        })
+    }
+}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> Decoded<'a, S, SliceReader<'a>>
+    for ValueRef<'a, S>
+{
+    type Fields = MessageValueRef<'a, S>;
+
+    fn new_message(fields: usize) -> Self::Fields {
+        MessageValueRef::with_capacity_and_hasher(fields, Default::default())
+    }
+
+    fn insert(message: &mut Self::Fields, name: &'a str, value: Self) {
+        message.insert(name, value);
+    }
+
+    fn message(message: Self::Fields) -> Self {
+        ValueRef::Message(message)
+    }
+
+    fn time_fields(sec: &Self, nanosec: &Self) -> Option<(i32, u32)> {
+        match (sec, nanosec) {
+            (ValueRef::I32(sec), ValueRef::U32(nanosec)) => Some((*sec, *nanosec)),
+            _ => None,
+        }
+    }
+
+    fn string(string: Cow<'a, str>) -> Self {
+        ValueRef::String(string)
+    }
+
+    fn array(elements: Vec<Self>) -> Self {
+        ValueRef::Array(elements)
+    }
+
+    fn constant(value: &'a Value<S>) -> Self {
+        value.into()
+    }
+
+    // Primitive arrays are always borrowed, they are as cheap as the slice they borrow
+    fn primitive_array(
+        r: &mut SliceReader<'a>,
+        element: &Element,
+        length: usize,
+        _options: &DecodeOptions,
+    ) -> Result<Option<Self>> {
+        let array = match element {
+            Element::Bool => {
+                PrimitiveArrayRef::Bool(r.take(length)?.iter().map(|b| *b != 0).collect())
+            }
+            Element::I8 => PrimitiveArrayRef::I8(bytemuck::cast_slice(r.take(length)?)),
+            Element::U8 => PrimitiveArrayRef::U8(r.take(length)?),
+            Element::I16 => PrimitiveArrayRef::I16(r.read_numbers(length)?),
+            Element::I32 => PrimitiveArrayRef::I32(r.read_numbers(length)?),
+            Element::I64 => PrimitiveArrayRef::I64(r.read_numbers(length)?),
+            Element::U16 | Element::WChar => PrimitiveArrayRef::U16(r.read_numbers(length)?),
+            Element::U32 => PrimitiveArrayRef::U32(r.read_numbers(length)?),
+            Element::U64 => PrimitiveArrayRef::U64(r.read_numbers(length)?),
+            Element::F32 => PrimitiveArrayRef::F32(r.read_numbers(length)?),
+            Element::F64 => PrimitiveArrayRef::F64(r.read_numbers(length)?),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(ValueRef::PrimitiveArray(array)))
+    }
+}
+
+/// Counterpart of the decoders `ByteCounter` for byte slices, alignment is relative to the end
+/// of the encapsulation header.
+pub(super) struct SliceReader<'a> {
+    pub(super) data: &'a [u8],
+    pub(super) pos: usize,
+    pub(super) encapsulation: Encapsulation,
+    /// Nesting depth of the message that is being decoded
+    pub(super) depth: usize,
+}
+
+macro_rules! read_with_endianness {
+    ($($name:ident -> $ty:ty),* $(,)?) => {
+        $(
+            fn $name(&mut self) -> io::Result<$ty> {
+                let bytes = self.take(size_of::<$ty>())?.try_into().unwrap();
+                Ok(match self.encapsulation.endianness() {
+                    Endianness::Little => <$ty>::from_le_bytes(bytes),
+                    Endianness::Big => <$ty>::from_be_bytes(bytes),
+                })
+            }
+        )*
+    };
+}
+
+impl<'a> SliceReader<'a> {
+    /// Borrows the next `size` bytes
+    pub(super) fn take(&mut self, size: usize) -> io::Result<&'a [u8]> {
+        if size > self.data.len() - self.pos {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let bytes = &self.data[self.pos..self.pos + size];
+        self.pos += size;
+        Ok(bytes)
+    }
+
+    /// Checks that only padding follows the end of the message
+    pub(super) fn finish(&mut self) -> io::Result<()> {
+        let _ = self.align_to(4);
+        if self.data[self.pos..].iter().any(|byte| *byte != 0) {
+            return Err(io::Error::other(format!(
+                "Encountered {} unexpected bytes after the end of the message",
+                self.data.len() - self.pos
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> CdrRead<'a> for SliceReader<'a> {
+    fn encapsulation(&self) -> Encapsulation {
+        self.encapsulation
+    }
+
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.data.len() - self.pos)
+    }
+
+    fn depth_mut(&mut self) -> &mut usize {
+        &mut self.depth
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    read_with_endianness!(
+        read_u16 -> u16,
+        read_u32 -> u32,
+        read_u64 -> u64,
+    );
+
+    fn read_bytes(&mut self, length: usize) -> io::Result<Cow<'a, [u8]>> {
+        self.take(length).map(Cow::Borrowed)
+    }
+
+    /// Borrows `length` numbers if their byte order is native and they are aligned in memory,
+    /// otherwise they are copied
+    fn read_numbers<T: Number>(&mut self, length: usize) -> io::Result<Cow<'a, [T]>> {
+        let size = length
+            .checked_mul(size_of::<T>())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        let bytes = self.take(size)?;
+
+        let endianness = self.encapsulation.endianness();
+        if is_native(endianness) {
+            if let Ok(values) = bytemuck::try_cast_slice(bytes) {
+                return Ok(Cow::Borrowed(values));
+            }
+        }
+
+        let mut values = vec![T::zeroed(); length];
+        bytemuck::cast_slice_mut(&mut values).copy_from_slice(bytes);
+        to_native(&mut values, endianness);
+        Ok(Cow::Owned(values))
+    }
+
+    fn skip(&mut self, size: usize) -> io::Result<()> {
+        self.take(size)?;
+        Ok(())
+    }
+
+    fn unread_u32(&mut self, _: u32) {
+        self.pos -= 4;
+    }
+}
@@ -0,0 +1,451 @@
+use super::borrowed::SliceReader;
+use super::decode::{check_empty_elements, element_error, member_error, missing_dependency};
+use super::encapsulation::Extensibility;
+use super::plan::{Element, FieldPlan, Layout, MessagePlan};
+use super::reader::{CdrRead, Scalar};
+use super::DynamicMsg;
+use crate::error::{Error, Result};
+use serde::de::value::MapDeserializer;
+use serde::de::{
+    self, DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize};
+use std::borrow::Cow;
+use std::hash::BuildHasher;
+use std::io;
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Deserializes a message directly from CDR bytes into any type implementing
+    /// [Deserialize], without decoding it to [Value](crate::Value)s first.
+    ///
+    /// Messages are deserialized as maps from field names to values, so structs with fields
+    /// named like the fields of the message definition can be derived. Fields are matched by
+    /// name, fields missing from the struct are skipped and constants are left out.
+    /// Arrays and sequences are deserialized as sequences, or as bytes for `uint8[]` and
+    /// `byte[]` if the type asks for them. Strings and bytes can be borrowed from `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::DecodingError] with the path of the field that could not be decoded or
+    /// did not match the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::DynamicMsg;
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Frame<'a> {
+    ///     frame_id: &'a str,
+    ///     data: &'a [u8],
+    ///     scale: f64,
+    /// }
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new(
+    ///     "package/msg/Frame",
+    ///     "string frame_id\nuint8[] data\nfloat64 scale",
+    /// )
+    /// .expect("The message definition was invalid");
+    /// let bytes = [
+    ///     0x00u8, 0x01, 0, 0, 4, 0, 0, 0, b'm', b'a', b'p', 0, 2, 0, 0, 0, 7, 9, 0, 0,
+    ///     0, 0, 0, 0, 0, 0, 0xf0, 0x3f,
+    /// ];
+    ///
+    /// let frame: Frame = dynamic_message.deserialize(&bytes)
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(frame.frame_id, "map");
+    /// assert_eq!(frame.data, [7, 9]);
+    /// assert_eq!(frame.scale, 1.0);
+    /// ```
+    pub fn deserialize<'de, T: Deserialize<'de>>(&self, data: &'de [u8]) -> Result<T> {
+        let mut r = self.slice_reader(data)?;
+        let value = T::deserialize(MessageDeserializer {
+            msg: self,
+            index: 0,
+            r: &mut r,
+        })
+        .map_err(|e| match decoding_error(e) {
+            // Errors of the message itself, like missing fields, are raised after reading it
+            Error::DecodingError {
+                path,
+                datatype,
+                encapsulation: None,
+                err,
+                ..
+            } => Error::DecodingError {
+                path,
+                datatype,
+                offset: r.pos,
+                encapsulation: Some(r.encapsulation),
+                err,
+            },
+            e => e,
+        })?;
+        r.finish()?;
+
+        Ok(value)
+    }
+}
+
+/// Deserializes a message as a map of its fields
+struct MessageDeserializer<'a, 'de, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    /// Index of the message inside the decode plan
+    index: usize,
+    r: &'a mut SliceReader<'de>,
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> de::Deserializer<'de>
+    for MessageDeserializer<'_, 'de, S>
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let r = self.r;
+        r.enter_message(self.msg.options().max_depth)?;
+
+        let extensibility = r.encapsulation.extensibility();
+        let end = match extensibility {
+            Extensibility::Final => None,
+            Extensibility::Appendable | Extensibility::Mutable => Some(r.read_dheader()?),
+        };
+        let mut fields = MessageFields {
+            msg: self.msg,
+            message: &self.msg.plan().messages[self.index],
+            fields: self.msg.plan().fields(self.index),
+            r: &mut *r,
+            extensibility,
+            end,
+            next: 0,
+            current: None,
+        };
+        let value = visitor.visit_map(&mut fields)?;
+        fields.finish()?;
+        r.exit_message();
+
+        Ok(value)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+/// Fields of a message in the order they are serialized
+struct MessageFields<'a, 'de, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    message: &'a MessagePlan,
+    /// Plans of the fields of the message
+    fields: &'a [FieldPlan<S>],
+    r: &'a mut SliceReader<'de>,
+    extensibility: Extensibility,
+    /// End of the delimited message for appendable and mutable messages
+    end: Option<usize>,
+    /// Index of the next field of final and appendable messages
+    next: usize,
+    /// The field whose key was returned last, along with the end of its member for mutable
+    /// messages
+    current: Option<(usize, Option<usize>)>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> MessageFields<'_, '_, S> {
+    /// Skips the fields the visitor did not read, as well as appended unknown members
+    fn finish(&mut self) -> Result<()> {
+        while self.next_key::<IgnoredAny>()?.is_some() {
+            self.next_value::<IgnoredAny>()?;
+        }
+        if let Some(end) = self.end {
+            self.r.skip_to(end)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> MapAccess<'de>
+    for MessageFields<'_, 'de, S>
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let field_index = match (self.extensibility, self.end) {
+            // Members of mutable messages are serialized in any order
+            (Extensibility::Mutable, Some(end)) => loop {
+                if self.r.pos >= end {
+                    return Ok(None);
+                }
+                let (member_id, member_end) = self.r.read_emheader()?;
+                // Members unknown to this definition are skipped
+                let Some(&field_index) = self.message.members.get(member_id) else {
+                    self.r.skip_to(member_end)?;
+                    continue;
+                };
+                self.current = Some((field_index, Some(member_end)));
+                break field_index;
+            },
+            _ => {
+                let fields = self.fields;
+                while self.next < fields.len() && fields[self.next].layout == Layout::Constant {
+                    self.next += 1;
+                }
+                if self.next == fields.len() {
+                    return Ok(None);
+                }
+                self.next += 1;
+                self.current = Some((self.next - 1, None));
+                self.next - 1
+            }
+        };
+
+        let name = self.fields[field_index].name.as_str();
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let Some((field_index, member_end)) = self.current.take() else {
+            return Err(de::Error::custom("a value was requested before its key"));
+        };
+
+        let r = &mut *self.r;
+        seed.deserialize(FieldDeserializer {
+            msg: self.msg,
+            field: &self.fields[field_index],
+            r: &mut *r,
+        })
+        .map_err(decoding_error)
+        .and_then(|value| {
+            // A member that is shorter than announced can not corrupt the following ones
+            if let Some(member_end) = member_end {
+                r.skip_to(member_end)?;
+            }
+            Ok(value)
+        })
+        .map_err(|e| member_error(&self.fields[field_index], r.pos, r.encapsulation, e))
+    }
+}
+
+/// Deserializes the value of a field, which may be an array of elements
+struct FieldDeserializer<'a, 'de, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    field: &'a FieldPlan<S>,
+    r: &'a mut SliceReader<'de>,
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> FieldDeserializer<'_, 'de, S> {
+    fn array_length(&mut self) -> Result<Option<usize>> {
+        let array_length = match self.field.layout {
+            Layout::Array(length) => Some(length),
+            Layout::Sequence(_) => None,
+            Layout::Unit | Layout::Constant => return Ok(None),
+        };
+        self.msg
+            .read_array_header(self.field, array_length, self.r)
+            .map(Some)
+    }
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> de::Deserializer<'de>
+    for FieldDeserializer<'_, 'de, S>
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        let Some(length) = self.array_length()? else {
+            return ElementDeserializer {
+                msg: self.msg,
+                element: &self.field.element,
+                alignment: self.field.alignment,
+                r: self.r,
+            }
+            .deserialize_any(visitor);
+        };
+
+        let mut elements = Elements {
+            msg: self.msg,
+            element: &self.field.element,
+            r: self.r,
+            index: 0,
+            length,
+        };
+        let value = visitor.visit_seq(&mut elements)?;
+        if elements.index < length {
+            return Err(de::Error::invalid_length(
+                length,
+                &"fewer elements in the array",
+            ));
+        }
+
+        Ok(value)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        if !matches!(self.field.element, Element::U8 | Element::I8)
+            || self.field.layout == Layout::Unit
+        {
+            return self.deserialize_any(visitor);
+        }
+        let length = self.array_length()?.unwrap_or_default();
+        visitor.visit_borrowed_bytes(self.r.take(length)?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Elements of an array or sequence
+struct Elements<'a, 'de, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    element: &'a Element,
+    r: &'a mut SliceReader<'de>,
+    /// Index of the next element
+    index: usize,
+    length: usize,
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> SeqAccess<'de>
+    for Elements<'_, 'de, S>
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.index == self.length {
+            return Ok(None);
+        }
+        let index = self.index;
+        self.index += 1;
+
+        // Elements are aligned once in front of the array
+        let start = self.r.pos;
+        let value = seed
+            .deserialize(ElementDeserializer {
+                msg: self.msg,
+                element: self.element,
+                alignment: 0,
+                r: &mut *self.r,
+            })
+            .map_err(|e| element_error(index, decoding_error(e)))?;
+        if index == 0 && self.r.pos == start {
+            check_empty_elements(self.length, self.r.remaining())?;
+        }
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.length - self.index)
+    }
+}
+
+/// Deserializes a single value, see DynamicMsg::decode_element
+struct ElementDeserializer<'a, 'de, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    element: &'a Element,
+    /// Alignment of primitive values, zero inside arrays
+    alignment: usize,
+    r: &'a mut SliceReader<'de>,
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> de::Deserializer<'de>
+    for ElementDeserializer<'_, 'de, S>
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let r = self.r;
+        if let Some(scalar) = r.read_scalar(self.element, self.alignment)? {
+            return match scalar {
+                Scalar::Bool(v) => visitor.visit_bool(v),
+                Scalar::I8(v) => visitor.visit_i8(v),
+                Scalar::I16(v) => visitor.visit_i16(v),
+                Scalar::I32(v) => visitor.visit_i32(v),
+                Scalar::I64(v) => visitor.visit_i64(v),
+                Scalar::U8(v) => visitor.visit_u8(v),
+                Scalar::U16(v) => visitor.visit_u16(v),
+                Scalar::U32(v) => visitor.visit_u32(v),
+                Scalar::U64(v) => visitor.visit_u64(v),
+                Scalar::F32(v) => visitor.visit_f32(v),
+                Scalar::F64(v) => visitor.visit_f64(v),
+                Scalar::Time(v) => visitor.visit_map(MapDeserializer::new(
+                    [("sec", v.sec), ("nsec", v.nsec)].into_iter(),
+                )),
+                Scalar::Duration(v) => visitor.visit_map(MapDeserializer::new(
+                    [("sec", v.sec), ("nsec", v.nsec)].into_iter(),
+                )),
+            };
+        }
+
+        match self.element {
+            Element::String(bound) => match self.msg.read_string(*bound, r)? {
+                Cow::Borrowed(string) => visitor.visit_borrowed_str(string),
+                Cow::Owned(string) => visitor.visit_string(string),
+            },
+            Element::WString(bound) => visitor.visit_string(self.msg.read_wstring(*bound, r)?),
+            Element::Message(index) => MessageDeserializer {
+                msg: self.msg,
+                index: *index,
+                r,
+            }
+            .deserialize_any(visitor),
+            Element::Missing(path) => Err(missing_dependency(path)),
+            _ => unreachable!("Scalars are read above"),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+/// Turns errors raised by visitors into decoding errors, so they are attributed to the field
+fn decoding_error(e: Error) -> Error {
+    match e {
+        Error::Deserialization(msg) => io::Error::other(msg).into(),
+        e => e,
+    }
+}
@@ -1,16 +1,25 @@
+use super::interpreter::Decoded;
+use super::plan::{DecodePlan, Element, FieldPlan, Layout};
+use super::reader::{to_native, CdrRead, Number, Scalar};
+use super::{DecodeOptions, Encapsulation, Endianness};
 use crate::error::{Error, Result};
-use crate::{DataType, FieldCase, FieldInfo, MessagePath, Msg, Value};
-use byteorder::{ReadBytesExt, LE};
+use crate::{DataType, MessagePath, MessageValue, Msg, PrimitiveArray, Value};
+use byteorder::{BE, LE};
 use lazy_static::lazy_static;
 use regex::RegexBuilder;
 // use rustc_hash::FxHashMap;
+use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
 use std::hash::{BuildHasher, RandomState};
 use std::io::{self, Read};
+use std::sync::Arc;
 
 pub(crate) type MessageValues<S> = VecDeque<Value<S>>;
 
+/// The source of the main message of a definition along with its parsed dependencies
+pub(super) type SplitDefinition<'a, S> = (&'a str, HashMap<MessagePath, Msg<S>, S>);
+
 // Most of this code is copied from
 // https://github.com/adnanademovic/rosrust/blob/master/rosrust/src/dynamic_msg.rs
 
@@ -21,6 +30,8 @@ pub struct DynamicMsg<S: BuildHasher + Default + Clone + core::fmt::Debug = Rand
     // = RandomState> {
     msg: Msg<S>,
     dependencies: HashMap<MessagePath, Msg<S>, S>,
+    plan: Arc<DecodePlan<S>>,
+    options: DecodeOptions,
 }
 
 impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
@@ -46,6 +57,27 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
     /// assert!(dynamic_message.is_ok());
     /// ```
     pub fn new(message_name: &str, message_definition: &str) -> Result<Self> {
+        let (message_src, dependencies) = Self::split_definition(message_name, message_definition)?;
+        let msg = Self::parse_msg(message_name, message_src)?;
+
+        Ok(Self::from_parts(msg, dependencies))
+    }
+
+    pub(super) fn from_parts(msg: Msg<S>, dependencies: HashMap<MessagePath, Msg<S>, S>) -> Self {
+        DynamicMsg {
+            plan: Arc::new(DecodePlan::compile(&msg, &dependencies)),
+            msg,
+            dependencies,
+            options: DecodeOptions::default(),
+        }
+    }
+
+    /// Splits a full message definition into the source of the main message and its parsed
+    /// dependencies.
+    pub(super) fn split_definition<'a>(
+        message_name: &str,
+        message_definition: &'a str,
+    ) -> Result<SplitDefinition<'a, S>> {
         lazy_static! {
             static ref RE_DESCRIPTOR_MESSAGES_SPLITTER: regex::Regex = RegexBuilder::new("^=+$")
                 .multi_line(true)
@@ -59,14 +91,49 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
                 "Message definition for {} is missing main message body",
                 message_name
             )))?;
-        let msg = Self::parse_msg(message_name, message_src)?;
         let mut dependencies = HashMap::default();
         for message_body in message_bodies {
             let dependency = Self::parse_dependency(message_body)?;
             dependencies.insert(dependency.path().clone(), dependency);
         }
 
-        Ok(DynamicMsg { msg, dependencies })
+        Ok((message_src, dependencies))
+    }
+
+    /// Replaces the [DecodeOptions] used when decoding messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::{DecodeOptions, DynamicMsg};
+    /// use ros2_message::{Time, Value};
+    ///
+    /// let msg_definition = r#"
+    /// builtin_interfaces/Time stamp
+    ///
+    /// ================================================================================
+    /// MSG: builtin_interfaces/Time
+    ///
+    /// int32 sec
+    /// uint32 nanosec
+    /// "#;
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/Stamped", msg_definition)
+    ///     .expect("The message definition was invalid")
+    ///     .with_options(DecodeOptions { map_builtin_time: true, ..Default::default() });
+    /// let message = dynamic_message.decode(&[0x00u8, 0x01, 0, 0, 157, 47, 136, 102, 42, 0, 0, 0][..])
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(message["stamp"], Value::Time(Time { sec: 1720201117, nsec: 42 }));
+    /// ```
+    pub fn with_options(mut self, options: DecodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the [DecodeOptions] used when decoding messages
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
     }
 
     /// Returns the underlying ROS2 message definition
@@ -111,7 +178,7 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
         Self::parse_msg(message_type.as_str(), message_src)
     }
 
-    fn get_dependency(&self, path: &MessagePath) -> Result<&Msg<S>> {
+    pub(super) fn get_dependency(&self, path: &MessagePath) -> Result<&Msg<S>> {
         let Some(msg) = self.dependencies.get(path) else {
             return Err(Error::MessageDependencyMissing {
                 package: path.package().to_owned(),
@@ -154,66 +221,99 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
     /// // Reading the value field of the message
     /// assert_eq!(message["value"], ros2_message::Value::F32(core::f32::consts::PI));
     /// ```
-    pub fn decode<R: Read>(&self, r: R) -> Result<HashMap<String, Value<S>, S>> {
-        let values = self.decode_message(self.msg(), r)?;
+    pub fn decode<R: Read>(&self, r: R) -> Result<MessageValue<S>> {
+        let (values, _) = self.decode_message::<R, Value<S>>(r, self.options.max_bytes)?;
+
+        Ok(values)
+    }
 
-        self.map_values(values)
+    /// Same as [Self::decode()], but also returns the encapsulation declared in the payload header.
+    ///
+    /// Passing the encapsulation on to [Self::encode_with_encapsulation()] re-encodes a message
+    /// with the byte order it was originally recorded in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::{DynamicMsg, Encapsulation, Endianness};
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/Msg", "uint16 value")
+    ///     .expect("The message definition was invalid");
+    /// let (message, encapsulation) = dynamic_message
+    ///     .decode_with_encapsulation(&[0x00u8, 0x00, 0, 0, 0x01, 0x02][..])
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(message["value"], ros2_message::Value::U16(0x0102));
+    /// assert_eq!(encapsulation, Encapsulation::CdrBe);
+    /// assert_eq!(encapsulation.endianness(), Endianness::Big);
+    /// ```
+    pub fn decode_with_encapsulation<R: Read>(
+        &self,
+        r: R,
+    ) -> Result<(MessageValue<S>, Encapsulation)> {
+        self.decode_message::<R, Value<S>>(r, self.options.max_bytes)
     }
 
     /// This maps the result of [Self::decode_unmapped()] to the result of [Self::decode()]
-    pub fn map_values(&self, mut values: MessageValues<S>) -> Result<HashMap<String, Value<S>, S>> {
-        self.map_field_names(self.msg(), &mut values)
+    pub fn map_values(&self, mut values: MessageValues<S>) -> Result<MessageValue<S>> {
+        self.map_field_names(0, &mut values)
     }
 
     // Map decoded field arrays to their field names for easy usage
-    // TODO!: Use better collection method for this operation
     fn map_field_names(
         &self,
-        msg: &Msg<S>,
+        index: usize,
         values: &mut MessageValues<S>,
-    ) -> Result<HashMap<String, Value<S>, S>> {
-        let mut map = HashMap::with_capacity_and_hasher(msg.fields().len(), Default::default());
-        for field_info in msg.fields().iter() {
-            let field_name = field_info.name().to_owned();
-
-            let unnest_values = |value, msg: &Msg<_>| {
-                let Value::Array(nested_values) = value else {
-                    return Err(Error::DecodingError {
-                        err: std::io::Error::other("Decoded message does not match the structure in the definition, please report this issue"),
-                        field: field_info.clone().to_random_state(),
-                        msg: msg.clone().to_random_state(),
-                        offset: 0,
-                    });
-                };
-
-                Ok(nested_values)
-            };
-
-            let value = values.pop_front().ok_or(Error::DecodingError {
-                err: std::io::Error::other("Decoded message does not match the structure in the definition, please report this issue"),
-                field: field_info.clone().to_random_state(),
-                msg: msg.clone().to_random_state(),
-                offset: 0,
-            })?;
+    ) -> Result<MessageValue<S>> {
+        let fields = self.plan.fields(index);
+        let mut map = MessageValue::with_capacity_and_hasher(fields.len(), Default::default());
+        for field in fields {
+            let value = values.pop_front().ok_or_else(|| invalid_member_error(
+                field,
+                0,
+                None,
+                io::Error::other("Decoded message does not match the structure in the definition, please report this issue"),
+            ))?;
+
+            map.insert(field.name.clone(), self.map_field_value(field, value)?);
+        }
 
-            let msg = match field_info.datatype() {
-                DataType::GlobalMessage(path) => Some(self.get_dependency(&path)?),
+        Ok(map)
+    }
 
-                DataType::LocalMessage(name) => Some(self.get_dependency(&msg.path().peer(name))?),
-                _ => None,
+    // Map the unmapped value of a single field, see Self::map_field_names
+    fn map_field_value(&self, field: &FieldPlan<S>, value: Value<S>) -> Result<Value<S>> {
+        let unnest_values = |value| {
+            let Value::Array(nested_values) = value else {
+                return Err(invalid_member_error(
+                    field,
+                    0,
+                    None,
+                    io::Error::other("Decoded message does not match the structure in the definition, please report this issue"),
+                ));
             };
 
-            let value = if let Some(msg) = msg {
-                let nested_values = unnest_values(value, msg)?;
-                match field_info.case() {
-                    FieldCase::Array(_) | FieldCase::Vector => {
+            Ok(nested_values)
+        };
+
+        let value = match &field.element {
+            // Messages mapped to builtin values are already decoded to their final value
+            Element::Message(_) if is_builtin_time(&value) => value,
+            Element::Message(nested) => {
+                let nested_values = unnest_values(value)?;
+                match field.layout {
+                    Layout::Array(_) | Layout::Sequence(_) => {
                         // we need to handle nested arrays and vectors
                         // fix TF encoding
-                        let mut mapped_msgs = vec![];
+                        let mut mapped_msgs = Vec::with_capacity(nested_values.len());
                         for nested_value in nested_values.into_iter() {
-                            let second_level_unnested = unnest_values(nested_value, msg)?;
+                            if is_builtin_time(&nested_value) {
+                                mapped_msgs.push(nested_value);
+                                continue;
+                            }
+                            let second_level_unnested = unnest_values(nested_value)?;
                             let msg = Value::Message(self.map_field_names(
-                                msg,
+                                *nested,
                                 &mut VecDeque::from(second_level_unnested),
                             )?);
                             mapped_msgs.push(msg);
@@ -221,17 +321,15 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
                         Value::Array(mapped_msgs)
                     }
                     _ => Value::Message(
-                        self.map_field_names(msg, &mut VecDeque::from(nested_values))?,
+                        self.map_field_names(*nested, &mut VecDeque::from(nested_values))?,
                     ),
                 }
-            } else {
-                value
-            };
-
-            map.insert(field_name, value);
-        }
+            }
+            Element::Missing(path) => return Err(missing_dependency(path)),
+            _ => value,
+        };
 
-        Ok(map)
+        Ok(value)
     }
 
     /// This will read the provided reader to its end and return a vector with all field values.
@@ -264,32 +362,39 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
     /// assert_eq!(message[1], ros2_message::Value::F32(core::f32::consts::PI));
     /// ```
     pub fn decode_unmapped<R: Read>(&self, r: R) -> Result<MessageValues<S>> {
-        self.decode_message(self.msg(), r)
+        let (values, _) = self.decode_message::<R, Unmapped<S>>(r, self.options.max_bytes)?;
+
+        Ok(values)
+    }
+
+    /// Same as [Self::decode_unmapped()], but also returns the encapsulation declared in the payload header.
+    pub fn decode_unmapped_with_encapsulation<R: Read>(
+        &self,
+        r: R,
+    ) -> Result<(MessageValues<S>, Encapsulation)> {
+        self.decode_message::<R, Unmapped<S>>(r, self.options.max_bytes)
     }
 
+    /// Decodes a whole payload, which fails to read more than `limit` bytes
     // This is necessary to prevent the creation of nested ByteCounters
-    fn decode_message<R: Read>(&self, msg: &Msg<S>, r: R) -> Result<MessageValues<S>> {
-        let mut r = ByteCounter::new(r);
+    pub(super) fn decode_message<'a, R: Read, V: Decoded<'a, S, ByteCounter<R>>>(
+        &'a self,
+        r: R,
+        limit: Option<usize>,
+    ) -> Result<(V::Fields, Encapsulation)> {
+        let msg = &self.msg;
+        let mut r = ByteCounter::new(r, limit);
 
         let mut buf = [0, 0, 0, 0];
         r.read_exact(&mut buf)?;
 
         // https://github.com/foxglove/cdr/blob/main/src/EncapsulationKind.ts
-        // let kind = buf[1];
-        if buf != [0, 0x01, 0, 0] {
-            return Err(Error::DecodingError {
-                msg: msg.clone().to_random_state(),
-                field: FieldInfo::new("uint8", "error_placeholder_field", crate::FieldCase::Unit)
-                    .unwrap(),
-                offset: r.bytes_read(),
-                err: io::Error::other(format!(
-                    "Invalid CRD kind {:b}, only little endian is supported",
-                    buf[1]
-                )),
-            });
-        }
+        let Some(encapsulation) = Encapsulation::from_header(buf) else {
+            return Err(self.unsupported_encapsulation(buf, r.position()));
+        };
+        r.set_encapsulation(encapsulation);
 
-        let decoded_values = self.decode_message_inner(msg, &mut r)?;
+        let decoded_values = self.decode_values::<_, V>(0, &mut r)?;
 
         // This is purely a sanity check
         {
@@ -310,225 +415,431 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
             }
         }
 
-        Ok(decoded_values)
+        Ok((decoded_values, encapsulation))
     }
 
-    fn decode_message_inner<R: Read>(
+    /// Reads a string, which is borrowed from the data if the reader borrows it
+    pub(super) fn read_string<'de>(
         &self,
-        msg: &Msg<S>,
-        r: &mut ByteCounter<R>,
-    ) -> Result<MessageValues<S>> {
-        let mut values = MessageValues::with_capacity(msg.fields().len());
-        for field in msg.fields() {
-            let res = match field.case() {
-                FieldCase::Const(_) => Ok(field.const_value().unwrap().clone()),
-                FieldCase::Unit | FieldCase::Default(_) => {
-                    self.decode_field(msg.path(), field, r, true)
-                }
-                //.expect("Error while decoding unit field"),
-                FieldCase::Vector => self.decode_field_array(msg.path(), field, None, r),
-                //.expect("Error while decoding vector field"),
-                FieldCase::Array(l) => self.decode_field_array(msg.path(), field, Some(*l), r), //.expect("Error while decoding array field"),
-            };
+        bound: Option<usize>,
+        r: &mut impl CdrRead<'de>,
+    ) -> Result<Cow<'de, str>> {
+        r.align_to(4)?;
+        let len = r.read_u32()? as usize;
+
+        if len == 0 {
+            return Ok(Cow::Borrowed(""));
+        }
+        if let Some(bound) = bound {
+            self.check_bound("string", len - 1, bound)?;
+        }
 
-            let val = match res {
-                Ok(v) => v,
-                Err(e) => {
-                    return Err(match e {
-                        Error::DecodingError { err, .. } => Error::DecodingError {
-                            msg: msg.clone().to_random_state(),
-                            field: field.clone().to_random_state(),
-                            offset: r.bytes_read(),
-                            err,
-                        },
-                        e => e,
-                    })
-                }
-            };
+        let bytes = r.read_bytes(len - 1)?;
+        if r.read_u8()? != 0 {
+            return Err(io::Error::other("The string is not terminated by a null byte").into());
+        }
 
-            values.push_back(val);
+        let string = match bytes {
+            Cow::Borrowed(bytes) => {
+                Cow::Borrowed(std::str::from_utf8(bytes).map_err(io::Error::other)?)
+            }
+            Cow::Owned(bytes) => Cow::Owned(String::from_utf8(bytes).map_err(io::Error::other)?),
+        };
+        Ok(string)
+    }
+
+    /// Reads a wide string, which is a sequence of UTF-16 code units without a terminator
+    pub(super) fn read_wstring<'de>(
+        &self,
+        bound: Option<usize>,
+        r: &mut impl CdrRead<'de>,
+    ) -> Result<String> {
+        r.align_to(4)?;
+        let len = r.read_u32()? as usize;
+        if let Some(bound) = bound {
+            self.check_bound("wstring", len, bound)?;
         }
 
-        Ok(values)
+        let units = r.read_numbers::<u16>(len)?;
+        Ok(String::from_utf16(&units).map_err(io::Error::other)?)
     }
 
-    fn decode_field<R: Read>(
+    // Reads everything in front of the elements of an array and returns its length
+    pub(super) fn read_array_header<'de>(
         &self,
-        parent: &MessagePath,
-        field: &FieldInfo<S>,
-        r: &mut ByteCounter<R>,
-        align: bool,
-    ) -> Result<Value<S>> {
-        /*
-        let field_type = field.datatype().to_string();
-        let prev_read = r.bytes_read();
-        println!(" {} > at {:X}", field_type, prev_read);
-        */
-
-        let alignment = if align {
-            match field.datatype() {
-                DataType::Bool | DataType::I8(_) | DataType::U8(_) => 1,
-                DataType::I16 | DataType::U16 => 2,
-                DataType::I32 | DataType::U32 | DataType::F32 | DataType::Time => 4,
-                DataType::I64 | DataType::U64 | DataType::F64 => 8,
-                _ => {
-                    4 // Default alignment for strings and messages
-                }
+        field: &FieldPlan<S>,
+        array_length: Option<usize>,
+        r: &mut impl CdrRead<'de>,
+    ) -> Result<usize> {
+        if r.encapsulation().is_xcdr2() && !field.primitive {
+            // XCDR2 prefixes collections of non primitive types with their size
+            r.read_dheader()?;
+        }
+
+        let array_length = match array_length {
+            Some(v) => v,
+            None => {
+                r.align_to(4)?;
+                r.read_u32()? as usize
             }
-        } else {
-            0
         };
+        if let Layout::Sequence(bound) = field.layout {
+            self.check_sequence_length(array_length, bound)?;
+        }
 
-        let value = match field.datatype() {
-            DataType::Bool => r.read_u8().map(|i| i != 0)?.into(),
-            DataType::I8(_) => r.read_i8()?.into(),
-            DataType::I16 => {
-                r.align_to(alignment)?;
-                r.read_i16::<LE>()?.into()
-            }
-            DataType::I32 => {
-                r.align_to(alignment)?;
-                r.read_i32::<LE>()?.into()
-            }
-            DataType::I64 => {
-                r.align_to(alignment)?;
-                r.read_i64::<LE>()?.into()
-            }
-            DataType::U8(_) => r.read_u8()?.into(),
-            DataType::U16 => {
-                r.align_to(alignment)?;
-                r.read_u16::<LE>()?.into()
-            }
-            DataType::U32 => {
-                r.align_to(alignment)?;
-                r.read_u32::<LE>()?.into()
-            }
-            DataType::U64 => {
-                r.align_to(alignment)?;
-                r.read_u64::<LE>()?.into()
-            }
-            DataType::F32 => {
-                r.align_to(alignment)?;
-                r.read_f32::<LE>()?.into()
-            }
-            DataType::F64 => {
-                r.align_to(alignment)?;
-                r.read_f64::<LE>()?.into()
-            }
-            DataType::String => {
-                r.align_to(4)?;
-                let len = r.read_u32::<LE>()?;
+        // Align once before reading the array payload. Individual elements skip alignment to avoid
+        // compounding offsets; variable-sized types handle their own alignment internally.
+        // Empty sequences carry no payload and therefore no padding either.
+        if array_length > 0 {
+            r.align_to(field.alignment)?;
+        }
 
-                if len == 0 {
-                    return Ok(Value::String("".to_owned()));
-                }
+        Ok(array_length)
+    }
 
-                let mut v = vec![0; (len - 1) as usize];
-                r.read_exact(&mut v)?;
-
-                // Read \0 character
-                let null = r.read_u8()?;
-                assert_eq!(0, null);
-
-                match String::from_utf8(v) {
-                    Ok(s) => Value::String(s),
-                    Err(e) => {
-                        return Err(Error::DecodingError {
-                            err: std::io::Error::other(e),
-                            field: field.clone().to_random_state(),
-                            msg: self.msg.clone().to_random_state(),
-                            offset: r.bytes_read(),
-                        })
-                    }
-                }
-            }
-            DataType::Time => {
-                r.align_to(4)?;
-                let sec = r.read_u32::<LE>()?;
-                let nsec = r.read_u32::<LE>()?;
+    pub(super) fn unsupported_encapsulation(&self, header: [u8; 4], offset: usize) -> Error {
+        Error::DecodingError {
+            path: String::new(),
+            datatype: String::new(),
+            offset,
+            encapsulation: None,
+            err: io::Error::other(format!(
+                "Unsupported encapsulation kind {:#04x}{:02x}, only CDR and XCDR2 are supported",
+                header[0], header[1]
+            )),
+        }
+    }
 
-                return Ok(Value::Time(crate::Time { sec, nsec }));
-            }
-            DataType::Duration => panic!("Duration parsing not implemented yet"),
-            DataType::LocalMessage(name) => {
-                let path = parent.peer(name);
-                let dependency = self.get_dependency(&path)?;
-
-                // Decoding is fully unmapped so messages are just expressed as
-                // arrays before they get mapped to field names
-                Value::Array(self.decode_message_inner(dependency, r)?.into())
-            }
-            DataType::GlobalMessage(path) => {
-                // panic!("Global messages unsupported (Hasher) {path}");
+    pub(super) fn plan(&self) -> &DecodePlan<S> {
+        &self.plan
+    }
+
+    pub(super) fn check_bound(&self, kind: &str, length: usize, bound: usize) -> io::Result<()> {
+        if self.options.validate_bounds && length > bound {
+            return Err(io::Error::other(format!(
+                "The {kind} of length {length} exceeds its bound of {bound}"
+            )));
+        }
+        Ok(())
+    }
 
-                let dependency = self.get_dependency(path)?;
-                let vec: Vec<_> = self.decode_message_inner(dependency, r)?.into();
+    /// Checks the length of a sequence against its bound and the `max_sequence_length` limit
+    pub(super) fn check_sequence_length(
+        &self,
+        length: usize,
+        bound: Option<usize>,
+    ) -> io::Result<()> {
+        if let Some(bound) = bound {
+            self.check_bound("sequence", length, bound)?;
+        }
+        match self.options.max_sequence_length {
+            Some(max) if length > max => Err(io::Error::other(format!(
+                "The sequence of length {length} exceeds the limit of {max} elements"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
 
-                vec.into()
-            }
-        };
+impl<'a, S, R> Decoded<'a, S, ByteCounter<R>> for Value<S>
+where
+    S: BuildHasher + Default + Clone + core::fmt::Debug,
+    R: Read,
+{
+    type Fields = MessageValue<S>;
 
-        /*
-        println!(
-            " {} < at {:X} ( +{}) => {}",
-            " ".repeat(field_type.len()),
-            r.bytes_read(),
-            r.bytes_read() - prev_read,
-            &value
-        );
-         */
+    fn new_message(fields: usize) -> Self::Fields {
+        MessageValue::with_capacity_and_hasher(fields, Default::default())
+    }
 
-        Ok(value)
+    fn insert(message: &mut Self::Fields, name: &'a str, value: Self) {
+        message.insert(name.to_owned(), value);
     }
 
-    fn decode_field_array<R: Read>(
-        &self,
-        parent: &MessagePath,
-        field: &FieldInfo<S>,
-        array_length: Option<usize>,
+    fn message(message: Self::Fields) -> Self {
+        Value::Message(message)
+    }
+
+    fn time_fields(sec: &Self, nanosec: &Self) -> Option<(i32, u32)> {
+        match (sec, nanosec) {
+            (Value::I32(sec), Value::U32(nanosec)) => Some((*sec, *nanosec)),
+            _ => None,
+        }
+    }
+
+    fn string(string: Cow<'a, str>) -> Self {
+        Value::String(string.into_owned())
+    }
+
+    fn array(elements: Vec<Self>) -> Self {
+        Value::Array(elements)
+    }
+
+    fn constant(value: &'a Value<S>) -> Self {
+        value.clone()
+    }
+
+    fn primitive_array(
         r: &mut ByteCounter<R>,
-    ) -> Result<Value<S>> {
-        let alignment = match field.datatype() {
-            DataType::Bool | DataType::I8(_) | DataType::U8(_) => 1,
-            DataType::I16 | DataType::U16 => 2,
-            DataType::I32 | DataType::U32 | DataType::F32 | DataType::Time => 4,
-            DataType::I64 | DataType::U64 | DataType::F64 => 8,
-            _ => 4, // strings and nested messages default to 4-byte alignment
-        };
+        element: &Element,
+        length: usize,
+        options: &DecodeOptions,
+    ) -> Result<Option<Self>> {
+        let start = r.position();
+        let array = read_primitive_array(element, length, r);
+        if options.primitive_arrays {
+            return Ok(array?.map(Value::PrimitiveArray));
+        }
 
-        let array_length = match array_length {
-            Some(v) => v,
-            None => {
-                r.align_to(4)?;
-                r.read_u32::<LE>()? as usize
-            }
-        };
+        // Reading the elements at once is faster than reading them one by one, errors are still
+        // attributed to the element that could not be read
+        let array = array.map_err(|e| {
+            let size = element.fixed_size().unwrap_or(1);
+            element_error((r.position() - start) / size, e.into())
+        })?;
+        Ok(array.map(|array| Value::Array(array.to_values())))
+    }
+}
 
-        // Align once before reading the array payload. Individual elements skip alignment to avoid
-        // compounding offsets; variable-sized types handle their own alignment internally.
-        r.align_to(alignment)?;
-        // TODO: optimize by checking data type only once
+/// Values of [DynamicMsg::decode_unmapped()], where messages are arrays of their field values
+pub(super) struct Unmapped<S: BuildHasher + Default + Clone + core::fmt::Debug>(Value<S>);
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<Scalar> for Unmapped<S> {
+    fn from(scalar: Scalar) -> Self {
+        Unmapped(scalar.into())
+    }
+}
+
+impl<'a, S, R> Decoded<'a, S, ByteCounter<R>> for Unmapped<S>
+where
+    S: BuildHasher + Default + Clone + core::fmt::Debug,
+    R: Read,
+{
+    type Fields = MessageValues<S>;
+
+    fn new_message(fields: usize) -> Self::Fields {
+        MessageValues::with_capacity(fields)
+    }
+
+    fn insert(message: &mut Self::Fields, _name: &'a str, value: Self) {
+        message.push_back(value.0);
+    }
+
+    fn message(message: Self::Fields) -> Self {
+        Unmapped(Value::Array(message.into()))
+    }
+
+    fn time_fields(sec: &Self, nanosec: &Self) -> Option<(i32, u32)> {
+        <Value<S> as Decoded<S, ByteCounter<R>>>::time_fields(&sec.0, &nanosec.0)
+    }
+
+    fn string(string: Cow<'a, str>) -> Self {
+        Unmapped(Value::String(string.into_owned()))
+    }
+
+    fn array(elements: Vec<Self>) -> Self {
+        Unmapped(Value::Array(elements.into_iter().map(|e| e.0).collect()))
+    }
+
+    fn constant(value: &'a Value<S>) -> Self {
+        Unmapped(value.clone())
+    }
 
-        let mut values = Vec::with_capacity(array_length);
-        for _ in 0..array_length {
-            values.push(self.decode_field(parent, field, r, false)?);
+    fn primitive_array(
+        r: &mut ByteCounter<R>,
+        element: &Element,
+        length: usize,
+        options: &DecodeOptions,
+    ) -> Result<Option<Self>> {
+        Ok(Value::primitive_array(r, element, length, options)?.map(Unmapped))
+    }
+}
+
+/// Reads an array of numbers or `bool` at once, returns `None` for other element types.
+fn read_primitive_array<R: Read>(
+    element: &Element,
+    length: usize,
+    r: &mut ByteCounter<R>,
+) -> io::Result<Option<PrimitiveArray>> {
+    let array = match element {
+        Element::Bool => {
+            PrimitiveArray::Bool(r.read_bytes(length)?.iter().map(|b| *b != 0).collect())
         }
+        Element::I8 => PrimitiveArray::I8(r.read_bytes(length)?.iter().map(|b| *b as i8).collect()),
+        Element::U8 => PrimitiveArray::U8(r.read_bytes(length)?.into_owned()),
+        Element::I16 => PrimitiveArray::I16(r.read_numbers(length)?.into_owned()),
+        Element::I32 => PrimitiveArray::I32(r.read_numbers(length)?.into_owned()),
+        Element::I64 => PrimitiveArray::I64(r.read_numbers(length)?.into_owned()),
+        Element::U16 | Element::WChar => PrimitiveArray::U16(r.read_numbers(length)?.into_owned()),
+        Element::U32 => PrimitiveArray::U32(r.read_numbers(length)?.into_owned()),
+        Element::U64 => PrimitiveArray::U64(r.read_numbers(length)?.into_owned()),
+        Element::F32 => PrimitiveArray::F32(r.read_numbers(length)?.into_owned()),
+        Element::F64 => PrimitiveArray::F64(r.read_numbers(length)?.into_owned()),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(array))
+}
 
-        Ok(Value::Array(values))
+/// Number of elements that are allocated upfront for arrays, larger arrays grow while they are
+/// read so that corrupted lengths can not exhaust the memory
+pub(super) const MAX_PREALLOCATED_ELEMENTS: usize = 4096;
+
+/// Number of elements without data that an array may hold if the length of the data is unknown
+pub(super) const MAX_EMPTY_ELEMENTS: usize = 1 << 16;
+
+/// Elements without any data, like messages of constants only, could be repeated endlessly by a
+/// corrupted length, so their arrays can not be longer than the number of bytes left to read.
+/// If that number is unknown, they can not be longer than [MAX_EMPTY_ELEMENTS].
+pub(super) fn check_empty_elements(length: usize, remaining: Option<usize>) -> io::Result<()> {
+    match remaining {
+        Some(remaining) if length > remaining => Err(io::Error::other(format!(
+            "The array of {length} elements without data is longer than the {remaining} bytes left"
+        ))),
+        None if length > MAX_EMPTY_ELEMENTS => Err(io::Error::other(format!(
+            "The array of {length} elements without data exceeds the limit of {MAX_EMPTY_ELEMENTS} elements"
+        ))),
+        _ => Ok(()),
     }
 }
 
-struct ByteCounter<R> {
+/// Attributes decoding errors of a member to the member, prefixing the path of the failed field
+/// with its name while the error is passed up through the nested messages
+pub(super) fn member_error<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    field: &FieldPlan<S>,
+    offset: usize,
+    encapsulation: Encapsulation,
+    e: Error,
+) -> Error {
+    match e {
+        Error::DecodingError {
+            path,
+            datatype,
+            offset: inner_offset,
+            encapsulation: inner_encapsulation,
+            err,
+        } => {
+            // The innermost member that failed has no type yet
+            let (datatype, offset) = match datatype.is_empty() {
+                true => (field.datatype.clone(), offset),
+                false => (datatype, inner_offset),
+            };
+            Error::DecodingError {
+                path: prefix_path(&field.name, path),
+                datatype,
+                offset,
+                encapsulation: inner_encapsulation.or(Some(encapsulation)),
+                err,
+            }
+        }
+        e => e,
+    }
+}
+
+/// Attributes decoding errors of an array element to its index
+pub(super) fn element_error(index: usize, e: Error) -> Error {
+    match e {
+        Error::DecodingError {
+            path,
+            datatype,
+            offset,
+            encapsulation,
+            err,
+        } => Error::DecodingError {
+            path: prefix_path(&format!("[{index}]"), path),
+            datatype,
+            offset,
+            encapsulation,
+            err,
+        },
+        e => e,
+    }
+}
+
+/// Error of a member that the decoder found to be invalid itself
+pub(super) fn invalid_member_error<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    field: &FieldPlan<S>,
+    offset: usize,
+    encapsulation: Option<Encapsulation>,
+    err: io::Error,
+) -> Error {
+    Error::DecodingError {
+        path: field.name.clone(),
+        datatype: field.datatype.clone(),
+        offset,
+        encapsulation,
+        err,
+    }
+}
+
+fn prefix_path(prefix: &str, path: String) -> String {
+    match path.is_empty() || path.starts_with('[') {
+        true => prefix.to_owned() + &path,
+        false => format!("{prefix}.{path}"),
+    }
+}
+
+pub(super) fn missing_dependency(path: &MessagePath) -> Error {
+    Error::MessageDependencyMissing {
+        package: path.package().to_owned(),
+        name: path.name().to_owned(),
+    }
+}
+
+fn is_builtin_time<S: BuildHasher + Default + Clone + core::fmt::Debug>(value: &Value<S>) -> bool {
+    matches!(value, Value::Time(_) | Value::Duration(_))
+}
+
+pub(super) fn is_primitive(datatype: &DataType) -> bool {
+    datatype.is_builtin() && !datatype.is_string()
+}
+
+pub(super) struct ByteCounter<R> {
     inner: R,
     count: usize,
+    /// Reading fails once `count` would exceed this
+    limit: usize,
+    encapsulation: Encapsulation,
+    /// Nesting depth of the message that is being decoded
+    depth: usize,
+    /// Bytes that were put back by [CdrRead::unread_u32], they are read before `inner`
+    unread: [u8; 4],
+    /// Number of bytes of `unread` that were read again
+    unread_pos: usize,
+}
+
+macro_rules! read_with_endianness {
+    ($($name:ident -> $ty:ty),* $(,)?) => {
+        $(
+            fn $name(&mut self) -> io::Result<$ty> {
+                match self.encapsulation.endianness() {
+                    Endianness::Little => byteorder::ReadBytesExt::$name::<LE>(self),
+                    Endianness::Big => byteorder::ReadBytesExt::$name::<BE>(self),
+                }
+            }
+        )*
+    };
 }
 
 impl<R> ByteCounter<R>
 where
     R: Read,
 {
-    fn new(inner: R) -> Self {
-        ByteCounter { inner, count: 0 }
+    /// Creates a counter for a whole payload, which fails to read more than `limit` bytes
+    pub(super) fn new(inner: R, limit: Option<usize>) -> Self {
+        ByteCounter {
+            inner,
+            count: 0,
+            limit: limit.unwrap_or(usize::MAX),
+            encapsulation: Encapsulation::default(),
+            depth: 0,
+            unread: [0; 4],
+            unread_pos: 4,
+        }
+    }
+
+    pub(super) fn set_encapsulation(&mut self, encapsulation: Encapsulation) {
+        self.encapsulation = encapsulation;
     }
 
     /*
@@ -536,33 +847,83 @@ where
         self.inner
     }
     */
+}
 
-    fn bytes_read(&self) -> usize {
-        self.count
+impl<R> CdrRead<'_> for ByteCounter<R>
+where
+    R: Read,
+{
+    fn encapsulation(&self) -> Encapsulation {
+        self.encapsulation
     }
 
-    /// Read the necessary amount of bytes so that the next read will be aligned to `size` bytes
-    fn align_to(&mut self, size: usize) -> io::Result<()> {
-        if size == 0 {
-            return Ok(()); // No alignment needed
-        }
+    fn position(&self) -> usize {
+        self.count
+    }
 
-        let cur_pos = self.bytes_read() - 4;
-        let cur_align = cur_pos % size;
+    /// Number of bytes that can be read before reaching the limit, if there is one
+    fn remaining(&self) -> Option<usize> {
+        (self.limit != usize::MAX).then(|| self.limit - self.count)
+    }
 
-        if cur_align > 0 {
-            let needed_offset = size - cur_align;
+    fn depth_mut(&mut self) -> &mut usize {
+        &mut self.depth
+    }
 
-            // println!("Aligning from {} + {}", cur_pos, needed_offset);
+    fn read_u8(&mut self) -> io::Result<u8> {
+        byteorder::ReadBytesExt::read_u8(self)
+    }
 
-            let mut buf = vec![0; needed_offset];
-            self.read_exact(&mut buf)?;
+    read_with_endianness!(
+        read_u16 -> u16,
+        read_u32 -> u32,
+        read_u64 -> u64,
+    );
+
+    /// Reads `length` bytes, the buffer only grows as far as the reader actually has data
+    fn read_bytes(&mut self, length: usize) -> io::Result<Cow<'static, [u8]>> {
+        let mut buf = Vec::new();
+        let read = self.take(length as u64).read_to_end(&mut buf)?;
+        if read != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
         }
+        Ok(Cow::Owned(buf))
+    }
 
-        // println!("Next byte will be {}", self.bytes_read());
+    /// Reads `length` numbers in chunks, so a corrupted length fails at the end of the data
+    /// instead of allocating all of them upfront.
+    fn read_numbers<T: Number>(&mut self, length: usize) -> io::Result<Cow<'static, [T]>> {
+        let mut values = Vec::with_capacity(length.min(MAX_PREALLOCATED_ELEMENTS));
+        while values.len() < length {
+            let start = values.len();
+            let chunk = (length - start).min(MAX_PREALLOCATED_ELEMENTS);
+            values.resize(start + chunk, T::zeroed());
+            self.read_exact(bytemuck::cast_slice_mut(&mut values[start..]))?;
+        }
+        to_native(&mut values, self.encapsulation.endianness());
+        Ok(Cow::Owned(values))
+    }
 
+    fn skip(&mut self, size: usize) -> io::Result<()> {
+        // Padding is skipped without setting up a copy
+        if size <= 8 {
+            return self.read_exact(&mut [0; 8][..size]);
+        }
+        let skipped = io::copy(&mut self.take(size as u64), &mut io::sink())?;
+        if skipped as usize != size {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
         Ok(())
     }
+
+    fn unread_u32(&mut self, n: u32) {
+        self.unread = match self.encapsulation.endianness() {
+            Endianness::Little => n.to_le_bytes(),
+            Endianness::Big => n.to_be_bytes(),
+        };
+        self.unread_pos = 0;
+        self.count -= 4;
+    }
 }
 
 impl<R> Read for ByteCounter<R>
@@ -570,7 +931,29 @@ where
     R: Read,
 {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let res = self.inner.read(buf);
+        if self.unread_pos < self.unread.len() && !buf.is_empty() {
+            let unread = &self.unread[self.unread_pos..];
+            let len = buf.len().min(unread.len());
+            buf[..len].copy_from_slice(&unread[..len]);
+            self.unread_pos += len;
+            self.count += len;
+            return Ok(len);
+        }
+
+        let available = self.limit - self.count;
+        if available == 0 && !buf.is_empty() {
+            // Reaching the limit is fine as long as the data ends there too
+            return match self.inner.read(&mut [0])? {
+                0 => Ok(0),
+                _ => Err(io::Error::other(format!(
+                    "The message exceeds the limit of {} bytes",
+                    self.limit
+                ))),
+            };
+        }
+
+        let len = buf.len().min(available);
+        let res = self.inner.read(&mut buf[..len]);
         if let Ok(size) = res {
             self.count += size
         }
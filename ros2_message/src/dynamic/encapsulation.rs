@@ -0,0 +1,156 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Byte order of serialized data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// Extensibility of the serialized structures, which decides the framing around their members.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Extensibility {
+    /// Members are serialized back to back.
+    Final,
+    /// Every structure is prefixed by a `DHEADER` holding its size.
+    Appendable,
+    /// Every structure is prefixed by a `DHEADER` and every member by an `EMHEADER`.
+    Mutable,
+}
+
+/// Encapsulation kind of serialized data, declared by the first bytes of every payload.
+///
+/// See the [DDS-RTPS specification](https://www.omg.org/spec/DDSI-RTPS/2.5/PDF) section 10 and
+/// the [DDS-XTypes specification](https://www.omg.org/spec/DDS-XTypes/1.3/PDF) section 7.4.3 for
+/// the list of representation identifiers.
+///
+/// The XCDR2 encapsulations describe the extensibility of the top level message only, since
+/// message definitions carry no extensibility annotations it is assumed for all nested messages too.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Encapsulation {
+    /// Classic big endian CDR (`CDR_BE`).
+    CdrBe,
+    /// Classic little endian CDR (`CDR_LE`), which is what ROS 2 middlewares emit by default.
+    #[default]
+    CdrLe,
+    /// Big endian XCDR2 of final types (`CDR2_BE`).
+    Cdr2Be,
+    /// Little endian XCDR2 of final types (`CDR2_LE`).
+    Cdr2Le,
+    /// Big endian XCDR2 of appendable types (`D_CDR2_BE`).
+    DelimitedCdr2Be,
+    /// Little endian XCDR2 of appendable types (`D_CDR2_LE`).
+    DelimitedCdr2Le,
+    /// Big endian XCDR2 of mutable types (`PL_CDR2_BE`).
+    PlCdr2Be,
+    /// Little endian XCDR2 of mutable types (`PL_CDR2_LE`).
+    PlCdr2Le,
+}
+
+impl Encapsulation {
+    /// Parses the encapsulation from the 4 byte header in front of every payload.
+    ///
+    /// Returns `None` for unknown or unsupported representation identifiers. XCDR2 payloads
+    /// are accepted with the `0x10`-`0x15` identifiers of DDS-XTypes 1.3 (`CDR2`, `PL_CDR2` and
+    /// `D_CDR`) as well as the `0x06`-`0x0b` identifiers that later revisions of DDS-RTPS and
+    /// DDS-XTypes assign to them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::{Encapsulation, Endianness};
+    ///
+    /// let encapsulation = Encapsulation::from_header([0x00, 0x01, 0, 0]).unwrap();
+    /// assert_eq!(encapsulation, Encapsulation::CdrLe);
+    /// assert_eq!(encapsulation.endianness(), Endianness::Little);
+    /// assert_eq!(Encapsulation::from_header([0x00, 0x09, 0, 0]), Some(Encapsulation::DelimitedCdr2Le));
+    /// assert!(Encapsulation::from_header([0x42, 0x42, 0, 0]).is_none());
+    /// ```
+    pub fn from_header(header: [u8; 4]) -> Option<Self> {
+        match [header[0], header[1]] {
+            [0x00, 0x00] => Some(Encapsulation::CdrBe),
+            [0x00, 0x01] => Some(Encapsulation::CdrLe),
+            [0x00, 0x06] | [0x00, 0x10] => Some(Encapsulation::Cdr2Be),
+            [0x00, 0x07] | [0x00, 0x11] => Some(Encapsulation::Cdr2Le),
+            [0x00, 0x08] | [0x00, 0x14] => Some(Encapsulation::DelimitedCdr2Be),
+            [0x00, 0x09] | [0x00, 0x15] => Some(Encapsulation::DelimitedCdr2Le),
+            [0x00, 0x0a] | [0x00, 0x12] => Some(Encapsulation::PlCdr2Be),
+            [0x00, 0x0b] | [0x00, 0x13] => Some(Encapsulation::PlCdr2Le),
+            _ => None,
+        }
+    }
+
+    /// Returns the 4 byte header that starts a payload of this encapsulation.
+    pub fn header(self) -> [u8; 4] {
+        let kind = match self {
+            Encapsulation::CdrBe => 0x00,
+            Encapsulation::CdrLe => 0x01,
+            Encapsulation::Cdr2Be => 0x06,
+            Encapsulation::Cdr2Le => 0x07,
+            Encapsulation::DelimitedCdr2Be => 0x08,
+            Encapsulation::DelimitedCdr2Le => 0x09,
+            Encapsulation::PlCdr2Be => 0x0a,
+            Encapsulation::PlCdr2Le => 0x0b,
+        };
+        [0x00, kind, 0x00, 0x00]
+    }
+
+    /// Returns the byte order used by this encapsulation.
+    pub fn endianness(self) -> Endianness {
+        match self {
+            Encapsulation::CdrBe
+            | Encapsulation::Cdr2Be
+            | Encapsulation::DelimitedCdr2Be
+            | Encapsulation::PlCdr2Be => Endianness::Big,
+            Encapsulation::CdrLe
+            | Encapsulation::Cdr2Le
+            | Encapsulation::DelimitedCdr2Le
+            | Encapsulation::PlCdr2Le => Endianness::Little,
+        }
+    }
+
+    /// Returns true if this is one of the XCDR2 encapsulations.
+    pub fn is_xcdr2(self) -> bool {
+        !matches!(self, Encapsulation::CdrBe | Encapsulation::CdrLe)
+    }
+
+    /// Largest alignment any value is padded to, XCDR2 caps 8 byte alignment at 4 bytes.
+    pub(crate) fn max_alignment(self) -> usize {
+        if self.is_xcdr2() {
+            4
+        } else {
+            8
+        }
+    }
+
+    pub(crate) fn extensibility(self) -> Extensibility {
+        match self {
+            Encapsulation::CdrBe
+            | Encapsulation::CdrLe
+            | Encapsulation::Cdr2Be
+            | Encapsulation::Cdr2Le => Extensibility::Final,
+            Encapsulation::DelimitedCdr2Be | Encapsulation::DelimitedCdr2Le => {
+                Extensibility::Appendable
+            }
+            Encapsulation::PlCdr2Be | Encapsulation::PlCdr2Le => Extensibility::Mutable,
+        }
+    }
+}
+
+impl fmt::Display for Encapsulation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Encapsulation::CdrBe => "CDR_BE".fmt(f),
+            Encapsulation::CdrLe => "CDR_LE".fmt(f),
+            Encapsulation::Cdr2Be => "CDR2_BE".fmt(f),
+            Encapsulation::Cdr2Le => "CDR2_LE".fmt(f),
+            Encapsulation::DelimitedCdr2Be => "D_CDR2_BE".fmt(f),
+            Encapsulation::DelimitedCdr2Le => "D_CDR2_LE".fmt(f),
+            Encapsulation::PlCdr2Be => "PL_CDR2_BE".fmt(f),
+            Encapsulation::PlCdr2Le => "PL_CDR2_LE".fmt(f),
+        }
+    }
+}
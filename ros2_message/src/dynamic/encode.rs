@@ -0,0 +1,529 @@
+use super::decode::{is_primitive, MessageValues};
+use super::encapsulation::Extensibility;
+use super::plan::alignment;
+use super::{DynamicMsg, Encapsulation, Endianness};
+use crate::error::{Error, Result};
+use crate::{DataType, FieldCase, FieldInfo, MessagePath, MessageValue, Msg, Value};
+use byteorder::{WriteBytesExt, BE, LE};
+use std::hash::BuildHasher;
+use std::io::Write;
+
+/// The field values of a single message, either mapped to their names or in definition order.
+enum Fields<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    Mapped(&'a MessageValue<S>),
+    Unmapped(Vec<&'a Value<S>>),
+}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> Fields<'a, S> {
+    fn from_value(value: &'a Value<S>) -> Option<Self> {
+        match value {
+            Value::Message(map) => Some(Fields::Mapped(map)),
+            Value::Array(values) => Some(Fields::Unmapped(values.iter().collect())),
+            _ => None,
+        }
+    }
+
+    fn get(&self, index: usize, field: &FieldInfo<S>) -> Option<&'a Value<S>> {
+        match self {
+            Fields::Mapped(map) => map.get(field.name()),
+            Fields::Unmapped(values) => values.get(index).copied(),
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Encodes a message into CDR bytes, this is the inverse operation of [Self::decode()].
+    ///
+    /// Constant fields are part of the message definition and are not encoded, so they can be
+    /// left out of the provided map.
+    ///
+    /// # Errors
+    ///
+    /// This will error if a field is missing or its value doesn't match the type in the message definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::DynamicMsg;
+    ///
+    /// let msg_definition = r#"
+    /// builtin_interfaces/Time stamp
+    /// float32 value
+    ///
+    /// ================================================================================
+    /// MSG: builtin_interfaces/Time
+    ///
+    /// int32 sec
+    /// uint32 nanosec
+    /// "#;
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/SmallMsg<S>", msg_definition)
+    ///     .expect("The message definition was invalid");
+    /// let bytes = [0x00u8, 0x01, 0, 0, 157, 47, 136, 102, 42, 0, 0 ,0, 219, 15, 73, 64];
+    /// let message = dynamic_message.decode(&bytes[..])
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(dynamic_message.encode(&message).unwrap(), bytes);
+    /// ```
+    pub fn encode(&self, value: &MessageValue<S>) -> Result<Vec<u8>> {
+        self.encode_with_encapsulation(value, Encapsulation::default())
+    }
+
+    /// Same as [Self::encode()], but writes the payload with the provided encapsulation
+    /// instead of little endian CDR.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::{DynamicMsg, Encapsulation};
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/Msg", "uint16 value")
+    ///     .expect("The message definition was invalid");
+    /// let bytes = [0x00u8, 0x00, 0, 0, 0x01, 0x02];
+    /// let (message, encapsulation) = dynamic_message.decode_with_encapsulation(&bytes[..])
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(dynamic_message.encode_with_encapsulation(&message, encapsulation).unwrap(), bytes);
+    /// ```
+    pub fn encode_with_encapsulation(
+        &self,
+        value: &MessageValue<S>,
+        encapsulation: Encapsulation,
+    ) -> Result<Vec<u8>> {
+        self.encode_message(self.msg(), Fields::Mapped(value), encapsulation)
+    }
+
+    /// Encodes field values in definition order into CDR bytes, this is the inverse operation
+    /// of [Self::decode_unmapped()].
+    ///
+    /// Nested messages are expected as arrays of their field values, just like
+    /// [Self::decode_unmapped()] returns them. Constant fields are skipped.
+    pub fn encode_unmapped(&self, values: &MessageValues<S>) -> Result<Vec<u8>> {
+        self.encode_unmapped_with_encapsulation(values, Encapsulation::default())
+    }
+
+    /// Same as [Self::encode_unmapped()], but writes the payload with the provided encapsulation
+    /// instead of little endian CDR.
+    pub fn encode_unmapped_with_encapsulation(
+        &self,
+        values: &MessageValues<S>,
+        encapsulation: Encapsulation,
+    ) -> Result<Vec<u8>> {
+        self.encode_message(
+            self.msg(),
+            Fields::Unmapped(values.iter().collect()),
+            encapsulation,
+        )
+    }
+
+    fn encode_message(
+        &self,
+        msg: &Msg<S>,
+        fields: Fields<'_, S>,
+        encapsulation: Encapsulation,
+    ) -> Result<Vec<u8>> {
+        let mut w = ByteWriter::new(encapsulation);
+
+        w.write_all(&encapsulation.header())?;
+
+        self.encode_message_inner(msg, &fields, &mut w)?;
+
+        Ok(w.into_inner())
+    }
+
+    fn encode_message_inner(
+        &self,
+        msg: &Msg<S>,
+        fields: &Fields<'_, S>,
+        w: &mut ByteWriter,
+    ) -> Result<()> {
+        match w.encapsulation().extensibility() {
+            Extensibility::Final => self.encode_members(msg, fields, w),
+            Extensibility::Appendable => {
+                let dheader = w.begin_delimited();
+                self.encode_members(msg, fields, w)?;
+                w.end_delimited(dheader);
+                Ok(())
+            }
+            Extensibility::Mutable => {
+                let dheader = w.begin_delimited();
+                let member_fields = msg.fields().iter().enumerate();
+                for (member_id, (index, field)) in member_fields
+                    .filter(|(_, field)| !field.is_constant())
+                    .enumerate()
+                {
+                    // Every member is written with length code 4, which means the member
+                    // size follows the EMHEADER as NEXTINT
+                    w.align_to(4);
+                    w.write_u32((4 << 28) | member_id as u32)?;
+                    let next_int = w.begin_delimited();
+                    self.encode_member(msg, index, field, fields, w)?;
+                    w.end_delimited(next_int);
+                }
+                w.end_delimited(dheader);
+                Ok(())
+            }
+        }
+    }
+
+    fn encode_members(
+        &self,
+        msg: &Msg<S>,
+        fields: &Fields<'_, S>,
+        w: &mut ByteWriter,
+    ) -> Result<()> {
+        for (index, field) in msg.fields().iter().enumerate() {
+            if !field.is_constant() {
+                self.encode_member(msg, index, field, fields, w)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn encode_member(
+        &self,
+        msg: &Msg<S>,
+        index: usize,
+        field: &FieldInfo<S>,
+        fields: &Fields<'_, S>,
+        w: &mut ByteWriter,
+    ) -> Result<()> {
+        let value = fields
+            .get(index, field)
+            .ok_or_else(|| encoding_error(msg.path(), field, "the field is missing"))?;
+
+        match field.case() {
+            FieldCase::Unit | FieldCase::Default(_) => {
+                self.encode_field(msg.path(), field, value, w, true)
+            }
+            FieldCase::Vector | FieldCase::BoundedVector(_) => {
+                self.encode_field_array(msg.path(), field, value, None, w)
+            }
+            FieldCase::Array(l) => self.encode_field_array(msg.path(), field, value, Some(*l), w),
+            FieldCase::Const(_) => Ok(()),
+        }
+    }
+
+    fn encode_field(
+        &self,
+        parent: &MessagePath,
+        field: &FieldInfo<S>,
+        value: &Value<S>,
+        w: &mut ByteWriter,
+        align: bool,
+    ) -> Result<()> {
+        let alignment = if align {
+            alignment(field.datatype())
+        } else {
+            0
+        };
+
+        let mismatch = || {
+            encoding_error(
+                parent,
+                field,
+                format!("expected a value of type `{}`", field.datatype()),
+            )
+        };
+
+        match (field.datatype(), value) {
+            (DataType::Bool, Value::Bool(v)) => w.write_u8(u8::from(*v))?,
+            (DataType::I8(_), Value::I8(v)) => w.write_i8(*v)?,
+            (DataType::I16, Value::I16(v)) => {
+                w.align_to(alignment);
+                w.write_i16(*v)?
+            }
+            (DataType::I32, Value::I32(v)) => {
+                w.align_to(alignment);
+                w.write_i32(*v)?
+            }
+            (DataType::I64, Value::I64(v)) => {
+                w.align_to(alignment);
+                w.write_i64(*v)?
+            }
+            (DataType::U8(_), Value::U8(v)) => w.write_u8(*v)?,
+            (DataType::U16, Value::U16(v)) => {
+                w.align_to(alignment);
+                w.write_u16(*v)?
+            }
+            (DataType::U32, Value::U32(v)) => {
+                w.align_to(alignment);
+                w.write_u32(*v)?
+            }
+            (DataType::U64, Value::U64(v)) => {
+                w.align_to(alignment);
+                w.write_u64(*v)?
+            }
+            (DataType::F32, Value::F32(v)) => {
+                w.align_to(alignment);
+                w.write_f32(*v)?
+            }
+            (DataType::F64, Value::F64(v)) => {
+                w.align_to(alignment);
+                w.write_f64(*v)?
+            }
+            (DataType::WChar, Value::U16(v)) => {
+                w.align_to(alignment);
+                w.write_u16(*v)?
+            }
+            (DataType::String | DataType::BoundedString(_), Value::String(s)) => {
+                if let DataType::BoundedString(bound) = field.datatype() {
+                    check_bound(parent, field, "string", s.len(), *bound)?;
+                }
+                w.align_to(4);
+                let len = u32::try_from(s.len() + 1)
+                    .map_err(|_| encoding_error(parent, field, "the string is too long"))?;
+                w.write_u32(len)?;
+                w.write_all(s.as_bytes())?;
+                // Terminating \0 character
+                w.write_u8(0)?;
+            }
+            (DataType::WString | DataType::BoundedWString(_), Value::String(s)) => {
+                let units: Vec<u16> = s.encode_utf16().collect();
+                if let DataType::BoundedWString(bound) = field.datatype() {
+                    check_bound(parent, field, "wstring", units.len(), *bound)?;
+                }
+                w.align_to(4);
+                let len = u32::try_from(units.len())
+                    .map_err(|_| encoding_error(parent, field, "the string is too long"))?;
+                w.write_u32(len)?;
+                for unit in units {
+                    w.write_u16(unit)?;
+                }
+            }
+            (DataType::Time, Value::Time(t)) => {
+                w.align_to(4);
+                w.write_u32(t.sec)?;
+                w.write_u32(t.nsec)?;
+            }
+            (DataType::Duration, Value::Duration(d)) => {
+                w.align_to(4);
+                w.write_i32(d.sec)?;
+                w.write_i32(d.nsec)?;
+            }
+            (DataType::LocalMessage(name), value) => {
+                let dependency = self.get_dependency(&parent.peer(name))?;
+                self.encode_nested_message(parent, field, dependency, value, w)?;
+            }
+            (DataType::GlobalMessage(path), value) => {
+                let dependency = self.get_dependency(path)?;
+                self.encode_nested_message(parent, field, dependency, value, w)?;
+            }
+            _ => return Err(mismatch()),
+        }
+
+        Ok(())
+    }
+
+    // Besides mapped and unmapped messages, builtin time messages also accept their builtin values
+    fn encode_nested_message(
+        &self,
+        parent: &MessagePath,
+        field: &FieldInfo<S>,
+        msg: &Msg<S>,
+        value: &Value<S>,
+        w: &mut ByteWriter,
+    ) -> Result<()> {
+        let (sec, nanosec) = match (msg.path().package(), msg.path().name(), value) {
+            ("builtin_interfaces", "Time", Value::Time(_))
+            | ("builtin_interfaces", "Duration", Value::Duration(_)) => {
+                value.builtin_time_fields().unwrap()
+            }
+            _ => {
+                let fields = Fields::from_value(value).ok_or_else(|| {
+                    encoding_error(
+                        parent,
+                        field,
+                        format!("expected a value of type `{}`", field.datatype()),
+                    )
+                })?;
+                return self.encode_message_inner(msg, &fields, w);
+            }
+        };
+
+        let mut map = MessageValue::<S>::with_capacity_and_hasher(2, S::default());
+        map.insert("sec".to_owned(), sec);
+        map.insert("nanosec".to_owned(), nanosec);
+        self.encode_message_inner(msg, &Fields::Mapped(&map), w)
+    }
+
+    fn encode_field_array(
+        &self,
+        parent: &MessagePath,
+        field: &FieldInfo<S>,
+        value: &Value<S>,
+        array_length: Option<usize>,
+        w: &mut ByteWriter,
+    ) -> Result<()> {
+        let alignment = alignment(field.datatype());
+
+        let converted;
+        let items = match value {
+            Value::Array(items) => items,
+            Value::PrimitiveArray(values) => {
+                converted = values.to_values();
+                &converted
+            }
+            _ => return Err(encoding_error(parent, field, "expected an array value")),
+        };
+
+        if let FieldCase::BoundedVector(bound) = field.case() {
+            check_bound(parent, field, "sequence", items.len(), *bound)?;
+        }
+
+        // XCDR2 prefixes collections of non primitive types with their size
+        let dheader = (w.encapsulation().is_xcdr2() && !is_primitive(field.datatype()))
+            .then(|| w.begin_delimited());
+
+        match array_length {
+            Some(l) if l != items.len() => {
+                return Err(encoding_error(
+                    parent,
+                    field,
+                    format!("expected {} array elements, got {}", l, items.len()),
+                ))
+            }
+            Some(_) => {}
+            None => {
+                let len = u32::try_from(items.len())
+                    .map_err(|_| encoding_error(parent, field, "the sequence is too long"))?;
+                w.align_to(4);
+                w.write_u32(len)?;
+            }
+        }
+
+        if !items.is_empty() {
+            w.align_to(alignment);
+        }
+
+        for item in items {
+            self.encode_field(parent, field, item, w, false)?;
+        }
+
+        if let Some(dheader) = dheader {
+            w.end_delimited(dheader);
+        }
+
+        Ok(())
+    }
+}
+
+fn encoding_error<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    parent: &MessagePath,
+    field: &FieldInfo<S>,
+    reason: impl Into<String>,
+) -> Error {
+    Error::EncodingError {
+        msg: parent.clone(),
+        field: field.name().to_owned(),
+        reason: reason.into(),
+    }
+}
+
+fn check_bound<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    parent: &MessagePath,
+    field: &FieldInfo<S>,
+    kind: &str,
+    length: usize,
+    bound: usize,
+) -> Result<()> {
+    if length > bound {
+        return Err(encoding_error(
+            parent,
+            field,
+            format!("the {kind} of length {length} exceeds its bound of {bound}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Counterpart of the decoders `ByteCounter`, alignment is relative to the end of the
+/// encapsulation header.
+struct ByteWriter {
+    inner: Vec<u8>,
+    encapsulation: Encapsulation,
+}
+
+macro_rules! write_with_endianness {
+    ($($name:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $name(&mut self, n: $ty) -> std::io::Result<()> {
+                match self.encapsulation.endianness() {
+                    Endianness::Little => WriteBytesExt::$name::<LE>(self, n),
+                    Endianness::Big => WriteBytesExt::$name::<BE>(self, n),
+                }
+            }
+        )*
+    };
+}
+
+impl ByteWriter {
+    fn new(encapsulation: Encapsulation) -> Self {
+        ByteWriter {
+            inner: Vec::new(),
+            encapsulation,
+        }
+    }
+
+    fn encapsulation(&self) -> Encapsulation {
+        self.encapsulation
+    }
+
+    /// Reserves space for a size prefix and returns its position for [Self::end_delimited()]
+    fn begin_delimited(&mut self) -> usize {
+        self.align_to(4);
+        let position = self.inner.len();
+        self.inner.extend_from_slice(&[0; 4]);
+        position
+    }
+
+    /// Writes the size of everything written since the matching [Self::begin_delimited()]
+    fn end_delimited(&mut self, position: usize) {
+        let size = (self.inner.len() - position - 4) as u32;
+        let bytes = match self.encapsulation.endianness() {
+            Endianness::Little => size.to_le_bytes(),
+            Endianness::Big => size.to_be_bytes(),
+        };
+        self.inner[position..position + 4].copy_from_slice(&bytes);
+    }
+
+    write_with_endianness!(
+        write_i16(i16),
+        write_i32(i32),
+        write_i64(i64),
+        write_u16(u16),
+        write_u32(u32),
+        write_u64(u64),
+        write_f32(f32),
+        write_f64(f64),
+    );
+
+    fn into_inner(self) -> Vec<u8> {
+        self.inner
+    }
+
+    /// Write the necessary amount of padding so that the next write will be aligned to `size` bytes
+    fn align_to(&mut self, size: usize) {
+        if size == 0 {
+            return;
+        }
+        let size = size.min(self.encapsulation.max_alignment());
+
+        let cur_align = (self.inner.len() - 4) % size;
+        if cur_align > 0 {
+            let padded_len = self.inner.len() + size - cur_align;
+            self.inner.resize(padded_len, 0);
+        }
+    }
+}
+
+impl Write for ByteWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
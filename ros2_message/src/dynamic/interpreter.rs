@@ -0,0 +1,450 @@
+use super::decode::{
+    check_empty_elements, element_error, invalid_member_error, member_error, missing_dependency,
+    MAX_PREALLOCATED_ELEMENTS,
+};
+use super::encapsulation::Extensibility;
+use super::plan::{BuiltinTime, Element, Layout};
+use super::reader::{CdrRead, Scalar};
+use super::{DecodeOptions, DynamicMsg};
+use crate::error::{Error, Result};
+use crate::Value;
+use std::borrow::Cow;
+use std::hash::BuildHasher;
+use std::io;
+use std::marker::PhantomData;
+
+/// Values that messages are decoded to from the reader `R`, like owned [Value]s or
+/// [ValueRef](super::ValueRef)s that borrow from the data.
+pub(super) trait Decoded<'a, S, R>: Sized + From<Scalar>
+where
+    S: BuildHasher + Default + Clone + core::fmt::Debug,
+    R: CdrRead<'a>,
+{
+    /// Values of the fields of a message, which are inserted in definition order
+    type Fields: core::fmt::Debug;
+
+    fn new_message(fields: usize) -> Self::Fields;
+
+    fn insert(message: &mut Self::Fields, name: &'a str, value: Self);
+
+    fn message(message: Self::Fields) -> Self;
+
+    /// Values of the `sec` and `nanosec` fields of a builtin time message, if they have the
+    /// types of the builtin time messages
+    fn time_fields(sec: &Self, nanosec: &Self) -> Option<(i32, u32)>;
+
+    fn string(string: Cow<'a, str>) -> Self;
+
+    fn array(elements: Vec<Self>) -> Self;
+
+    fn constant(value: &'a Value<S>) -> Self;
+
+    /// Reads an array of numbers or `bool` at once, returns `None` for other element types.
+    fn primitive_array(
+        r: &mut R,
+        element: &Element,
+        length: usize,
+        options: &DecodeOptions,
+    ) -> Result<Option<Self>>;
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Decodes the message at `index` of the plan
+    pub(super) fn decode_values<'a, R, V>(&'a self, index: usize, r: &mut R) -> Result<V::Fields>
+    where
+        R: CdrRead<'a>,
+        V: Decoded<'a, S, R>,
+    {
+        let mut decoder = Decoder::<S, R, V>::new(self);
+        decoder.push_message(index, false, r)?;
+        match decoder.run(r)? {
+            Completed::Message(values) => Ok(values),
+            Completed::Value(_) => unreachable!("Only nested messages are mapped to values"),
+        }
+    }
+
+    /// Decodes the value of the field at `index` of the plan
+    pub(super) fn decode_field<'a, R, V>(&'a self, index: usize, r: &mut R) -> Result<V>
+    where
+        R: CdrRead<'a>,
+        V: Decoded<'a, S, R>,
+    {
+        let mut decoder = Decoder::<S, R, V>::new(self);
+        let value = match decoder.field(index, r) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => decoder.run(r).map(|completed| match completed {
+                Completed::Message(values) => V::message(values),
+                Completed::Value(value) => value,
+            }),
+            Err(e) => Err(e),
+        };
+
+        let field = &self.plan().fields[index];
+        value.map_err(|e| member_error(field, r.position(), r.encapsulation(), e))
+    }
+}
+
+/// Interprets the decode plan with an explicit stack of frames for the nested messages and
+/// arrays of messages that are being decoded
+struct Decoder<'a, S, R, V>
+where
+    S: BuildHasher + Default + Clone + core::fmt::Debug,
+    R: CdrRead<'a>,
+    V: Decoded<'a, S, R>,
+{
+    msg: &'a DynamicMsg<S>,
+    stack: Vec<Frame<V, V::Fields>>,
+    reader: PhantomData<fn(&mut R)>,
+}
+
+enum Frame<V, M> {
+    Message(MessageFrame<V, M>),
+    /// Elements of an array of messages
+    Array(ArrayFrame<V>),
+}
+
+struct MessageFrame<V, M> {
+    /// Index of the message inside the plan
+    index: usize,
+    /// Field that is decoded next, an index into the fields of the plan
+    next: usize,
+    /// Field that is being decoded, decoding errors are attributed to it
+    field: Option<usize>,
+    values: FrameValues<V, M>,
+    /// End of the DHEADER of appendable and mutable messages
+    end: Option<usize>,
+    /// Members of mutable messages are each prefixed by an EMHEADER, which allows them to be
+    /// reordered or left out, see DDS-XTypes 7.4.3.5.3
+    mutable: bool,
+    /// End of the member of a mutable message that is being decoded
+    member_end: usize,
+    /// Set for nested builtin time messages that are mapped to builtin values
+    builtin_time: Option<BuiltinTime>,
+}
+
+enum FrameValues<V, M> {
+    /// Values of the fields, which are decoded in definition order
+    Ordered(M),
+    /// Values of the fields by their position in the definition. Used for mutable messages,
+    /// whose members may be serialized in any order, and for builtin time messages, which
+    /// only become messages if they can not be mapped to builtin values.
+    Indexed(Vec<Option<V>>),
+}
+
+struct ArrayFrame<V> {
+    /// Index of the message of the elements inside the plan
+    index: usize,
+    elements: Vec<V>,
+    length: usize,
+    /// Position of the first element
+    start: usize,
+    /// Element that is being decoded
+    current: Option<usize>,
+}
+
+/// The value of a frame that was popped off the stack
+enum Completed<V, M> {
+    Message(M),
+    /// Arrays and messages that were mapped to builtin values
+    Value(V),
+}
+
+impl<'a, S, R, V> Decoder<'a, S, R, V>
+where
+    S: BuildHasher + Default + Clone + core::fmt::Debug,
+    R: CdrRead<'a>,
+    V: Decoded<'a, S, R>,
+{
+    fn new(msg: &'a DynamicMsg<S>) -> Self {
+        Decoder {
+            msg,
+            stack: Vec::new(),
+            reader: PhantomData,
+        }
+    }
+
+    /// Steps through the plan until the frame at the bottom of the stack is completed
+    fn run(&mut self, r: &mut R) -> Result<Completed<V, V::Fields>> {
+        loop {
+            match self.step(r) {
+                Ok(Some(completed)) => return Ok(completed),
+                Ok(None) => {}
+                Err(e) => return Err(self.attribute(e, r)),
+            }
+        }
+    }
+
+    fn step(&mut self, r: &mut R) -> Result<Option<Completed<V, V::Fields>>> {
+        let plan = self.msg.plan();
+        let field = match self.stack.last_mut() {
+            Some(Frame::Message(frame)) => {
+                let message = &plan.messages[frame.index];
+                let field = match frame.mutable {
+                    false => {
+                        let next = frame.next;
+                        frame.next += 1;
+                        Some(next).filter(|next| message.fields.contains(next))
+                    }
+                    true if r.position() < frame.end.expect("Mutable messages are delimited") => {
+                        let (member_id, member_end) = r.read_emheader()?;
+                        // Member ids are assigned sequentially to all fields that are not
+                        // constants
+                        let Some(field) = message.members.get(member_id) else {
+                            // Skip members unknown to this definition
+                            r.skip_to(member_end)?;
+                            return Ok(None);
+                        };
+                        frame.member_end = member_end;
+                        Some(message.fields.start + field)
+                    }
+                    true => None,
+                };
+                frame.field = field;
+                field
+            }
+            Some(Frame::Array(frame)) => {
+                if frame.elements.len() < frame.length {
+                    frame.current = Some(frame.elements.len());
+                    let index = frame.index;
+                    self.push_message(index, true, r)?;
+                    return Ok(None);
+                }
+                let Some(Frame::Array(frame)) = self.stack.pop() else {
+                    unreachable!()
+                };
+                return self.complete(Completed::Value(V::array(frame.elements)), r);
+            }
+            None => unreachable!("Decoding stops once the stack is empty"),
+        };
+
+        match field {
+            Some(field) => {
+                if let Some(value) = self.field(field, r)? {
+                    self.deliver(value, r)?;
+                }
+                Ok(None)
+            }
+            None => {
+                let Some(Frame::Message(frame)) = self.stack.pop() else {
+                    unreachable!()
+                };
+                let completed = self.finish_message(frame, r)?;
+                self.complete(completed, r)
+            }
+        }
+    }
+
+    /// Decodes the value of a field, or pushes the frames that decode it
+    fn field(&mut self, index: usize, r: &mut R) -> Result<Option<V>> {
+        let msg = self.msg;
+        let field = &msg.plan().fields[index];
+        let array_length = match field.layout {
+            Layout::Constant => {
+                let constant = field.constant.as_ref().expect("Constants have a value");
+                return Ok(Some(V::constant(constant)));
+            }
+            Layout::Unit => {
+                if let Element::Message(index) = field.element {
+                    self.push_message(index, true, r)?;
+                    return Ok(None);
+                }
+                return self.element(&field.element, field.alignment, r).map(Some);
+            }
+            Layout::Array(length) => Some(length),
+            Layout::Sequence(_) => None,
+        };
+
+        let length = msg.read_array_header(field, array_length, r)?;
+        if let Some(array) = V::primitive_array(r, &field.element, length, msg.options())? {
+            return Ok(Some(array));
+        }
+        if let Element::Message(index) = field.element {
+            self.stack.push(Frame::Array(ArrayFrame {
+                index,
+                elements: Vec::with_capacity(length.min(MAX_PREALLOCATED_ELEMENTS)),
+                length,
+                start: r.position(),
+                current: None,
+            }));
+            return Ok(None);
+        }
+
+        let mut elements = Vec::with_capacity(length.min(MAX_PREALLOCATED_ELEMENTS));
+        for index in 0..length {
+            let start = r.position();
+            elements.push(
+                self.element(&field.element, 0, r)
+                    .map_err(|e| element_error(index, e))?,
+            );
+            if index == 0 && r.position() == start {
+                check_empty_elements(length, r.remaining())?;
+            }
+        }
+        Ok(Some(V::array(elements)))
+    }
+
+    /// Decodes a single value that is not a message, primitive values are only aligned if
+    /// `alignment` is not zero
+    fn element(&self, element: &Element, alignment: usize, r: &mut R) -> Result<V> {
+        if let Some(scalar) = r.read_scalar(element, alignment)? {
+            return Ok(scalar.into());
+        }
+
+        match element {
+            Element::String(bound) => Ok(V::string(self.msg.read_string(*bound, r)?)),
+            Element::WString(bound) => Ok(V::string(Cow::Owned(self.msg.read_wstring(*bound, r)?))),
+            Element::Missing(path) => Err(missing_dependency(path)),
+            _ => unreachable!("Scalars are read above and messages are decoded in frames"),
+        }
+    }
+
+    /// Pushes the frame of a message, `nested` messages may be mapped to builtin values
+    fn push_message(&mut self, index: usize, nested: bool, r: &mut R) -> Result<()> {
+        let options = self.msg.options();
+        r.enter_message(options.max_depth)?;
+        let message = &self.msg.plan().messages[index];
+        let builtin_time = message
+            .builtin_time
+            .filter(|_| nested && options.map_builtin_time);
+
+        let extensibility = r.encapsulation().extensibility();
+        let end = match extensibility {
+            Extensibility::Final => None,
+            // Appendable messages may have been extended by members unknown to this definition
+            Extensibility::Appendable | Extensibility::Mutable => Some(r.read_dheader()?),
+        };
+        let mutable = extensibility == Extensibility::Mutable;
+        let values = match mutable || builtin_time.is_some() {
+            true => FrameValues::Indexed(message.fields.clone().map(|_| None).collect()),
+            false => FrameValues::Ordered(V::new_message(message.fields.len())),
+        };
+
+        self.stack.push(Frame::Message(MessageFrame {
+            index,
+            next: message.fields.start,
+            field: None,
+            values,
+            end,
+            mutable,
+            member_end: 0,
+            builtin_time,
+        }));
+        Ok(())
+    }
+
+    /// Reads the end of a message whose frame was popped off the stack
+    fn finish_message(
+        &self,
+        frame: MessageFrame<V, V::Fields>,
+        r: &mut R,
+    ) -> Result<Completed<V, V::Fields>> {
+        if let Some(end) = frame.end {
+            r.skip_to(end)?;
+        }
+        r.exit_message();
+
+        let values = match frame.values {
+            FrameValues::Ordered(values) => return Ok(Completed::Message(values)),
+            FrameValues::Indexed(values) => values,
+        };
+        if let Some(time) = frame.builtin_time {
+            if let (Some(Some(sec)), Some(Some(nanosec))) =
+                (values.get(time.sec), values.get(time.nanosec))
+            {
+                if let Some(scalar) =
+                    V::time_fields(sec, nanosec).and_then(|(sec, nanosec)| time.value(sec, nanosec))
+                {
+                    return Ok(Completed::Value(scalar.into()));
+                }
+            }
+        }
+
+        let mut message = V::new_message(values.len());
+        for (field, value) in self.msg.plan().fields(frame.index).iter().zip(values) {
+            let value = match (value, &field.constant) {
+                (Some(value), _) => value,
+                // Constants are not members of mutable messages
+                (None, Some(constant)) => V::constant(constant),
+                (None, None) => {
+                    return Err(invalid_member_error(
+                        field,
+                        r.position(),
+                        Some(r.encapsulation()),
+                        io::Error::other("Member is missing from the mutable message"),
+                    ))
+                }
+            };
+            V::insert(&mut message, &field.name, value);
+        }
+
+        Ok(Completed::Message(message))
+    }
+
+    /// Passes the value of a completed frame on to the frame below it
+    fn complete(
+        &mut self,
+        completed: Completed<V, V::Fields>,
+        r: &mut R,
+    ) -> Result<Option<Completed<V, V::Fields>>> {
+        if self.stack.is_empty() {
+            return Ok(Some(completed));
+        }
+        let value = match completed {
+            Completed::Message(values) => V::message(values),
+            Completed::Value(value) => value,
+        };
+        self.deliver(value, r)?;
+        Ok(None)
+    }
+
+    /// Adds the value of a field or array element to the frame on top of the stack
+    fn deliver(&mut self, value: V, r: &mut R) -> Result<()> {
+        let plan = self.msg.plan();
+        match self.stack.last_mut() {
+            Some(Frame::Message(frame)) => {
+                let field = frame.field.expect("A field is being decoded");
+                if frame.mutable {
+                    // A member that is shorter than announced can not corrupt the following ones
+                    r.skip_to(frame.member_end)?;
+                }
+                match &mut frame.values {
+                    FrameValues::Ordered(values) => {
+                        V::insert(values, &plan.fields[field].name, value)
+                    }
+                    FrameValues::Indexed(values) => {
+                        values[field - plan.messages[frame.index].fields.start] = Some(value)
+                    }
+                }
+                frame.field = None;
+            }
+            Some(Frame::Array(frame)) => {
+                frame.elements.push(value);
+                frame.current = None;
+                if frame.elements.len() == 1 && r.position() == frame.start {
+                    check_empty_elements(frame.length, r.remaining())?;
+                }
+            }
+            None => unreachable!("Values are passed to the frame below"),
+        }
+
+        Ok(())
+    }
+
+    /// Attributes an error to the fields and array elements that were being decoded
+    fn attribute(&self, mut e: Error, r: &R) -> Error {
+        let plan = self.msg.plan();
+        for frame in self.stack.iter().rev() {
+            e = match frame {
+                Frame::Message(MessageFrame {
+                    field: Some(field), ..
+                }) => member_error(&plan.fields[*field], r.position(), r.encapsulation(), e),
+                Frame::Array(ArrayFrame {
+                    current: Some(index),
+                    ..
+                }) => element_error(*index, e),
+                _ => e,
+            };
+        }
+        e
+    }
+}
@@ -0,0 +1,67 @@
+use super::DynamicMsg;
+use crate::json::write_message_json;
+use crate::{JsonOptions, MessagePath, MessageValue};
+use std::fmt::{self, Write};
+use std::hash::BuildHasher;
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Writes a message as JSON like [ToJson](crate::ToJson), guided by the message definition.
+    ///
+    /// Arrays of `uint8[]`, `char[]` and `byte[]` fields are written as bytes whether they were
+    /// decoded in bulk or element by element, so [BytesFormat::Base64](crate::BytesFormat) writes
+    /// every one of them as a string, including empty ones. Fields that are not part of the
+    /// message definition are written as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::{dynamic::DynamicMsg, BytesFormat, JsonOptions, Value};
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new(
+    ///     "package/msg/Blob",
+    ///     "uint8[] data\nuint8[] empty",
+    /// )
+    /// .expect("The message definition was invalid");
+    ///
+    /// let message = [
+    ///     ("data", Value::Array(vec![Value::U8(1), Value::U8(2)])),
+    ///     ("empty", Value::Array(vec![])),
+    /// ]
+    /// .into_iter()
+    /// .collect::<Value>()
+    /// .try_into_map()
+    /// .unwrap();
+    ///
+    /// let options = JsonOptions {
+    ///     bytes: BytesFormat::Base64,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     dynamic_message.to_json_with_options(&message, &options),
+    ///     r#"{"data":"AQI=","empty":""}"#
+    /// );
+    /// ```
+    pub fn write_json(
+        &self,
+        message: &MessageValue<S>,
+        w: &mut dyn Write,
+        options: &JsonOptions,
+    ) -> fmt::Result {
+        let dependency = |path: &MessagePath| self.dependency(path);
+        write_message_json(w, options, self.msg(), &dependency, message)
+    }
+
+    /// Returns the message as compact JSON with the default options, see
+    /// [write_json](Self::write_json).
+    pub fn to_json(&self, message: &MessageValue<S>) -> String {
+        self.to_json_with_options(message, &JsonOptions::default())
+    }
+
+    /// Returns the message as JSON with the provided options, see [write_json](Self::write_json).
+    pub fn to_json_with_options(&self, message: &MessageValue<S>, options: &JsonOptions) -> String {
+        let mut json = String::new();
+        self.write_json(message, &mut json, options)
+            .expect("Writing to a string does not fail");
+        json
+    }
+}
@@ -1,4 +1,52 @@
 #[cfg(feature = "decode")]
+mod action;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "decode")]
+mod borrowed;
+#[cfg(feature = "decode")]
+mod de;
+#[cfg(feature = "decode")]
 mod decode;
 #[cfg(feature = "decode")]
+mod encapsulation;
+#[cfg(feature = "decode")]
+mod encode;
+#[cfg(feature = "decode")]
+mod interpreter;
+#[cfg(feature = "decode")]
+mod json;
+#[cfg(feature = "decode")]
+mod options;
+#[cfg(feature = "decode")]
+mod plan;
+#[cfg(feature = "decode")]
+mod projection;
+#[cfg(feature = "decode")]
+mod reader;
+#[cfg(feature = "decode")]
+mod ser;
+#[cfg(feature = "decode")]
+mod service;
+#[cfg(feature = "decode")]
+mod stream;
+#[cfg(all(feature = "decode", feature = "yaml"))]
+mod yaml;
+#[cfg(feature = "decode")]
+pub use action::DynamicAction;
+#[cfg(feature = "arrow")]
+pub use arrow::RecordBatchBuilder;
+#[cfg(feature = "decode")]
+pub use borrowed::{MessageValueRef, PrimitiveArrayRef, ValueRef};
+#[cfg(feature = "decode")]
 pub use decode::DynamicMsg;
+#[cfg(feature = "decode")]
+pub use encapsulation::{Encapsulation, Endianness};
+#[cfg(feature = "decode")]
+pub use options::DecodeOptions;
+#[cfg(feature = "decode")]
+pub use projection::Projection;
+#[cfg(feature = "decode")]
+pub use service::DynamicSrv;
+#[cfg(feature = "decode")]
+pub use stream::{Framing, MessageStream, StreamedMessage};
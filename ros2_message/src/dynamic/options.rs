@@ -0,0 +1,48 @@
+/// Options that change how [DynamicMsg](super::DynamicMsg) decodes messages, see
+/// [DynamicMsg::with_options()](super::DynamicMsg::with_options()).
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::dynamic::DecodeOptions;
+///
+/// let options = DecodeOptions {
+///     map_builtin_time: true,
+///     ..Default::default()
+/// };
+/// assert!(!DecodeOptions::default().map_builtin_time);
+/// assert!(!DecodeOptions::default().validate_bounds);
+/// assert!(!DecodeOptions::default().primitive_arrays);
+/// assert_eq!(DecodeOptions::default().max_bytes, None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Decode `builtin_interfaces/Time` and `builtin_interfaces/Duration` messages to
+    /// [Value::Time](crate::Value::Time) and [Value::Duration](crate::Value::Duration)
+    /// instead of nested messages with `sec` and `nanosec` fields.
+    ///
+    /// Messages that the builtin values can not represent, like times with negative seconds,
+    /// are still decoded to nested messages.
+    pub map_builtin_time: bool,
+    /// Fail decoding if a bounded string or sequence is longer than its bound, e.g. a
+    /// `string<=10` with 11 bytes.
+    ///
+    /// The encoder always validates bounds.
+    pub validate_bounds: bool,
+    /// Decode arrays and sequences of numbers and `bool` to a single
+    /// [Value::PrimitiveArray](crate::Value::PrimitiveArray) instead of a
+    /// [Value::Array](crate::Value::Array) with one value per element.
+    ///
+    /// Their content is read in bulk, which is a lot faster for large arrays like the `data`
+    /// of a `sensor_msgs/Image` or `sensor_msgs/PointCloud2`.
+    pub primitive_arrays: bool,
+    /// Fail decoding if a payload is larger than this many bytes, including its encapsulation
+    /// header. Decoding stops reading as soon as the limit is exceeded.
+    pub max_bytes: Option<usize>,
+    /// Fail decoding if a sequence declares more elements than this, before any of them are
+    /// read.
+    pub max_sequence_length: Option<usize>,
+    /// Fail decoding if messages are nested deeper than this, where the top level message has
+    /// a depth of 1. Only recursive message definitions can nest messages arbitrarily deep.
+    pub max_depth: Option<usize>,
+}
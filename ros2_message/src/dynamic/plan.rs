@@ -0,0 +1,281 @@
+use super::reader::Scalar;
+use crate::{DataType, Duration, FieldCase, FieldInfo, MessagePath, Msg, Time, Value};
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::ops::Range;
+
+/// The schema of a message and all its dependencies, flattened into one list of fields that
+/// the decoder steps through like an instruction stream, see [DynamicMsg::new()](super::DynamicMsg::new()).
+///
+/// Every message is a range of this list and fields reference their nested messages by index,
+/// so decoding pushes a frame for every nested message instead of looking up dependencies.
+/// Everything that only depends on the schema is resolved once when the plan is compiled.
+#[derive(Clone, Debug)]
+pub(super) struct DecodePlan<S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    /// Plans of the root message, which is always the first one, and all its dependencies
+    pub(super) messages: Vec<MessagePlan>,
+    /// Plans of the fields of all messages, the fields of a message are next to each other
+    pub(super) fields: Vec<FieldPlan<S>>,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct MessagePlan {
+    pub(super) path: MessagePath,
+    /// Range of the fields of the message inside [DecodePlan::fields], in definition order
+    pub(super) fields: Range<usize>,
+    /// Indices of the fields that are members of the serialized message, which excludes
+    /// constants. The position inside the list is the XCDR2 member id.
+    pub(super) members: Vec<usize>,
+    /// Set for `builtin_interfaces/Time` and `builtin_interfaces/Duration`, which may be
+    /// mapped to builtin values
+    pub(super) builtin_time: Option<BuiltinTime>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct BuiltinTime {
+    pub(super) kind: BuiltinTimeKind,
+    /// Index of the `sec` field
+    pub(super) sec: usize,
+    /// Index of the `nanosec` field
+    pub(super) nanosec: usize,
+}
+
+impl BuiltinTime {
+    /// The builtin value of a message with these `sec` and `nanosec` fields, values out of
+    /// the range of the builtin value stay messages
+    pub(super) fn value(&self, sec: i32, nanosec: u32) -> Option<Scalar> {
+        match self.kind {
+            BuiltinTimeKind::Time => Some(Scalar::Time(Time {
+                sec: u32::try_from(sec).ok()?,
+                nsec: nanosec,
+            })),
+            BuiltinTimeKind::Duration => Some(Scalar::Duration(Duration {
+                sec,
+                nsec: i32::try_from(nanosec).ok()?,
+            })),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum BuiltinTimeKind {
+    Time,
+    Duration,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct FieldPlan<S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    pub(super) name: String,
+    /// Type of the field as it is written in the definition, like `int32[<=3]`
+    pub(super) datatype: String,
+    /// Value of constants, which are not serialized
+    pub(super) constant: Option<Value<S>>,
+    pub(super) element: Element,
+    pub(super) layout: Layout,
+    /// Alignment of the element type
+    pub(super) alignment: usize,
+    /// Whether the element type is primitive, collections of other types carry an XCDR2
+    /// `DHEADER`
+    pub(super) primitive: bool,
+}
+
+/// How often the element of a field is repeated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Layout {
+    /// The value is part of the definition and not serialized
+    Constant,
+    Unit,
+    Array(usize),
+    /// Sequence with an optional upper bound
+    Sequence(Option<usize>),
+}
+
+/// The type of a single value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum Element {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    WChar,
+    /// String with an optional bound
+    String(Option<usize>),
+    /// Wide string with an optional bound
+    WString(Option<usize>),
+    Time,
+    Duration,
+    /// Index of the nested message inside [DecodePlan::messages]
+    Message(usize),
+    /// A nested message that is not part of the dependencies, which fails when decoded
+    Missing(MessagePath),
+}
+
+impl Element {
+    /// Size of a single value inside an array, if it does not depend on the data.
+    ///
+    /// Values inside arrays are not padded individually, which keeps `Time` and `Duration`
+    /// aligned since they are a multiple of their alignment.
+    pub(super) fn fixed_size(&self) -> Option<usize> {
+        match self {
+            Element::Bool | Element::I8 | Element::U8 => Some(1),
+            Element::I16 | Element::U16 | Element::WChar => Some(2),
+            Element::I32 | Element::U32 | Element::F32 => Some(4),
+            Element::I64 | Element::U64 | Element::F64 | Element::Time | Element::Duration => {
+                Some(8)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DecodePlan<S> {
+    /// Compiles the plan of `msg`, nested messages are looked up in `dependencies`.
+    ///
+    /// Missing dependencies are not an error until a message that contains them is decoded.
+    pub(super) fn compile(msg: &Msg<S>, dependencies: &HashMap<MessagePath, Msg<S>, S>) -> Self {
+        // Messages are numbered in the order they are first referenced, which is also the
+        // order they are compiled in
+        let mut indices: HashMap<MessagePath, usize> = HashMap::new();
+        indices.insert(msg.path().clone(), 0);
+        let mut msgs = vec![msg];
+        let mut plan = DecodePlan {
+            messages: vec![],
+            fields: vec![],
+        };
+
+        while let Some(&msg) = msgs.get(plan.messages.len()) {
+            let start = plan.fields.len();
+            for field in msg.fields() {
+                let element = match field.datatype() {
+                    DataType::Bool => Element::Bool,
+                    DataType::I8(_) => Element::I8,
+                    DataType::I16 => Element::I16,
+                    DataType::I32 => Element::I32,
+                    DataType::I64 => Element::I64,
+                    DataType::U8(_) => Element::U8,
+                    DataType::U16 => Element::U16,
+                    DataType::U32 => Element::U32,
+                    DataType::U64 => Element::U64,
+                    DataType::F32 => Element::F32,
+                    DataType::F64 => Element::F64,
+                    DataType::WChar => Element::WChar,
+                    DataType::String => Element::String(None),
+                    DataType::BoundedString(bound) => Element::String(Some(*bound)),
+                    DataType::WString => Element::WString(None),
+                    DataType::BoundedWString(bound) => Element::WString(Some(*bound)),
+                    DataType::Time => Element::Time,
+                    DataType::Duration => Element::Duration,
+                    DataType::LocalMessage(name) => {
+                        nested(msg.path().peer(name), dependencies, &mut indices, &mut msgs)
+                    }
+                    DataType::GlobalMessage(path) => {
+                        nested(path.clone(), dependencies, &mut indices, &mut msgs)
+                    }
+                };
+                let layout = match field.case() {
+                    FieldCase::Const(_) => Layout::Constant,
+                    FieldCase::Unit | FieldCase::Default(_) => Layout::Unit,
+                    FieldCase::Array(length) => Layout::Array(*length),
+                    FieldCase::Vector => Layout::Sequence(None),
+                    FieldCase::BoundedVector(bound) => Layout::Sequence(Some(*bound)),
+                };
+                plan.fields.push(FieldPlan {
+                    name: field.name().to_owned(),
+                    datatype: field_type(field),
+                    constant: field.const_value().cloned(),
+                    element,
+                    layout,
+                    alignment: alignment(field.datatype()),
+                    primitive: super::decode::is_primitive(field.datatype()),
+                });
+            }
+
+            let fields = start..plan.fields.len();
+            plan.messages.push(MessagePlan {
+                path: msg.path().clone(),
+                members: (0..fields.len())
+                    .filter(|&index| plan.fields[start + index].layout != Layout::Constant)
+                    .collect(),
+                builtin_time: builtin_time(msg),
+                fields,
+            });
+        }
+
+        plan
+    }
+
+    /// Plans of the fields of the message at `index`
+    pub(super) fn fields(&self, index: usize) -> &[FieldPlan<S>] {
+        &self.fields[self.messages[index].fields.clone()]
+    }
+}
+
+/// Returns the element of a nested message, queueing the message if it has not been seen yet.
+fn nested<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    path: MessagePath,
+    dependencies: &'a HashMap<MessagePath, Msg<S>, S>,
+    indices: &mut HashMap<MessagePath, usize>,
+    msgs: &mut Vec<&'a Msg<S>>,
+) -> Element {
+    if let Some(index) = indices.get(&path) {
+        return Element::Message(*index);
+    }
+    let Some(dependency) = dependencies.get(&path) else {
+        return Element::Missing(path);
+    };
+
+    indices.insert(path, msgs.len());
+    msgs.push(dependency);
+    Element::Message(msgs.len() - 1)
+}
+
+/// Type of a field as it is written in a message definition, like `int32[<=3]`
+fn field_type<S: BuildHasher + Default + Clone + core::fmt::Debug>(field: &FieldInfo<S>) -> String {
+    let datatype = field.datatype();
+    match field.case() {
+        FieldCase::Unit | FieldCase::Const(_) | FieldCase::Default(_) => datatype.to_string(),
+        FieldCase::Vector => format!("{datatype}[]"),
+        FieldCase::Array(l) => format!("{datatype}[{l}]"),
+        FieldCase::BoundedVector(l) => format!("{datatype}[<={l}]"),
+    }
+}
+
+/// Alignment of a single value of the data type inside CDR payloads.
+pub(super) fn alignment(datatype: &DataType) -> usize {
+    match datatype {
+        DataType::Bool | DataType::I8(_) | DataType::U8(_) => 1,
+        DataType::I16 | DataType::U16 | DataType::WChar => 2,
+        DataType::I32 | DataType::U32 | DataType::F32 | DataType::Time | DataType::Duration => 4,
+        DataType::I64 | DataType::U64 | DataType::F64 => 8,
+        // Strings and nested messages start with a 4 byte length or their first member
+        _ => 4,
+    }
+}
+
+fn builtin_time<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    msg: &Msg<S>,
+) -> Option<BuiltinTime> {
+    let path = msg.path();
+    if path.package() != "builtin_interfaces" {
+        return None;
+    }
+    let kind = match path.name() {
+        "Time" => BuiltinTimeKind::Time,
+        "Duration" => BuiltinTimeKind::Duration,
+        _ => return None,
+    };
+    let field = |name| msg.fields().iter().position(|f| f.name() == name);
+
+    Some(BuiltinTime {
+        kind,
+        sec: field("sec")?,
+        nanosec: field("nanosec")?,
+    })
+}
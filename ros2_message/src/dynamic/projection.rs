@@ -0,0 +1,382 @@
+use super::decode::{
+    check_empty_elements, element_error, member_error, missing_dependency, ByteCounter,
+    MAX_PREALLOCATED_ELEMENTS,
+};
+use super::encapsulation::Extensibility;
+use super::plan::{Element, FieldPlan, Layout};
+use super::reader::CdrRead;
+use super::{DynamicMsg, Encapsulation};
+use crate::error::{Error, Result};
+use crate::{MessageValue, Value};
+use std::hash::BuildHasher;
+use std::io::{self, Read};
+
+/// A set of fields of a message that [DynamicMsg::decode_projected()] decodes, while skipping
+/// over all other fields. Create it with [DynamicMsg::projection()].
+///
+/// A projection is only valid for the message it was created for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Projection {
+    fields: Vec<Selection>,
+}
+
+/// What to decode of a field
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Selection {
+    Skip,
+    All,
+    /// Only some fields of a nested message, or of every message in an array
+    Fields(Vec<Selection>),
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Creates a projection that selects the fields at `paths`, where nested fields are
+    /// separated by dots like `header.stamp`.
+    ///
+    /// A path into an array of messages, like `transforms.child_frame_id`, selects the field
+    /// in every message of the array.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidFieldPath] if a path does not lead to a field of the message.
+    pub fn projection<P: AsRef<str>>(
+        &self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Projection> {
+        let plan = self.plan();
+        let mut root = vec![Selection::Skip; plan.messages[0].fields.len()];
+
+        for path in paths {
+            let path = path.as_ref();
+            let invalid = |reason: String| Error::InvalidFieldPath {
+                path: path.to_owned(),
+                reason,
+            };
+
+            let mut index = 0;
+            let mut selections = &mut root;
+            let mut names = path.split('.').peekable();
+            while let Some(name) = names.next() {
+                let fields = plan.fields(index);
+                let Some(field_index) = fields.iter().position(|f| f.name == name) else {
+                    return Err(invalid(format!(
+                        "`{}` has no field `{name}`",
+                        plan.messages[index].path
+                    )));
+                };
+                let selection = &mut selections[field_index];
+
+                if names.peek().is_none() {
+                    *selection = Selection::All;
+                    break;
+                }
+
+                match &fields[field_index].element {
+                    Element::Message(nested) => index = *nested,
+                    Element::Missing(path) => return Err(missing_dependency(path)),
+                    _ => {
+                        return Err(invalid(format!(
+                            "`{name}` is not a message and has no fields"
+                        )))
+                    }
+                }
+                match selection {
+                    // The whole message is selected already
+                    Selection::All => break,
+                    Selection::Skip => {
+                        *selection = Selection::Fields(vec![
+                            Selection::Skip;
+                            plan.messages[index].fields.len()
+                        ])
+                    }
+                    Selection::Fields(_) => {}
+                }
+                let Selection::Fields(nested) = selection else {
+                    unreachable!()
+                };
+                selections = nested;
+            }
+        }
+
+        Ok(Projection { fields: root })
+    }
+
+    /// Decodes only the fields selected by `projection` and returns them in the same structure
+    /// that [Self::decode()] does. Fields that are not selected are skipped without decoding
+    /// them and reading stops after the last selected field.
+    ///
+    /// Since the rest of the message is not read, trailing data is not detected like it is by
+    /// [Self::decode()]. Mutable XCDR2 messages are decoded completely before the fields are
+    /// selected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::DynamicMsg;
+    /// use ros2_message::Value;
+    ///
+    /// let msg_definition = r#"
+    /// builtin_interfaces/Time stamp
+    /// float32[] ranges
+    ///
+    /// ================================================================================
+    /// MSG: builtin_interfaces/Time
+    ///
+    /// int32 sec
+    /// uint32 nanosec
+    /// "#;
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/Scan", msg_definition)
+    ///     .expect("The message definition was invalid");
+    /// let projection = dynamic_message.projection(["stamp.sec"])
+    ///     .expect("The field does not exist");
+    /// let message = dynamic_message.decode_projected(&[0x00u8, 0x01, 0, 0, 157, 47, 136, 102, 42, 0, 0, 0, 0, 0, 0, 0][..], &projection)
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(message.len(), 1);
+    /// assert_eq!(message["stamp"].as_map().unwrap()["sec"], Value::I32(1720201117));
+    /// ```
+    pub fn decode_projected<R: Read>(
+        &self,
+        r: R,
+        projection: &Projection,
+    ) -> Result<MessageValue<S>> {
+        let mut r = ByteCounter::new(r, self.options().max_bytes);
+
+        let mut buf = [0, 0, 0, 0];
+        r.read_exact(&mut buf)?;
+
+        let Some(encapsulation) = Encapsulation::from_header(buf) else {
+            return Err(self.unsupported_encapsulation(buf, r.position()));
+        };
+        r.set_encapsulation(encapsulation);
+
+        self.decode_projected_inner(0, &projection.fields, true, &mut r)
+    }
+
+    // If `last` is set, everything after the last selected field is left unread
+    fn decode_projected_inner<R: Read>(
+        &self,
+        index: usize,
+        selections: &[Selection],
+        last: bool,
+        r: &mut ByteCounter<R>,
+    ) -> Result<MessageValue<S>> {
+        let extensibility = r.encapsulation().extensibility();
+        if extensibility == Extensibility::Mutable {
+            // Members of mutable messages may be serialized in any order
+            let values = self.decode_values::<_, Value<S>>(index, r)?;
+            return self.project_values(index, selections, values);
+        }
+
+        r.enter_message(self.options().max_depth)?;
+        let end = match extensibility {
+            Extensibility::Appendable => Some(r.read_dheader()?),
+            _ => None,
+        };
+        let values = self.decode_projected_members(index, selections, last, r)?;
+        if let (Some(end), false) = (end, last) {
+            r.skip_to(end)?;
+        }
+        r.exit_message();
+
+        Ok(values)
+    }
+
+    fn decode_projected_members<R: Read>(
+        &self,
+        index: usize,
+        selections: &[Selection],
+        last: bool,
+        r: &mut ByteCounter<R>,
+    ) -> Result<MessageValue<S>> {
+        let start = self.plan().messages[index].fields.start;
+        let fields = self.plan().fields(index);
+        let mut map = MessageValue::<S>::with_hasher(S::default());
+
+        let end = match last {
+            true => selections
+                .iter()
+                .rposition(|s| *s != Selection::Skip)
+                .map_or(0, |i| i + 1),
+            false => selections.len(),
+        };
+        for (field_index, selection) in selections[..end].iter().enumerate() {
+            let field = &fields[field_index];
+            let value = match selection {
+                Selection::Skip => {
+                    self.skip_member(field, r)
+                        .map_err(|e| member_error(field, r.position(), r.encapsulation(), e))?;
+                    continue;
+                }
+                Selection::All => self.decode_field::<_, Value<S>>(start + field_index, r)?,
+                Selection::Fields(nested) => {
+                    let last = last && field_index + 1 == end;
+                    self.decode_projected_field(field, nested, last, r)
+                        .map_err(|e| member_error(field, r.position(), r.encapsulation(), e))?
+                }
+            };
+            map.insert(field.name.clone(), value);
+        }
+
+        Ok(map)
+    }
+
+    // Decodes some fields of a nested message or every message in an array
+    fn decode_projected_field<R: Read>(
+        &self,
+        field: &FieldPlan<S>,
+        selections: &[Selection],
+        last: bool,
+        r: &mut ByteCounter<R>,
+    ) -> Result<Value<S>> {
+        let Element::Message(index) = field.element else {
+            unreachable!("Only messages have fields");
+        };
+
+        let array_length = match field.layout {
+            Layout::Array(length) => Some(length),
+            Layout::Sequence(_) => None,
+            _ => {
+                let values = self.decode_projected_inner(index, selections, last, r)?;
+                return Ok(Value::Message(values));
+            }
+        };
+        let array_length = self.read_array_header(field, array_length, r)?;
+
+        let mut values = Vec::with_capacity(array_length.min(MAX_PREALLOCATED_ELEMENTS));
+        for element in 0..array_length {
+            let start = r.position();
+            let message = self
+                .decode_projected_inner(index, selections, false, r)
+                .map_err(|e| element_error(element, e))?;
+            values.push(Value::Message(message));
+            if element == 0 && r.position() == start {
+                check_empty_elements(array_length, r.remaining())?;
+            }
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    // Selects fields of an already decoded message
+    fn project_values(
+        &self,
+        index: usize,
+        selections: &[Selection],
+        mut values: MessageValue<S>,
+    ) -> Result<MessageValue<S>> {
+        let fields = self.plan().fields(index);
+        let mut map = MessageValue::<S>::with_hasher(S::default());
+
+        for (field, selection) in fields.iter().zip(selections) {
+            let name = field.name.as_str();
+            let (Some(value), Selection::All | Selection::Fields(_)) =
+                (values.swap_remove(name), selection)
+            else {
+                continue;
+            };
+            let value = match (selection, &field.element, value) {
+                (Selection::Fields(nested), Element::Message(index), Value::Message(values)) => {
+                    Value::Message(self.project_values(*index, nested, values)?)
+                }
+                (Selection::Fields(nested), Element::Message(index), Value::Array(items)) => {
+                    Value::Array(
+                        items
+                            .into_iter()
+                            .map(|item| match item {
+                                Value::Message(values) => {
+                                    Ok(Value::Message(self.project_values(*index, nested, values)?))
+                                }
+                                item => Ok(item),
+                            })
+                            .collect::<Result<_>>()?,
+                    )
+                }
+                // Builtin time values have no fields to select
+                (_, _, value) => value,
+            };
+            map.insert(name.to_owned(), value);
+        }
+
+        Ok(map)
+    }
+
+    fn skip_member<R: Read>(&self, field: &FieldPlan<S>, r: &mut ByteCounter<R>) -> Result<()> {
+        let array_length = match field.layout {
+            Layout::Constant => return Ok(()),
+            Layout::Unit => return self.skip_element(&field.element, field.alignment, r),
+            Layout::Array(length) => Some(length),
+            Layout::Sequence(_) => None,
+        };
+
+        if r.encapsulation().is_xcdr2() && !field.primitive {
+            // The DHEADER of collections of non primitive types holds their size
+            let end = r.read_dheader()?;
+            r.skip_to(end)?;
+            return Ok(());
+        }
+        let array_length = self.read_array_header(field, array_length, r)?;
+
+        match field.element.fixed_size() {
+            Some(size) => {
+                let size = size
+                    .checked_mul(array_length)
+                    .ok_or_else(|| io::Error::other("The array is too large"))?;
+                r.skip(size)?;
+            }
+            None => {
+                for index in 0..array_length {
+                    self.skip_element(&field.element, 0, r)
+                        .map_err(|e| element_error(index, e))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // See DynamicMsg::decode_element
+    fn skip_element<R: Read>(
+        &self,
+        element: &Element,
+        alignment: usize,
+        r: &mut ByteCounter<R>,
+    ) -> Result<()> {
+        match element {
+            Element::String(_) => {
+                r.align_to(4)?;
+                let len = r.read_u32()? as usize;
+                r.skip(len)?;
+            }
+            Element::WString(_) => {
+                r.align_to(4)?;
+                let len = r.read_u32()? as usize;
+                r.skip(2 * len)?;
+            }
+            Element::Message(index) => {
+                match r.encapsulation().extensibility() {
+                    Extensibility::Final => {
+                        r.enter_message(self.options().max_depth)?;
+                        for field in self.plan().fields(*index) {
+                            self.skip_member(field, r)?;
+                        }
+                        r.exit_message();
+                    }
+                    // The DHEADER holds the size of the whole message
+                    Extensibility::Appendable | Extensibility::Mutable => {
+                        let end = r.read_dheader()?;
+                        r.skip_to(end)?;
+                    }
+                }
+            }
+            Element::Missing(path) => return Err(missing_dependency(path)),
+            element => {
+                r.align_to(alignment)?;
+                r.skip(element.fixed_size().unwrap_or_default())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use super::plan::Element;
+use super::{Encapsulation, Endianness};
+use crate::{Duration, Time, Value};
+use std::borrow::Cow;
+use std::hash::BuildHasher;
+use std::io;
+
+/// Reads CDR encoded data, implemented by the `ByteCounter` of [DynamicMsg::decode()] for any
+/// reader and the `SliceReader` of [DynamicMsg::decode_borrowed()] for byte slices, so that both
+/// read values and the framing of XCDR2 the same way.
+///
+/// Positions include the 4 bytes of the encapsulation header, alignment is relative to the end
+/// of it.
+///
+/// [DynamicMsg::decode()]: super::DynamicMsg::decode()
+/// [DynamicMsg::decode_borrowed()]: super::DynamicMsg::decode_borrowed()
+pub(super) trait CdrRead<'de> {
+    fn encapsulation(&self) -> Encapsulation;
+
+    /// Number of bytes read so far
+    fn position(&self) -> usize;
+
+    /// Number of bytes that can still be read, `None` if the end of the data is not known
+    fn remaining(&self) -> Option<usize>;
+
+    /// Nesting depth of the message that is being decoded
+    fn depth_mut(&mut self) -> &mut usize;
+
+    fn read_u8(&mut self) -> io::Result<u8>;
+
+    fn read_u16(&mut self) -> io::Result<u16>;
+
+    fn read_u32(&mut self) -> io::Result<u32>;
+
+    fn read_u64(&mut self) -> io::Result<u64>;
+
+    /// Borrows or reads the next `length` bytes
+    fn read_bytes(&mut self, length: usize) -> io::Result<Cow<'de, [u8]>>;
+
+    /// Borrows or reads the next `length` numbers
+    fn read_numbers<T: Number>(&mut self, length: usize) -> io::Result<Cow<'de, [T]>>;
+
+    fn skip(&mut self, size: usize) -> io::Result<()>;
+
+    /// Puts back a `u32` that was just read, so that it is read again
+    fn unread_u32(&mut self, n: u32);
+
+    fn read_i8(&mut self) -> io::Result<i8> {
+        self.read_u8().map(|n| n as i8)
+    }
+
+    fn read_i16(&mut self) -> io::Result<i16> {
+        self.read_u16().map(|n| n as i16)
+    }
+
+    fn read_i32(&mut self) -> io::Result<i32> {
+        self.read_u32().map(|n| n as i32)
+    }
+
+    fn read_i64(&mut self) -> io::Result<i64> {
+        self.read_u64().map(|n| n as i64)
+    }
+
+    fn read_f32(&mut self) -> io::Result<f32> {
+        self.read_u32().map(f32::from_bits)
+    }
+
+    fn read_f64(&mut self) -> io::Result<f64> {
+        self.read_u64().map(f64::from_bits)
+    }
+
+    /// Skip the padding so that the next read will be aligned to `size` bytes
+    fn align_to(&mut self, size: usize) -> io::Result<()> {
+        if size == 0 {
+            return Ok(()); // No alignment needed
+        }
+        let size = size.min(self.encapsulation().max_alignment());
+
+        let cur_align = (self.position() - 4) % size;
+        if cur_align > 0 {
+            self.skip(size - cur_align)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads an XCDR2 `DHEADER` and returns the position at which the delimited section ends
+    fn read_dheader(&mut self) -> io::Result<usize> {
+        self.align_to(4)?;
+        let size = self.read_u32()? as usize;
+        Ok(self.position() + size)
+    }
+
+    /// Skip to the end of a delimited section
+    fn skip_to(&mut self, end: usize) -> io::Result<()> {
+        let Some(remaining) = end.checked_sub(self.position()) else {
+            return Err(io::Error::other(format!(
+                "Read {} bytes past the end of a delimited section at byte {}",
+                self.position() - end,
+                end
+            )));
+        };
+        self.skip(remaining)
+    }
+
+    /// Reads the EMHEADER of a member of a mutable message and returns the member id along
+    /// with the position at which the member ends, see DDS-XTypes 7.4.3.5.3
+    fn read_emheader(&mut self) -> io::Result<(usize, usize)> {
+        self.align_to(4)?;
+        let emheader = self.read_u32()?;
+        let length_code = (emheader >> 28) & 0b111;
+        let member_id = (emheader & 0x0fff_ffff) as usize;
+
+        let size = match length_code {
+            0..=3 => 1 << length_code,
+            4 => self.read_u32()? as usize,
+            // Length codes 5 to 7 reuse the first 4 bytes of the member as NEXTINT, which is
+            // not included in the size
+            _ => {
+                let next_int = self.read_u32()?;
+                self.unread_u32(next_int);
+                let size = match length_code {
+                    5 => next_int as usize,
+                    6 => 4 * next_int as usize,
+                    _ => 8 * next_int as usize,
+                };
+                4 + size
+            }
+        };
+
+        Ok((member_id, self.position() + size))
+    }
+
+    /// Counts a nested message, which fails if messages are nested deeper than `max_depth`
+    fn enter_message(&mut self, max_depth: Option<usize>) -> io::Result<()> {
+        let depth = self.depth_mut();
+        *depth += 1;
+        match max_depth {
+            Some(max) if *depth > max => Err(io::Error::other(format!(
+                "The message is nested {depth} levels deep, which exceeds the limit of {max}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn exit_message(&mut self) {
+        *self.depth_mut() -= 1;
+    }
+
+    /// Reads a single value of a number, `bool` or ROS 1 time element, which is only aligned
+    /// if `alignment` is not zero. Returns `None` for strings and messages.
+    fn read_scalar(&mut self, element: &Element, alignment: usize) -> io::Result<Option<Scalar>> {
+        if matches!(
+            element,
+            Element::String(_) | Element::WString(_) | Element::Message(_) | Element::Missing(_)
+        ) {
+            return Ok(None);
+        }
+        self.align_to(alignment)?;
+
+        let scalar = match element {
+            Element::Bool => Scalar::Bool(self.read_u8()? != 0),
+            Element::I8 => Scalar::I8(self.read_i8()?),
+            Element::I16 => Scalar::I16(self.read_i16()?),
+            Element::I32 => Scalar::I32(self.read_i32()?),
+            Element::I64 => Scalar::I64(self.read_i64()?),
+            Element::U8 => Scalar::U8(self.read_u8()?),
+            Element::U16 | Element::WChar => Scalar::U16(self.read_u16()?),
+            Element::U32 => Scalar::U32(self.read_u32()?),
+            Element::U64 => Scalar::U64(self.read_u64()?),
+            Element::F32 => Scalar::F32(self.read_f32()?),
+            Element::F64 => Scalar::F64(self.read_f64()?),
+            Element::Time => Scalar::Time(Time {
+                sec: self.read_u32()?,
+                nsec: self.read_u32()?,
+            }),
+            Element::Duration => Scalar::Duration(Duration {
+                sec: self.read_i32()?,
+                nsec: self.read_i32()?,
+            }),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(scalar))
+    }
+}
+
+/// A single value that is read as a whole, see [CdrRead::read_scalar]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Scalar {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Time(Time),
+    Duration(Duration),
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<Scalar> for Value<S> {
+    fn from(scalar: Scalar) -> Self {
+        match scalar {
+            Scalar::Bool(v) => Value::Bool(v),
+            Scalar::I8(v) => Value::I8(v),
+            Scalar::I16(v) => Value::I16(v),
+            Scalar::I32(v) => Value::I32(v),
+            Scalar::I64(v) => Value::I64(v),
+            Scalar::U8(v) => Value::U8(v),
+            Scalar::U16(v) => Value::U16(v),
+            Scalar::U32(v) => Value::U32(v),
+            Scalar::U64(v) => Value::U64(v),
+            Scalar::F32(v) => Value::F32(v),
+            Scalar::F64(v) => Value::F64(v),
+            Scalar::Time(v) => Value::Time(v),
+            Scalar::Duration(v) => Value::Duration(v),
+        }
+    }
+}
+
+/// Numbers that are read in bulk by [CdrRead::read_numbers]
+pub(super) trait Number: bytemuck::Pod {
+    /// Reverses the byte order of the number
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_number {
+    ($($ty:ty),*) => {
+        $(
+            impl Number for $ty {
+                fn swap_bytes(self) -> Self {
+                    <$ty>::swap_bytes(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(i16, i32, i64, u16, u32, u64);
+
+impl Number for f32 {
+    fn swap_bytes(self) -> Self {
+        f32::from_bits(self.to_bits().swap_bytes())
+    }
+}
+
+impl Number for f64 {
+    fn swap_bytes(self) -> Self {
+        f64::from_bits(self.to_bits().swap_bytes())
+    }
+}
+
+/// Whether numbers in the byte order of `endianness` can be used as they are
+pub(super) fn is_native(endianness: Endianness) -> bool {
+    match endianness {
+        Endianness::Little => cfg!(target_endian = "little"),
+        Endianness::Big => cfg!(target_endian = "big"),
+    }
+}
+
+/// Converts numbers that were copied as they are into the byte order of the machine
+pub(super) fn to_native<T: Number>(values: &mut [T], endianness: Endianness) {
+    if !is_native(endianness) {
+        for value in values {
+            *value = value.swap_bytes();
+        }
+    }
+}
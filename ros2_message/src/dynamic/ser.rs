@@ -0,0 +1,631 @@
+use super::{DynamicMsg, Encapsulation};
+use crate::error::{Error, Result};
+use crate::{
+    DataType, Duration, FieldCase, I8Variant, MessagePath, MessageValue, Msg, PrimitiveArray, Time,
+    Value,
+};
+use serde::ser::{self, Impossible, Serialize};
+use std::hash::BuildHasher;
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Serializes any type implementing [Serialize] into CDR bytes, this is the inverse
+    /// operation of [Self::deserialize()].
+    ///
+    /// The value has to serialize as a struct or map with an entry for every field of the
+    /// message definition, constants can be left out. Values are checked against the types of
+    /// the fields: integers are accepted for all numeric fields they fit into, sequences for
+    /// arrays and bytes for `uint8[]` and `byte[]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::EncodingError] naming the field whose value does not match the message
+    /// definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::DynamicMsg;
+    /// use serde_derive::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Reading {
+    ///     id: u8,
+    ///     values: Vec<f32>,
+    /// }
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new(
+    ///     "package/msg/Reading",
+    ///     "uint16 id\nfloat32[] values",
+    /// )
+    /// .expect("The message definition was invalid");
+    ///
+    /// let bytes = dynamic_message
+    ///     .serialize(&Reading { id: 7, values: vec![1.0] })
+    ///     .expect("The value does not match the message definition");
+    ///
+    /// assert_eq!(bytes, [0x00, 0x01, 0, 0, 7, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0x80, 0x3f]);
+    /// ```
+    pub fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        self.serialize_with_encapsulation(value, Encapsulation::default())
+    }
+
+    /// Same as [Self::serialize()], but writes the payload with the provided encapsulation
+    /// instead of little endian CDR.
+    pub fn serialize_with_encapsulation<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+        encapsulation: Encapsulation,
+    ) -> Result<Vec<u8>> {
+        let value = value.serialize(ValueSerializer {
+            msg: self,
+            parent: self.msg().path(),
+            expected: Expected::Message(self.msg()),
+        })?;
+        let Value::Message(message) = value else {
+            return Err(Error::Serialization("expected a message".to_owned()));
+        };
+
+        self.encode_with_encapsulation(&message, encapsulation)
+    }
+}
+
+/// What the serialized value has to match
+#[derive_where::derive_where(Clone, Copy)]
+enum Expected<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    /// A value of a field, or an array of them if the field is an array
+    Field { datatype: &'a DataType, array: bool },
+    /// A message with the given definition
+    Message(&'a Msg<S>),
+}
+
+/// Serializes a value into a [Value] matching the expected type
+struct ValueSerializer<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    /// The message containing the value, which local message types are resolved against
+    parent: &'a MessagePath,
+    expected: Expected<'a, S>,
+}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> ValueSerializer<'a, S> {
+    fn mismatch(&self) -> Error {
+        match self.expected {
+            Expected::Field {
+                datatype,
+                array: false,
+            } => Error::Serialization(format!("expected a value of type `{datatype}`")),
+            Expected::Field {
+                datatype,
+                array: true,
+            } => Error::Serialization(format!("expected an array of `{datatype}`")),
+            Expected::Message(msg) => {
+                Error::Serialization(format!("expected a message of type `{}`", msg.path()))
+            }
+        }
+    }
+
+    /// Returns the single value the serializer expects
+    fn datatype(&self) -> Result<&'a DataType> {
+        match self.expected {
+            Expected::Field {
+                datatype,
+                array: false,
+            } => Ok(datatype),
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn integer(self, v: i128) -> Result<Value<S>> {
+        let out_of_range = || {
+            Error::Serialization(format!(
+                "{v} is out of range for a value of type `{}`",
+                self.datatype().unwrap()
+            ))
+        };
+
+        Ok(match self.datatype()? {
+            DataType::I8(_) => Value::I8(v.try_into().map_err(|_| out_of_range())?),
+            DataType::I16 => Value::I16(v.try_into().map_err(|_| out_of_range())?),
+            DataType::I32 => Value::I32(v.try_into().map_err(|_| out_of_range())?),
+            DataType::I64 => Value::I64(v.try_into().map_err(|_| out_of_range())?),
+            DataType::U8(_) => Value::U8(v.try_into().map_err(|_| out_of_range())?),
+            DataType::U16 | DataType::WChar => {
+                Value::U16(v.try_into().map_err(|_| out_of_range())?)
+            }
+            DataType::U32 => Value::U32(v.try_into().map_err(|_| out_of_range())?),
+            DataType::U64 => Value::U64(v.try_into().map_err(|_| out_of_range())?),
+            DataType::F32 => Value::F32(v as f32),
+            DataType::F64 => Value::F64(v as f64),
+            _ => return Err(self.mismatch()),
+        })
+    }
+
+    fn fields(self) -> Result<FieldsSerializer<'a, S>> {
+        let kind = match self.expected {
+            Expected::Message(msg) => Composite::Message(msg),
+            Expected::Field { array: true, .. } => return Err(self.mismatch()),
+            Expected::Field { datatype, .. } => match datatype {
+                DataType::Time => Composite::Time,
+                DataType::Duration => Composite::Duration,
+                DataType::LocalMessage(name) => {
+                    Composite::Message(self.msg.get_dependency(&self.parent.peer(name))?)
+                }
+                DataType::GlobalMessage(path) => Composite::Message(self.msg.get_dependency(path)?),
+                _ => return Err(self.mismatch()),
+            },
+        };
+
+        Ok(FieldsSerializer {
+            msg: self.msg,
+            kind,
+            values: MessageValue::with_hasher(S::default()),
+            key: None,
+        })
+    }
+
+    fn elements(self, len: Option<usize>) -> Result<ElementsSerializer<'a, S>> {
+        let Expected::Field {
+            datatype,
+            array: true,
+        } = self.expected
+        else {
+            return Err(self.mismatch());
+        };
+
+        Ok(ElementsSerializer {
+            element: ValueSerializer {
+                msg: self.msg,
+                parent: self.parent,
+                expected: Expected::Field {
+                    datatype,
+                    array: false,
+                },
+            },
+            values: Vec::with_capacity(len.unwrap_or_default().min(4096)),
+        })
+    }
+}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> ser::Serializer
+    for ValueSerializer<'a, S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+    type SerializeSeq = ElementsSerializer<'a, S>;
+    type SerializeTuple = ElementsSerializer<'a, S>;
+    type SerializeTupleStruct = ElementsSerializer<'a, S>;
+    type SerializeTupleVariant = Impossible<Value<S>, Error>;
+    type SerializeMap = FieldsSerializer<'a, S>;
+    type SerializeStruct = FieldsSerializer<'a, S>;
+    type SerializeStructVariant = Impossible<Value<S>, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value<S>> {
+        match self.datatype()? {
+            DataType::Bool => Ok(Value::Bool(v)),
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value<S>> {
+        self.integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value<S>> {
+        self.integer(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value<S>> {
+        match i128::try_from(v) {
+            Ok(v) => self.integer(v),
+            Err(_) => Err(Error::Serialization(format!(
+                "{v} is out of range for a value of type `{}`",
+                self.datatype()?
+            ))),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value<S>> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value<S>> {
+        match self.datatype()? {
+            DataType::F32 => Ok(Value::F32(v as f32)),
+            DataType::F64 => Ok(Value::F64(v)),
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value<S>> {
+        match self.datatype()? {
+            DataType::String
+            | DataType::BoundedString(_)
+            | DataType::WString
+            | DataType::BoundedWString(_) => Ok(Value::String(v.to_string())),
+            // Characters outside of the basic multilingual plane need two UTF-16 code units
+            DataType::WChar if v.len_utf16() == 1 => {
+                let mut unit = [0];
+                v.encode_utf16(&mut unit);
+                Ok(Value::U16(unit[0]))
+            }
+            DataType::U8(_) if v.is_ascii() => Ok(Value::U8(v as u8)),
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value<S>> {
+        match self.datatype()? {
+            DataType::String
+            | DataType::BoundedString(_)
+            | DataType::WString
+            | DataType::BoundedWString(_) => Ok(Value::String(v.to_owned())),
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value<S>> {
+        match self.expected {
+            Expected::Field {
+                datatype: DataType::U8(_),
+                array: true,
+            } => Ok(Value::PrimitiveArray(PrimitiveArray::U8(v.to_vec()))),
+            Expected::Field {
+                datatype: DataType::I8(I8Variant::Byte),
+                array: true,
+            } => Ok(Value::PrimitiveArray(PrimitiveArray::I8(
+                v.iter().map(|&b| b as i8).collect(),
+            ))),
+            _ => Err(self.mismatch()),
+        }
+    }
+
+    fn serialize_none(self) -> Result<Value<S>> {
+        Err(self.mismatch())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value<S>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value<S>> {
+        Err(self.mismatch())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value<S>> {
+        Err(self.mismatch())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Value<S>> {
+        Err(self.mismatch())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value<S>> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Value<S>> {
+        Err(self.mismatch())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.elements(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.elements(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.elements(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(self.mismatch())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.fields()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.fields()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(self.mismatch())
+    }
+}
+
+/// Serializes the elements of an array or sequence
+struct ElementsSerializer<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    element: ValueSerializer<'a, S>,
+    values: Vec<Value<S>>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ElementsSerializer<'_, S> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let index = self.values.len();
+        let element = ValueSerializer {
+            msg: self.element.msg,
+            parent: self.element.parent,
+            expected: self.element.expected,
+        };
+        let value = value.serialize(element).map_err(|e| match e {
+            Error::Serialization(reason) => {
+                Error::Serialization(format!("element {index}: {reason}"))
+            }
+            e => e,
+        })?;
+        self.values.push(value);
+        Ok(())
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeSeq
+    for ElementsSerializer<'_, S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(Value::Array(self.values))
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeTuple
+    for ElementsSerializer<'_, S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(Value::Array(self.values))
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeTupleStruct
+    for ElementsSerializer<'_, S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(Value::Array(self.values))
+    }
+}
+
+/// Values made up of named fields
+enum Composite<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    Message(&'a Msg<S>),
+    Time,
+    Duration,
+}
+
+/// Serializes the fields of a message, or of the builtin time types
+struct FieldsSerializer<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    kind: Composite<'a, S>,
+    values: MessageValue<S>,
+    /// Name of the map entry whose value is serialized next
+    key: Option<String>,
+}
+
+static STRING: DataType = DataType::String;
+static U32: DataType = DataType::U32;
+static I32: DataType = DataType::I32;
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldsSerializer<'_, S> {
+    fn field<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<()> {
+        let value = match self.kind {
+            Composite::Message(msg) => {
+                let Some(field) = msg.fields().iter().find(|field| field.name() == name) else {
+                    return Err(Error::EncodingError {
+                        msg: msg.path().clone(),
+                        field: name.to_owned(),
+                        reason: "the field does not exist in the message definition".to_owned(),
+                    });
+                };
+                if field.is_constant() {
+                    return Ok(());
+                }
+
+                let array = !matches!(field.case(), FieldCase::Unit | FieldCase::Default(_));
+                let serializer = ValueSerializer {
+                    msg: self.msg,
+                    parent: msg.path(),
+                    expected: Expected::Field {
+                        datatype: field.datatype(),
+                        array,
+                    },
+                };
+                // Errors of nested messages already name the field they failed at
+                value.serialize(serializer).map_err(|e| match e {
+                    Error::Serialization(reason) => Error::EncodingError {
+                        msg: msg.path().clone(),
+                        field: name.to_owned(),
+                        reason,
+                    },
+                    e => e,
+                })?
+            }
+            Composite::Time | Composite::Duration => {
+                let datatype = match (&self.kind, name) {
+                    (Composite::Time, "sec" | "nsec") => &U32,
+                    (Composite::Duration, "sec" | "nsec") => &I32,
+                    _ => {
+                        return Err(Error::Serialization(format!(
+                            "`{name}` is not a field of a time value"
+                        )))
+                    }
+                };
+                value
+                    .serialize(ValueSerializer {
+                        msg: self.msg,
+                        parent: self.msg.msg().path(),
+                        expected: Expected::Field {
+                            datatype,
+                            array: false,
+                        },
+                    })
+                    .map_err(|e| match e {
+                        Error::Serialization(reason) => {
+                            Error::Serialization(format!("{name}: {reason}"))
+                        }
+                        e => e,
+                    })?
+            }
+        };
+
+        self.values.insert(name.to_owned(), value);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Value<S>> {
+        let mut take = |name: &str| {
+            self.values
+                .swap_remove(name)
+                .ok_or_else(|| Error::Serialization(format!("missing field `{name}`")))
+        };
+
+        match self.kind {
+            Composite::Message(_) => Ok(Value::Message(self.values)),
+            Composite::Time => match (take("sec")?, take("nsec")?) {
+                (Value::U32(sec), Value::U32(nsec)) => Ok(Value::Time(Time { sec, nsec })),
+                _ => unreachable!("time fields are serialized as `uint32`"),
+            },
+            Composite::Duration => match (take("sec")?, take("nsec")?) {
+                (Value::I32(sec), Value::I32(nsec)) => Ok(Value::Duration(Duration { sec, nsec })),
+                _ => unreachable!("duration fields are serialized as `int32`"),
+            },
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeStruct
+    for FieldsSerializer<'_, S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        self.finish()
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeMap
+    for FieldsSerializer<'_, S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = key.serialize(ValueSerializer {
+            msg: self.msg,
+            parent: self.msg.msg().path(),
+            expected: Expected::Field {
+                datatype: &STRING,
+                array: false,
+            },
+        });
+        match key {
+            Ok(Value::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error::Serialization(
+                "field names must be strings".to_owned(),
+            )),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let Some(key) = self.key.take() else {
+            return Err(Error::Serialization(
+                "a value was serialized before its key".to_owned(),
+            ));
+        };
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        self.finish()
+    }
+}
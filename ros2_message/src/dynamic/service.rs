@@ -0,0 +1,162 @@
+use super::{DecodeOptions, DynamicMsg};
+use crate::error::Result;
+use crate::message_path::{EVENT_SUFFIX, REQUEST_SUFFIX, RESPONSE_SUFFIX};
+use crate::registry::BUILTIN_MESSAGES;
+use crate::srv::SERVICE_EVENT_INFO;
+use crate::{MessagePath, MessageValue, Msg, ServicePath, Srv};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::hash::{BuildHasher, RandomState};
+use std::io::Read;
+
+/// A dynamic service provides decoders for the request, response and event messages of a ROS2
+/// service at runtime, just like [DynamicMsg] does for messages. See [Self::new()] for more.
+#[derive(Clone, Debug)]
+pub struct DynamicSrv<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
+    srv: Srv<S>,
+    request: DynamicMsg<S>,
+    response: DynamicMsg<S>,
+    event: DynamicMsg<S>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicSrv<S> {
+    /// Create a new `DynamicSrv<S>` by parsing its service definition, which is the content of
+    /// the `srv` file followed by the definitions of all messages it depends upon, in the same
+    /// format that [DynamicMsg::new()] takes.
+    ///
+    /// The service name may be given as `package/srv/Name` or `package/Name`. Names of the
+    /// request, response and event messages like `package/srv/Name_Event`, which rosbag2 records
+    /// service events under, are accepted as well.
+    ///
+    /// The `service_msgs/ServiceEventInfo` and `builtin_interfaces/Time` messages of service
+    /// events don't need to be part of the definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::DynamicSrv;
+    /// use ros2_message::Value;
+    ///
+    /// let srv_definition = r#"
+    /// int64 a
+    /// int64 b
+    /// ---
+    /// int64 sum
+    /// "#;
+    /// let dynamic_service = DynamicSrv::<std::hash::RandomState>::new("package/srv/AddTwoInts_Event", srv_definition)
+    ///     .expect("The service definition was invalid");
+    ///
+    /// let response = dynamic_service.decode_response(&[0x00u8, 0x01, 0, 0, 42, 0, 0, 0, 0, 0, 0, 0][..])
+    ///     .expect("The supplied bytes do not match the service definition");
+    /// assert_eq!(response["sum"], Value::I64(42));
+    /// ```
+    pub fn new(service_name: &str, service_definition: &str) -> Result<Self> {
+        let service_name = strip_suffixes(
+            service_name,
+            &[REQUEST_SUFFIX, RESPONSE_SUFFIX, EVENT_SUFFIX],
+        );
+        let (service_src, dependencies) =
+            DynamicMsg::split_definition(service_name, service_definition)?;
+        let srv = Srv::new(service_name.try_into()?, service_src)?;
+
+        Ok(Self::from_srv(srv, dependencies.into_values()))
+    }
+
+    /// Create a new `DynamicSrv<S>` from an already parsed service and all messages it depends
+    /// upon, directly or indirectly.
+    ///
+    /// Just like with [Self::new()], the messages of service events are added if missing.
+    pub fn from_srv(srv: Srv<S>, dependencies: impl IntoIterator<Item = Msg<S>>) -> Self {
+        let mut dependencies = collect_dependencies(dependencies, &[SERVICE_EVENT_INFO]);
+        for msg in [srv.request(), srv.response()] {
+            dependencies.insert(msg.path().clone(), msg.clone());
+        }
+
+        DynamicSrv {
+            request: DynamicMsg::from_parts(srv.request().clone(), dependencies.clone()),
+            response: DynamicMsg::from_parts(srv.response().clone(), dependencies.clone()),
+            event: DynamicMsg::from_parts(srv.event().clone(), dependencies),
+            srv,
+        }
+    }
+
+    /// Replaces the [DecodeOptions] used when decoding requests, responses and events.
+    pub fn with_options(self, options: DecodeOptions) -> Self {
+        DynamicSrv {
+            request: self.request.with_options(options.clone()),
+            response: self.response.with_options(options.clone()),
+            event: self.event.with_options(options),
+            srv: self.srv,
+        }
+    }
+
+    /// Returns the underlying ROS2 service definition
+    pub fn srv(&self) -> &Srv<S> {
+        &self.srv
+    }
+
+    /// Returns the path of the service
+    pub fn path(&self) -> &ServicePath {
+        self.srv.path()
+    }
+
+    /// Returns the decoder of the request message
+    pub fn request(&self) -> &DynamicMsg<S> {
+        &self.request
+    }
+
+    /// Returns the decoder of the response message
+    pub fn response(&self) -> &DynamicMsg<S> {
+        &self.response
+    }
+
+    /// Returns the decoder of the event message, see [Srv::event()]
+    pub fn event(&self) -> &DynamicMsg<S> {
+        &self.event
+    }
+
+    /// Decodes a request payload, see [DynamicMsg::decode()].
+    pub fn decode_request<R: Read>(&self, r: R) -> Result<MessageValue<S>> {
+        self.request.decode(r)
+    }
+
+    /// Decodes a response payload, see [DynamicMsg::decode()].
+    pub fn decode_response<R: Read>(&self, r: R) -> Result<MessageValue<S>> {
+        self.response.decode(r)
+    }
+
+    /// Decodes a service event payload, see [DynamicMsg::decode()].
+    ///
+    /// The `request` and `response` fields of events are sequences that hold at most one
+    /// message, depending on the kind of event.
+    pub fn decode_event<R: Read>(&self, r: R) -> Result<MessageValue<S>> {
+        self.event.decode(r)
+    }
+}
+
+/// Strips the first matching suffix from `name`.
+pub(super) fn strip_suffixes<'a>(name: &'a str, suffixes: &[&str]) -> &'a str {
+    suffixes
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(name)
+}
+
+/// Maps `dependencies` to their paths and adds the builtin messages as well as the `derived`
+/// messages that are part of the derived types, unless a dependency defines them already.
+pub(super) fn collect_dependencies<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    dependencies: impl IntoIterator<Item = Msg<S>>,
+    derived: &[(&str, &str)],
+) -> HashMap<MessagePath, Msg<S>, S> {
+    let mut dependencies: HashMap<MessagePath, Msg<S>, S> = dependencies
+        .into_iter()
+        .map(|msg| (msg.path().clone(), msg))
+        .collect();
+    for (path, source) in BUILTIN_MESSAGES.iter().chain(derived) {
+        let path = MessagePath::try_from(*path).expect("Invalid builtin message path");
+        dependencies.entry(path).or_insert_with_key(|path| {
+            Msg::new(path.clone(), source).expect("Invalid builtin message definition")
+        });
+    }
+    dependencies
+}
@@ -0,0 +1,206 @@
+use super::decode::ByteCounter;
+use super::reader::CdrRead;
+use super::{DynamicMsg, Encapsulation, Endianness};
+use crate::error::Result;
+use crate::{MessageValue, Value};
+use byteorder::{ReadBytesExt, BE, LE};
+use std::hash::BuildHasher;
+use std::io::{self, Read};
+
+/// How the messages of a stream are separated, see [DynamicMsg::decode_stream()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Framing {
+    /// Payloads follow each other directly. Every payload may end with the padding that the
+    /// options of its encapsulation header declare, as defined by DDS-XTypes 7.6.3.1.2.
+    Concatenated,
+    /// Every payload is prefixed by its size as a `uint32` in the given byte order, the size
+    /// includes the encapsulation header of the payload.
+    LengthPrefixed(Endianness),
+}
+
+/// A message decoded from a stream along with its position inside the stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamedMessage<S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    /// The decoded message, like it is returned by [DynamicMsg::decode()]
+    pub message: MessageValue<S>,
+    /// The encapsulation declared by the payload header
+    pub encapsulation: Encapsulation,
+    /// Position of the first byte of the message inside the stream, including its length prefix
+    pub offset: usize,
+    /// Number of bytes the message consumed, including its length prefix and padding
+    pub size: usize,
+}
+
+/// Iterator over the messages of a stream, created by [DynamicMsg::decode_stream()].
+///
+/// The iterator ends at the end of the stream. After an error of a concatenated stream it ends
+/// as well, since the start of the next message is unknown. Length prefixed streams continue
+/// with the next message if a payload could not be decoded.
+#[derive(Debug)]
+pub struct MessageStream<'a, R, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    reader: R,
+    framing: Framing,
+    offset: usize,
+    done: bool,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Decodes messages one at a time from a stream of back-to-back payloads, like a log file
+    /// or a socket. Reads from `r` are small, so wrap it in a [BufReader](std::io::BufReader) if
+    /// it is not buffered already.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::dynamic::{DynamicMsg, Endianness, Framing};
+    /// use ros2_message::Value;
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new("package/msg/Msg", "uint16 value")
+    ///     .expect("The message definition was invalid");
+    /// let stream = [
+    ///     6u8, 0, 0, 0, 0x00, 0x01, 0, 0, 1, 0,
+    ///     6, 0, 0, 0, 0x00, 0x00, 0, 0, 0, 2,
+    /// ];
+    ///
+    /// let messages = dynamic_message
+    ///     .decode_stream(&stream[..], Framing::LengthPrefixed(Endianness::Little))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .expect("The supplied bytes do not match the message definition");
+    ///
+    /// assert_eq!(messages.len(), 2);
+    /// assert_eq!(messages[1].message["value"], Value::U16(2));
+    /// assert_eq!(messages[1].offset, 10);
+    /// assert_eq!(messages[1].size, 10);
+    /// ```
+    pub fn decode_stream<R: Read>(&self, r: R, framing: Framing) -> MessageStream<'_, R, S> {
+        MessageStream {
+            msg: self,
+            reader: r,
+            framing,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read, S: BuildHasher + Default + Clone + core::fmt::Debug> MessageStream<'_, R, S> {
+    /// Returns the number of bytes consumed from the stream so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn next_concatenated(&mut self) -> Result<Option<(MessageValue<S>, Encapsulation, usize)>> {
+        let mut r = ByteCounter::new(&mut self.reader, self.msg.options().max_bytes);
+
+        let mut header = [0, 0, 0, 0];
+        if !read_first_byte(&mut r, &mut header[0])? {
+            return Ok(None);
+        }
+        r.read_exact(&mut header[1..])?;
+
+        let Some(encapsulation) = Encapsulation::from_header(header) else {
+            return Err(self.msg.unsupported_encapsulation(header, r.position()));
+        };
+        r.set_encapsulation(encapsulation);
+
+        let values = self.msg.decode_values::<_, Value<S>>(0, &mut r)?;
+        // The last two bits of the options hold the number of padding bytes
+        r.skip((header[3] & 0b11) as usize)?;
+
+        let size = r.position();
+        Ok(Some((values, encapsulation, size)))
+    }
+
+    fn next_length_prefixed(
+        &mut self,
+        endianness: Endianness,
+    ) -> Result<Option<(MessageValue<S>, Encapsulation, usize)>> {
+        let mut prefix = [0, 0, 0, 0];
+        if !read_first_byte(&mut self.reader, &mut prefix[0])? {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut prefix[1..])?;
+        let length = match endianness {
+            Endianness::Little => (&prefix[..]).read_u32::<LE>()?,
+            Endianness::Big => (&prefix[..]).read_u32::<BE>()?,
+        } as usize;
+        if let Some(max) = self.msg.options().max_bytes.filter(|max| length > *max) {
+            return Err(io::Error::other(format!(
+                "The message of {length} bytes exceeds the limit of {max} bytes"
+            ))
+            .into());
+        }
+
+        let mut payload = Vec::new();
+        let read = (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(&mut payload)?;
+        if read != length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        // The payload is complete, so the next message can be found even if this one is invalid
+        self.offset += 4 + length;
+        let (message, encapsulation) = self
+            .msg
+            .decode_message::<_, Value<S>>(&payload[..], Some(length))?;
+        Ok(Some((message, encapsulation, 4 + length)))
+    }
+}
+
+impl<R: Read, S: BuildHasher + Default + Clone + core::fmt::Debug> Iterator
+    for MessageStream<'_, R, S>
+{
+    type Item = Result<StreamedMessage<S>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let offset = self.offset;
+        let res = match self.framing {
+            Framing::Concatenated => self.next_concatenated(),
+            Framing::LengthPrefixed(endianness) => self.next_length_prefixed(endianness),
+        };
+
+        match res {
+            Ok(Some((message, encapsulation, size))) => {
+                self.offset = offset + size;
+                Some(Ok(StreamedMessage {
+                    message,
+                    encapsulation,
+                    offset,
+                    size,
+                }))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                // Only length prefixed streams advance past invalid payloads
+                self.done = self.offset == offset;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Reads the first byte of a message, returns `false` at the end of the stream
+fn read_first_byte(r: &mut impl Read, byte: &mut u8) -> io::Result<bool> {
+    loop {
+        match r.read(std::slice::from_mut(byte)) {
+            Ok(0) => return Ok(false),
+            Ok(_) => return Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
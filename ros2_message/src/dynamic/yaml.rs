@@ -0,0 +1,349 @@
+use super::DynamicMsg;
+use crate::error::{Error, Result};
+use crate::{
+    DataType, Duration, FieldCase, FieldInfo, MessagePath, MessageValue, Msg, Time, Value,
+};
+use std::fmt::Write;
+use std::hash::BuildHasher;
+use yaml_rust2::{yaml::Hash, Yaml, YamlLoader};
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> DynamicMsg<S> {
+    /// Parses a message from YAML the way `ros2 topic pub` reads it, this is the inverse
+    /// operation of [ToYaml](crate::ToYaml).
+    ///
+    /// The document has to be a mapping of field names to values, in which nested messages are
+    /// mappings themselves and arrays are sequences. Fields that are left out get the default
+    /// value of the message definition, or zero, `false`, an empty string or an empty array if
+    /// the definition has none. Constants are filled in from the definition and cannot be set.
+    /// Values are checked against the types of the fields, integers are accepted for float
+    /// fields and `builtin_interfaces` times for `time` and `duration` fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidYaml] naming the field whose value does not match the message
+    /// definition, or [Error::MessageDependencyMissing] if a nested message is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ros2_message::{dynamic::DynamicMsg, Value};
+    ///
+    /// let dynamic_message = DynamicMsg::<std::hash::RandomState>::new(
+    ///     "package/msg/Reading",
+    ///     "uint16 id\nfloat32[] values\nstring frame_id \"base\"",
+    /// )
+    /// .expect("The message definition was invalid");
+    ///
+    /// let message = dynamic_message
+    ///     .parse_yaml("{id: 7, values: [1, 2.5]}")
+    ///     .expect("The YAML does not match the message definition");
+    ///
+    /// assert_eq!(message["id"], Value::U16(7));
+    /// assert_eq!(message["values"], Value::Array(vec![Value::F32(1.0), Value::F32(2.5)]));
+    /// assert_eq!(message["frame_id"], Value::String("base".into()));
+    /// ```
+    pub fn parse_yaml(&self, yaml: &str) -> Result<MessageValue<S>> {
+        let invalid = |reason: String| Error::InvalidYaml {
+            path: String::new(),
+            reason,
+        };
+        let mut documents = YamlLoader::load_from_str(yaml)
+            .map_err(|e| invalid(e.to_string()))?
+            .into_iter();
+        // An empty document sets no fields at all
+        let document = documents.next().unwrap_or(Yaml::Null);
+        if documents.next().is_some() {
+            return Err(invalid("expected a single document".to_owned()));
+        }
+
+        YamlParser {
+            msg: self,
+            path: String::new(),
+        }
+        .message(self.msg(), &document)
+    }
+}
+
+static U32: DataType = DataType::U32;
+static I32: DataType = DataType::I32;
+
+/// Converts YAML into the values of a message
+struct YamlParser<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    msg: &'a DynamicMsg<S>,
+    /// Path of the value that is parsed, like `poses[1].position`
+    path: String,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> YamlParser<'_, S> {
+    fn error(&self, reason: impl Into<String>) -> Error {
+        Error::InvalidYaml {
+            path: self.path.clone(),
+            reason: reason.into(),
+        }
+    }
+
+    fn mismatch(&self, datatype: &DataType) -> Error {
+        self.error(format!("expected a value of type `{datatype}`"))
+    }
+
+    /// Runs `f` for the field with the given name, so errors point at it
+    fn field_path<T>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let len = self.path.len();
+        if len > 0 {
+            self.path.push('.');
+        }
+        self.path.push_str(name);
+        let res = f(self);
+        self.path.truncate(len);
+        res
+    }
+
+    /// Runs `f` for the array element with the given index, so errors point at it
+    fn index_path<T>(&mut self, index: usize, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let len = self.path.len();
+        write!(self.path, "[{index}]").expect("Writing to a string does not fail");
+        let res = f(self);
+        self.path.truncate(len);
+        res
+    }
+
+    fn message(&mut self, msg: &Msg<S>, yaml: &Yaml) -> Result<MessageValue<S>> {
+        let empty = Hash::new();
+        let entries = match yaml {
+            Yaml::Hash(entries) => entries,
+            Yaml::Null => &empty,
+            _ => return Err(self.error(format!("expected a message of type `{}`", msg.path()))),
+        };
+
+        for key in entries.keys() {
+            let Yaml::String(name) = key else {
+                return Err(self.error(format!("expected field names as keys, got `{key:?}`")));
+            };
+            match msg.fields().iter().find(|field| field.name() == name) {
+                None => {
+                    return self.field_path(name, |p| {
+                        Err(p.error("the field does not exist in the message definition"))
+                    })
+                }
+                Some(field) if field.is_constant() => {
+                    return self.field_path(name, |p| Err(p.error("constants cannot be set")))
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut values = MessageValue::with_hasher(S::default());
+        for field in msg.fields() {
+            let value = match field.const_value() {
+                Some(value) => value.clone(),
+                None => self.field_path(field.name(), |p| {
+                    match entries.get(&Yaml::String(field.name().to_owned())) {
+                        Some(yaml) => p.field(msg.path(), field, yaml),
+                        None => p.default(msg.path(), field),
+                    }
+                })?,
+            };
+            values.insert(field.name().to_owned(), value);
+        }
+        Ok(values)
+    }
+
+    fn field(
+        &mut self,
+        parent: &MessagePath,
+        field: &FieldInfo<S>,
+        yaml: &Yaml,
+    ) -> Result<Value<S>> {
+        let datatype = field.datatype();
+        if matches!(
+            field.case(),
+            FieldCase::Unit | FieldCase::Default(_) | FieldCase::Const(_)
+        ) {
+            return self.element(parent, datatype, yaml);
+        }
+
+        let Yaml::Array(items) = yaml else {
+            return Err(self.error(format!("expected an array of `{datatype}`")));
+        };
+        match field.case() {
+            FieldCase::Array(len) if items.len() != *len => {
+                return Err(self.error(format!(
+                    "expected an array of {len} elements, got {}",
+                    items.len()
+                )))
+            }
+            FieldCase::BoundedVector(bound) if items.len() > *bound => {
+                return Err(self.error(format!(
+                    "the sequence of length {} exceeds its bound of {bound}",
+                    items.len()
+                )))
+            }
+            _ => {}
+        }
+
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| self.index_path(index, |p| p.element(parent, datatype, item)))
+            .collect::<Result<_>>()
+            .map(Value::Array)
+    }
+
+    fn element(
+        &mut self,
+        parent: &MessagePath,
+        datatype: &DataType,
+        yaml: &Yaml,
+    ) -> Result<Value<S>> {
+        Ok(match datatype {
+            DataType::Bool => match yaml {
+                Yaml::Boolean(v) => Value::Bool(*v),
+                _ => return Err(self.mismatch(datatype)),
+            },
+            DataType::F32 => Value::F32(self.float(datatype, yaml)? as f32),
+            DataType::F64 => Value::F64(self.float(datatype, yaml)?),
+            // A single character can be used instead of its code
+            DataType::WChar => match yaml {
+                Yaml::String(v) if v.chars().count() == 1 => {
+                    let c = v.chars().next().unwrap();
+                    self.integer(datatype, &Yaml::Integer(u32::from(c).into()))?
+                }
+                yaml => self.integer(datatype, yaml)?,
+            },
+            DataType::String
+            | DataType::BoundedString(_)
+            | DataType::WString
+            | DataType::BoundedWString(_) => {
+                // Plain scalars that look like numbers are taken as they are written
+                let v = match yaml {
+                    Yaml::String(v) | Yaml::Real(v) => v.clone(),
+                    Yaml::Integer(v) => v.to_string(),
+                    Yaml::Boolean(v) => v.to_string(),
+                    _ => return Err(self.mismatch(datatype)),
+                };
+                let (kind, length, bound) = match datatype {
+                    DataType::BoundedString(bound) => ("string", v.len(), *bound),
+                    DataType::BoundedWString(bound) => {
+                        ("wstring", v.encode_utf16().count(), *bound)
+                    }
+                    _ => ("string", 0, usize::MAX),
+                };
+                if length > bound {
+                    return Err(self.error(format!(
+                        "the {kind} of length {length} exceeds its bound of {bound}"
+                    )));
+                }
+                Value::String(v)
+            }
+            DataType::Time | DataType::Duration => {
+                let Yaml::Hash(entries) = yaml else {
+                    return Err(self.mismatch(datatype));
+                };
+                let field_type = match datatype {
+                    DataType::Time => &U32,
+                    _ => &I32,
+                };
+                let zero = Yaml::Integer(0);
+                let (mut sec, mut nsec) = (&zero, &zero);
+                for (key, value) in entries {
+                    match key.as_str() {
+                        Some("sec") => sec = value,
+                        Some("nanosec" | "nsec") => nsec = value,
+                        _ => {
+                            return Err(self.error(format!(
+                                "expected `sec` and `nanosec` fields, got `{key:?}`"
+                            )))
+                        }
+                    }
+                }
+                let sec = self.field_path("sec", |p| p.integer(field_type, sec))?;
+                let nsec = self.field_path("nanosec", |p| p.integer(field_type, nsec))?;
+                match (sec, nsec) {
+                    (Value::U32(sec), Value::U32(nsec)) => Value::Time(Time { sec, nsec }),
+                    (Value::I32(sec), Value::I32(nsec)) => Value::Duration(Duration { sec, nsec }),
+                    _ => unreachable!(
+                        "time fields are parsed as `uint32`, duration fields as `int32`"
+                    ),
+                }
+            }
+            DataType::LocalMessage(name) => {
+                let msg = self.msg.get_dependency(&parent.peer(name))?;
+                Value::Message(self.message(msg, yaml)?)
+            }
+            DataType::GlobalMessage(path) => {
+                let msg = self.msg.get_dependency(path)?;
+                Value::Message(self.message(msg, yaml)?)
+            }
+            _ => self.integer(datatype, yaml)?,
+        })
+    }
+
+    fn integer(&self, datatype: &DataType, yaml: &Yaml) -> Result<Value<S>> {
+        let v: i128 = match yaml {
+            Yaml::Integer(v) => (*v).into(),
+            // Integers that do not fit into `i64` are read as floats
+            Yaml::Real(v) => v.parse().map_err(|_| self.mismatch(datatype))?,
+            _ => return Err(self.mismatch(datatype)),
+        };
+        let out_of_range = || {
+            self.error(format!(
+                "{v} is out of range for a value of type `{datatype}`"
+            ))
+        };
+
+        Ok(match datatype {
+            DataType::I8(_) => Value::I8(v.try_into().map_err(|_| out_of_range())?),
+            DataType::I16 => Value::I16(v.try_into().map_err(|_| out_of_range())?),
+            DataType::I32 => Value::I32(v.try_into().map_err(|_| out_of_range())?),
+            DataType::I64 => Value::I64(v.try_into().map_err(|_| out_of_range())?),
+            DataType::U8(_) => Value::U8(v.try_into().map_err(|_| out_of_range())?),
+            DataType::U16 | DataType::WChar => {
+                Value::U16(v.try_into().map_err(|_| out_of_range())?)
+            }
+            DataType::U32 => Value::U32(v.try_into().map_err(|_| out_of_range())?),
+            DataType::U64 => Value::U64(v.try_into().map_err(|_| out_of_range())?),
+            _ => return Err(self.mismatch(datatype)),
+        })
+    }
+
+    fn float(&self, datatype: &DataType, yaml: &Yaml) -> Result<f64> {
+        match yaml {
+            Yaml::Integer(v) => Ok(*v as f64),
+            Yaml::Real(_) => yaml.as_f64().ok_or_else(|| self.mismatch(datatype)),
+            _ => Err(self.mismatch(datatype)),
+        }
+    }
+
+    /// Returns the value of a field that is not set
+    fn default(&mut self, parent: &MessagePath, field: &FieldInfo<S>) -> Result<Value<S>> {
+        if let Some(value) = field.default_value() {
+            return Ok(value.clone());
+        }
+
+        match field.case() {
+            FieldCase::Array(len) => (0..*len)
+                .map(|_| self.zero(parent, field.datatype()))
+                .collect::<Result<_>>()
+                .map(Value::Array),
+            FieldCase::Vector | FieldCase::BoundedVector(_) => Ok(Value::Array(vec![])),
+            _ => self.zero(parent, field.datatype()),
+        }
+    }
+
+    fn zero(&mut self, parent: &MessagePath, datatype: &DataType) -> Result<Value<S>> {
+        match datatype {
+            DataType::Bool => Ok(Value::Bool(false)),
+            DataType::String
+            | DataType::BoundedString(_)
+            | DataType::WString
+            | DataType::BoundedWString(_) => Ok(Value::String(String::new())),
+            DataType::Time => Ok(Value::Time(Time { sec: 0, nsec: 0 })),
+            DataType::Duration => Ok(Value::Duration(Duration { sec: 0, nsec: 0 })),
+            // Messages without any fields set are filled with their defaults
+            DataType::LocalMessage(_) | DataType::GlobalMessage(_) => {
+                self.element(parent, datatype, &Yaml::Null)
+            }
+            _ => self.element(parent, datatype, &Yaml::Integer(0)),
+        }
+    }
+}
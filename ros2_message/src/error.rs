@@ -1,6 +1,6 @@
-use std::{backtrace::Backtrace, hash::RandomState};
-
-use crate::{FieldInfo, MessagePath, Msg};
+#[cfg(feature = "decode")]
+use crate::dynamic::Encapsulation;
+use crate::MessagePath;
 
 /// Enumeration of all errors that can be returned.
 #[derive(thiserror::Error, Debug)]
@@ -32,6 +32,9 @@ pub enum Error {
     ///
     /// For example, to calculate an MD5 sum for a message, you first need to calculate it for
     /// all messages it depends upon, and passing them into the calculation call.
+    ///
+    /// [MessageRegistry](crate::MessageRegistry) also returns this for a requested message,
+    /// service or action that it does not contain.
     #[error("message dependency missing: {package}/{name}")]
     MessageDependencyMissing {
         /// Package that the message is located in.
@@ -50,45 +53,156 @@ pub enum Error {
         value: String,
     },
 
+    /// A path to a field of a message does not match the message definition.
+    #[error("field path `{path}` is invalid, {reason}")]
+    InvalidFieldPath {
+        /// The field path as it was provided.
+        path: String,
+        /// Reason for the failure.
+        reason: String,
+    },
+
     /// The provided message data is either invalid or unsupported.
     ///
     /// This can happen if an incorrect message definition was used to decode a message.
-    #[error("failed to decode field:\n\n\"{field}\"\n\nGot `{err}` at byte {offset}\n\n{msg}")]
+    #[cfg(feature = "decode")]
+    #[error("failed to decode {}: {err}", decoding_location(.path, .datatype, *.offset, .encapsulation))]
     DecodingError {
-        /// The associated message definition that was used to decode the data
-        msg: Msg<RandomState>,
-        /// The field that the decoder failed at
-        field: FieldInfo<RandomState>,
-        /// The byte offset the deocder failed at
+        /// Path of the field the decoder failed at, like `transforms[2].transform.rotation.w`,
+        /// empty if it failed outside of any field
+        path: String,
+        /// Data type of the field as written in the message definition, like `float64[]`
+        datatype: String,
+        /// The byte offset the decoder failed at, counted from the start of the payload
         offset: usize,
+        /// Encapsulation of the payload, `None` if the header could not be read
+        encapsulation: Option<Encapsulation>,
+        /// The underlying io error
+        err: std::io::Error,
+    },
+
+    /// The provided value does not match the message definition it is encoded with.
+    #[error("failed to encode field `{field}` of message `{msg}`: {reason}")]
+    EncodingError {
+        /// Path of the message that contains the field
+        msg: MessagePath,
+        /// Name of the field that the encoder failed at
+        field: String,
+        /// Reason for the failure
+        reason: String,
+    },
+
+    /// A value could not be serialized, see [to_value()](crate::to_value) and
+    /// [DynamicMsg::serialize()](crate::dynamic::DynamicMsg::serialize).
+    #[error("failed to serialize value: {0}")]
+    Serialization(String),
+
+    /// A value could not be deserialized into the requested type, see
+    /// [from_value()](crate::from_value).
+    #[error("failed to deserialize value: {0}")]
+    Deserialization(String),
+
+    /// A YAML document does not match the message definition it is parsed with, see
+    /// [DynamicMsg::parse_yaml()](crate::dynamic::DynamicMsg::parse_yaml).
+    #[cfg(all(feature = "decode", feature = "yaml"))]
+    #[error("failed to parse YAML{}: {reason}", yaml_location(.path))]
+    InvalidYaml {
+        /// Path of the field the parser failed at, like `poses[1].position.x`, empty if the
+        /// document as a whole is invalid
+        path: String,
+        /// Reason for the failure
+        reason: String,
+    },
+
+    /// A decoded message does not match the Arrow schema it is appended to, see
+    /// [RecordBatchBuilder::append()](crate::dynamic::RecordBatchBuilder::append).
+    #[cfg(feature = "arrow")]
+    #[error("failed to convert {} to Arrow: {reason}", arrow_location(.path))]
+    ArrowConversion {
+        /// Path of the field the conversion failed at, like `points[3].x`, empty if it failed
+        /// outside of any field
+        path: String,
+        /// Reason for the failure
+        reason: String,
+    },
+
+    /// A directory or file containing message definitions could not be read.
+    #[error("failed to read `{}`: {err}", .path.display())]
+    ReadDefinition {
+        /// The directory or file that could not be read
+        path: std::path::PathBuf,
         /// The underlying io error
         err: std::io::Error,
     },
+
+    /// Messages depend upon each other in a cycle, so they can never be fully resolved.
+    #[error("cyclic message dependency: {}", .cycle.iter().map(ToString::to_string).collect::<Vec<_>>().join(" -> "))]
+    CyclicDependency {
+        /// The messages forming the cycle, the first message is repeated at the end
+        cycle: Vec<MessagePath>,
+    },
 }
 
+#[cfg(feature = "decode")]
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
-        let trace = Backtrace::force_capture();
-        // TODO!: this probably isn't wanted behaviour but left in for debugging purposes for now
-        eprintln!("{}", trace);
-
-        let default_msg = Msg::new(
-            MessagePath::new("placeholder", "PlaceholderMessage").unwrap(),
-            "",
-        )
-        .unwrap();
-        let default_field =
-            FieldInfo::new("uint8", "error_placeholder_field", crate::FieldCase::Unit).unwrap();
-
+        // The decoder fills in the field and offset while passing the error up
         Error::DecodingError {
-            msg: default_msg,
-            field: default_field,
+            path: String::new(),
+            datatype: String::new(),
             offset: 0,
+            encapsulation: None,
             err: value,
         }
     }
 }
 
+impl serde::de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Deserialization(msg.to_string())
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Serialization(msg.to_string())
+    }
+}
+
+#[cfg(feature = "decode")]
+fn decoding_location(
+    path: &str,
+    datatype: &str,
+    offset: usize,
+    encapsulation: &Option<Encapsulation>,
+) -> String {
+    let mut location = match path.is_empty() {
+        true => "message".to_owned(),
+        false => format!("field `{path}` of type `{datatype}`"),
+    };
+    location.push_str(&format!(" at byte {offset}"));
+    if let Some(encapsulation) = encapsulation {
+        location.push_str(&format!(" of {encapsulation} data"));
+    }
+    location
+}
+
+#[cfg(all(feature = "decode", feature = "yaml"))]
+fn yaml_location(path: &str) -> String {
+    match path.is_empty() {
+        true => String::new(),
+        false => format!(" at field `{path}`"),
+    }
+}
+
+#[cfg(feature = "arrow")]
+fn arrow_location(path: &str) -> String {
+    match path.is_empty() {
+        true => "message".to_owned(),
+        false => format!("field `{path}`"),
+    }
+}
+
 /*
 impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<Error> for &Error
 where
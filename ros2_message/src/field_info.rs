@@ -24,6 +24,12 @@ pub enum FieldCase {
     ///
     /// Examples: `float32[64]`, `geometry_msgs/Point[10]`.
     Array(usize),
+    /// Field of an array with an upper bound on its length.
+    ///
+    /// The contained number is the maximum array length.
+    ///
+    /// Examples: `float32[<=64]`, `geometry_msgs/Point[<=10]`.
+    BoundedVector(usize),
     /// Field describing a constant value.
     ///
     /// The contained `String` is the unparsed value.
@@ -55,14 +61,6 @@ impl<T> PartialEq for Uncompared<T> {
 
 impl<T> Eq for Uncompared<T> {}
 
-impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Uncompared<Option<Value<S>>> {
-    pub(crate) fn to_random_state(self) -> Uncompared<Option<Value<RandomState>>> {
-        Uncompared {
-            inner: self.inner.map(Value::to_random_state),
-        }
-    }
-}
-
 /// Full description of one field in a `msg` or `srv` file.
 #[derive(Debug, Serialize, Deserialize)]
 #[derive_where(Clone, PartialEq, Eq, Hash)]
@@ -72,6 +70,8 @@ pub struct FieldInfo<S: BuildHasher + Default + Clone + core::fmt::Debug = Rando
     datatype: DataType,
     name: String,
     case: FieldCase,
+    // Unparsed default of array fields, unit fields keep it in `FieldCase::Default`
+    array_default: Option<String>,
     #[serde(bound(deserialize = "", serialize = ""))]
     const_value: Uncompared<Option<Value<S>>>,
     #[serde(bound(deserialize = "", serialize = ""))]
@@ -84,9 +84,14 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> fmt::Display for Field
             FieldCase::Unit => write!(f, "{} {}", self.datatype, self.name),
             FieldCase::Vector => write!(f, "{}[] {}", self.datatype, self.name),
             FieldCase::Array(l) => write!(f, "{}[{}] {}", self.datatype, l, self.name),
+            FieldCase::BoundedVector(l) => write!(f, "{}[<={}] {}", self.datatype, l, self.name),
             FieldCase::Const(val) => write!(f, "{} {}={}", self.datatype, self.name, val),
             FieldCase::Default(val) => write!(f, "{} {} {}", self.datatype, self.name, val),
+        }?;
+        if let Some(default) = &self.array_default {
+            write!(f, " {}", default)?;
         }
+        Ok(())
     }
 }
 
@@ -119,16 +124,81 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldInfo<S> {
     /// assert!(FieldInfo::new("bad/field/type", "foo", FieldCase::Vector).is_err());
     /// ```
     pub fn new(datatype: &str, name: impl Into<String>, case: FieldCase) -> Result<FieldInfo<S>> {
-        Self::evaluate(datatype.try_into()?, name.into(), case)
+        Self::evaluate(datatype.try_into()?, name.into(), case, None)
+    }
+
+    /// Create a field with a default value, which is provided unparsed like in a message
+    /// definition.
+    ///
+    /// For unit fields this is the same as using [FieldCase::Default], array fields take an
+    /// array literal like `[1, 2, 3]`. Strings may be quoted, in which case escape sequences
+    /// like `\"` are resolved.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if the data type cannot be parsed, the default value is invalid
+    /// or the field is a constant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::{FieldCase, Value};
+    /// # type FieldInfo = ros2_message::FieldInfo<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let field = FieldInfo::with_default("int32", "samples", FieldCase::Vector, "[-1, 0, 1]")?;
+    ///
+    /// assert_eq!(
+    ///     field.default_value(),
+    ///     Some(&Value::Array(vec![Value::I32(-1), Value::I32(0), Value::I32(1)])),
+    /// );
+    /// assert_eq!(format!("{}", field), "int32[] samples [-1, 0, 1]");
+    ///
+    /// let field = FieldInfo::with_default("string", "greeting", FieldCase::Unit, r#""say \"hi\"""#)?;
+    /// assert_eq!(field.default_value(), Some(&Value::String(r#"say "hi""#.into())));
+    ///
+    /// assert!(FieldInfo::with_default("int32", "pair", FieldCase::Array(2), "[1, 2, 3]").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_default(
+        datatype: &str,
+        name: impl Into<String>,
+        case: FieldCase,
+        default: impl Into<String>,
+    ) -> Result<FieldInfo<S>> {
+        let name = name.into();
+        let default = default.into();
+        match case {
+            FieldCase::Unit | FieldCase::Default(_) => {
+                Self::new(datatype, name, FieldCase::Default(default))
+            }
+            FieldCase::Vector | FieldCase::Array(_) | FieldCase::BoundedVector(_) => {
+                Self::evaluate(datatype.try_into()?, name, case, Some(default))
+            }
+            FieldCase::Const(_) => Err(Error::BadMessageContent(format!(
+                "constant `{}` can not have a default value",
+                name
+            ))),
+        }
     }
 
-    fn evaluate(datatype: DataType, name: String, case: FieldCase) -> Result<FieldInfo<S>> {
+    fn evaluate(
+        datatype: DataType,
+        name: String,
+        case: FieldCase,
+        array_default: Option<String>,
+    ) -> Result<FieldInfo<S>> {
         fn parse_datatype_const<S: BuildHasher + Default + Clone + core::fmt::Debug>(
             dtype: &DataType,
             raw_value: &str,
         ) -> Option<Value<S>> {
             match dtype {
-                DataType::Bool => Some(Value::Bool(raw_value != "0")),
+                DataType::Bool => match raw_value.to_lowercase().as_str() {
+                    "true" | "1" => Some(Value::Bool(true)),
+                    "false" | "0" => Some(Value::Bool(false)),
+                    _ => None,
+                },
                 DataType::I8(_) => raw_value.parse().ok().map(Value::I8),
                 DataType::I16 => raw_value.parse().ok().map(Value::I16),
                 DataType::I32 => raw_value.parse().ok().map(Value::I32),
@@ -139,7 +209,11 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldInfo<S> {
                 DataType::U64 => raw_value.parse().ok().map(Value::U64),
                 DataType::F32 => raw_value.parse().ok().map(Value::F32),
                 DataType::F64 => raw_value.parse().ok().map(Value::F64),
-                DataType::String => Some(Value::String(raw_value.to_owned())),
+                DataType::String
+                | DataType::BoundedString(_)
+                | DataType::WString
+                | DataType::BoundedWString(_) => parse_string_literal(raw_value).map(Value::String),
+                DataType::WChar => raw_value.parse().ok().map(Value::U16),
                 DataType::Time
                 | DataType::Duration
                 | DataType::LocalMessage(_)
@@ -158,27 +232,45 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldInfo<S> {
                 })?)
             }
 
-            FieldCase::Unit | FieldCase::Vector | FieldCase::Array(_) | FieldCase::Default(_) => {
-                None
-            }
+            FieldCase::Unit
+            | FieldCase::Vector
+            | FieldCase::Array(_)
+            | FieldCase::BoundedVector(_)
+            | FieldCase::Default(_) => None,
         };
-        let default_value = match &case {
-            FieldCase::Default(raw_value) => {
-                Some(parse_datatype_const(&datatype, raw_value).ok_or_else(|| {
-                    Error::BadConstant {
-                        name: name.clone(),
-                        datatype: format!("{}", datatype),
-                        value: raw_value.into(),
-                    }
-                })?)
+        let bad_default = |raw_value: &str| Error::BadConstant {
+            name: name.clone(),
+            datatype: format!("{}", datatype),
+            value: raw_value.into(),
+        };
+        let default_value = match (&case, &array_default) {
+            (FieldCase::Default(raw_value), _) => Some(
+                parse_datatype_const(&datatype, raw_value).ok_or_else(|| bad_default(raw_value))?,
+            ),
+            (FieldCase::Vector | FieldCase::Array(_) | FieldCase::BoundedVector(_), Some(raw)) => {
+                let values = split_array_literal(raw)
+                    .and_then(|items| {
+                        items
+                            .into_iter()
+                            .map(|item| parse_datatype_const(&datatype, item))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .filter(|values| match case {
+                        FieldCase::Array(l) => values.len() == l,
+                        FieldCase::BoundedVector(l) => values.len() <= l,
+                        _ => true,
+                    })
+                    .ok_or_else(|| bad_default(raw))?;
+                Some(Value::Array(values))
             }
-            FieldCase::Unit | FieldCase::Vector | FieldCase::Array(_) | FieldCase::Const(_) => None,
+            _ => None,
         };
 
         Ok(FieldInfo {
             datatype,
             name,
             case,
+            array_default,
             const_value: Uncompared { inner: const_value },
             default_value: Uncompared {
                 inner: default_value,
@@ -206,6 +298,35 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldInfo<S> {
         self.const_value.inner.as_ref()
     }
 
+    /// Returns the parsed default value if the field has one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::{FieldCase, Value};
+    /// # type FieldInfo = ros2_message::FieldInfo<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// assert_eq!(
+    ///     FieldInfo::new("bool", "foo", FieldCase::Default("true".into()))?.default_value(),
+    ///     Some(&Value::Bool(true)),
+    /// );
+    /// assert_eq!(FieldInfo::new("bool", "foo", FieldCase::Unit)?.default_value(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_value(&self) -> Option<&Value<S>> {
+        self.default_value.inner.as_ref()
+    }
+
+    /// Returns the default value as written in the definition, if the field has one.
+    pub(crate) fn default_literal(&self) -> Option<&str> {
+        match &self.case {
+            FieldCase::Default(default) => Some(default),
+            _ => self.array_default.as_deref(),
+        }
+    }
+
     /// Returns true if the field contains a constant value.
     ///
     /// # Examples
@@ -300,6 +421,7 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldInfo<S> {
             (false, _) | (_, &FieldCase::Unit) => format!("{} {}", datatype, self.name),
             (true, &FieldCase::Vector) => format!("{}[] {}", datatype, self.name),
             (true, &FieldCase::Array(l)) => format!("{}[{}] {}", datatype, l, self.name),
+            (true, &FieldCase::BoundedVector(l)) => format!("{}[<={}] {}", datatype, l, self.name),
         })
     }
 
@@ -339,24 +461,6 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FieldInfo<S> {
             _ => false,
         }
     }
-
-    pub(crate) fn to_random_state(self) -> FieldInfo<RandomState> {
-        let Self {
-            datatype,
-            name,
-            case,
-            const_value,
-            default_value,
-        } = self;
-
-        FieldInfo {
-            datatype,
-            name,
-            case,
-            const_value: const_value.to_random_state(),
-            default_value: default_value.to_random_state(),
-        }
-    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -364,13 +468,15 @@ struct FieldInfoSerde {
     datatype: DataType,
     name: String,
     case: FieldCase,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<String>,
 }
 
 impl<S: BuildHasher + Default + Clone + core::fmt::Debug> TryFrom<FieldInfoSerde> for FieldInfo<S> {
     type Error = Error;
 
     fn try_from(src: FieldInfoSerde) -> Result<Self> {
-        Self::evaluate(src.datatype, src.name, src.case)
+        Self::evaluate(src.datatype, src.name, src.case, src.default)
     }
 }
 
@@ -380,6 +486,72 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<FieldInfo<S>> for
             datatype: src.datatype,
             name: src.name,
             case: src.case,
+            default: src.array_default,
         }
     }
 }
+
+/// Resolves a string literal, which is either quoted with escape sequences or taken as is.
+fn parse_string_literal(raw: &str) -> Option<String> {
+    let Some(quote) = raw.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+        return Some(raw.to_owned());
+    };
+    let inner = raw.strip_prefix(quote)?.strip_suffix(quote)?;
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            if c == quote {
+                // Unescaped quotes end the literal early
+                return None;
+            }
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            escaped @ ('\\' | '"' | '\'') => value.push(escaped),
+            other => {
+                value.push('\\');
+                value.push(other);
+            }
+        }
+    }
+    Some(value)
+}
+
+/// Splits an array literal like `[1, 2, 3]` into its unparsed items, commas inside of quoted
+/// strings don't separate items.
+fn split_array_literal(raw: &str) -> Option<Vec<&str>> {
+    let inner = raw.trim().strip_prefix('[')?.strip_suffix(']')?;
+    if inner.trim().is_empty() {
+        return Some(vec![]);
+    }
+
+    let mut items = vec![];
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ',') => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(inner[start..].trim());
+
+    if quote.is_some() || items.iter().any(|item| item.is_empty()) {
+        return None;
+    }
+    Some(items)
+}
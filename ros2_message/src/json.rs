@@ -0,0 +1,391 @@
+use crate::{DataType, I8Variant, MessagePath, MessageValue, Msg, PrimitiveArray, Value};
+use std::fmt::{self, Write};
+use std::hash::BuildHasher;
+
+/// How arrays of bytes are written to JSON, see [JsonOptions::bytes].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BytesFormat {
+    /// An array of numbers like `[104, 105]`, like `ros2 topic echo` prints them.
+    #[default]
+    Numbers,
+    /// A base64 string with padding like `"aGk="`, like rosbridge sends them.
+    Base64,
+}
+
+/// How `NaN` and infinite floats are written to JSON, see [JsonOptions::non_finite].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NonFiniteFloats {
+    /// `null`, which keeps the output valid JSON but loses the value.
+    #[default]
+    Null,
+    /// The strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    String,
+    /// The bare literals `NaN`, `Infinity` and `-Infinity`. They are not valid JSON, but
+    /// Python's `json` module and thereby rosbridge read and write them.
+    Literal,
+}
+
+/// Options that change how values are written by [ToJson].
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::{BytesFormat, JsonOptions, NonFiniteFloats};
+///
+/// let options = JsonOptions {
+///     bytes: BytesFormat::Base64,
+///     ..Default::default()
+/// };
+/// assert_eq!(JsonOptions::default().bytes, BytesFormat::Numbers);
+/// assert_eq!(JsonOptions::default().non_finite, NonFiniteFloats::Null);
+/// assert!(!JsonOptions::default().pretty);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// How `uint8[]`, `char[]` and `byte[]` arrays are written. [PrimitiveArray::U8] values
+    /// are always bytes, other arrays only when they are written by `DynamicMsg::to_json` and
+    /// their field is one of these arrays.
+    pub bytes: BytesFormat,
+    /// How `NaN` and infinite `float32` and `float64` values are written.
+    pub non_finite: NonFiniteFloats,
+    /// Indent nested arrays and messages by two spaces per level instead of writing
+    /// everything on a single line.
+    pub pretty: bool,
+}
+
+/// Writes decoded values as plain JSON, following the conventions of `ros2 topic echo` and
+/// rosbridge.
+///
+/// Numbers, strings and booleans are written as they are, arrays as JSON arrays and messages as
+/// objects with their fields in the order of the message, which is the order of the message
+/// definition for decoded messages. [Time](crate::Time) and [Duration](crate::Duration) are
+/// written as objects with `sec` and `nanosec` fields like `builtin_interfaces/Time`.
+///
+/// Values do not know the types of their fields, so only [PrimitiveArray::U8] is written as
+/// bytes. `DynamicMsg::to_json` writes every `uint8[]`, `char[]` and `byte[]` field as bytes.
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::{ToJson, Time, Value};
+///
+/// let value: Value = [
+///     ("stamp", Value::Time(Time { sec: 1, nsec: 5 })),
+///     ("ranges", Value::Array(vec![Value::F32(0.5), Value::F32(f32::NAN)])),
+///     ("frame_id", Value::String("laser".into())),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// assert_eq!(
+///     value.to_json(),
+///     r#"{"stamp":{"sec":1,"nanosec":5},"ranges":[0.5,null],"frame_id":"laser"}"#
+/// );
+/// ```
+pub trait ToJson {
+    /// Writes the value as JSON with the provided options.
+    fn write_json(&self, w: &mut dyn Write, options: &JsonOptions) -> fmt::Result;
+
+    /// Returns the value as compact JSON with the default options.
+    fn to_json(&self) -> String {
+        self.to_json_with_options(&JsonOptions::default())
+    }
+
+    /// Returns the value as JSON with the provided options.
+    fn to_json_with_options(&self, options: &JsonOptions) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json, options)
+            .expect("Writing to a string does not fail");
+        json
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ToJson for Value<S> {
+    fn write_json(&self, w: &mut dyn Write, options: &JsonOptions) -> fmt::Result {
+        JsonWriter { w, options }.value(self, None, 0)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ToJson for MessageValue<S> {
+    fn write_json(&self, w: &mut dyn Write, options: &JsonOptions) -> fmt::Result {
+        JsonWriter { w, options }.message(self, None, 0)
+    }
+}
+
+/// Writes the message as JSON like [ToJson], with the message definition deciding which arrays
+/// are bytes. Nested messages are looked up with `dependency`.
+#[cfg(feature = "decode")]
+pub(crate) fn write_message_json<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    w: &mut dyn Write,
+    options: &JsonOptions,
+    msg: &'a Msg<S>,
+    dependency: &'a dyn Fn(&MessagePath) -> Option<&'a Msg<S>>,
+    message: &MessageValue<S>,
+) -> fmt::Result {
+    JsonWriter { w, options }.message(message, Some((msg, dependency)), 0)
+}
+
+/// Definition of the field a value belongs to
+struct FieldType<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    parent: &'a MessagePath,
+    datatype: &'a DataType,
+    dependency: &'a dyn Fn(&MessagePath) -> Option<&'a Msg<S>>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Clone for FieldType<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Copy for FieldType<'_, S> {}
+
+impl<'a, S: BuildHasher + Default + Clone + core::fmt::Debug> FieldType<'a, S> {
+    /// Returns true for `uint8`, `char` and `byte`, whose arrays are written as bytes
+    fn is_bytes(&self) -> bool {
+        matches!(
+            self.datatype,
+            DataType::U8(_) | DataType::I8(I8Variant::Byte)
+        )
+    }
+
+    /// Returns the definition of nested messages, with the function to look up theirs
+    fn msg(&self) -> Option<MessageType<'a, S>> {
+        let msg = match self.datatype {
+            DataType::LocalMessage(name) => (self.dependency)(&self.parent.peer(name)),
+            DataType::GlobalMessage(path) => (self.dependency)(path),
+            _ => None,
+        }?;
+        Some((msg, self.dependency))
+    }
+}
+
+type MessageType<'a, S> = (&'a Msg<S>, &'a dyn Fn(&MessagePath) -> Option<&'a Msg<S>>);
+
+struct JsonWriter<'a> {
+    w: &'a mut dyn Write,
+    options: &'a JsonOptions,
+}
+
+impl JsonWriter<'_> {
+    fn value<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &mut self,
+        value: &Value<S>,
+        field: Option<FieldType<'_, S>>,
+        depth: usize,
+    ) -> fmt::Result {
+        match value {
+            Value::Bool(v) => write!(self.w, "{v}"),
+            Value::I8(v) => write!(self.w, "{v}"),
+            Value::I16(v) => write!(self.w, "{v}"),
+            Value::I32(v) => write!(self.w, "{v}"),
+            Value::I64(v) => write!(self.w, "{v}"),
+            Value::U8(v) => write!(self.w, "{v}"),
+            Value::U16(v) => write!(self.w, "{v}"),
+            Value::U32(v) => write!(self.w, "{v}"),
+            Value::U64(v) => write!(self.w, "{v}"),
+            // Debug formatting keeps the decimal point and the shortest representation
+            Value::F32(v) => self.float(f64::from(*v), format_args!("{v:?}")),
+            Value::F64(v) => self.float(*v, format_args!("{v:?}")),
+            Value::String(v) => self.string(v),
+            Value::Time(v) => self.object(
+                [
+                    ("sec", &Value::<S>::U32(v.sec), None),
+                    ("nanosec", &Value::U32(v.nsec), None),
+                ]
+                .into_iter(),
+                depth,
+            ),
+            Value::Duration(v) => self.object(
+                [
+                    ("sec", &Value::<S>::I32(v.sec), None),
+                    ("nanosec", &Value::I32(v.nsec), None),
+                ]
+                .into_iter(),
+                depth,
+            ),
+            Value::Array(items) if field.is_some_and(|field| field.is_bytes()) => {
+                let bytes = items
+                    .iter()
+                    .map(|item| match item {
+                        Value::U8(v) => Some(*v),
+                        Value::I8(v) => Some(*v as u8),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match bytes {
+                    Some(bytes) => self.bytes(&bytes, depth),
+                    None => self.array(items.iter(), field, depth),
+                }
+            }
+            Value::Array(items) => self.array(items.iter(), field, depth),
+            Value::PrimitiveArray(PrimitiveArray::U8(bytes)) => self.bytes(bytes, depth),
+            Value::PrimitiveArray(PrimitiveArray::I8(bytes))
+                if field.is_some_and(|field| field.is_bytes()) =>
+            {
+                let bytes: Vec<_> = bytes.iter().map(|&v| v as u8).collect();
+                self.bytes(&bytes, depth)
+            }
+            Value::PrimitiveArray(items) => {
+                let items = items.to_values::<S>();
+                self.array(items.iter(), field, depth)
+            }
+            Value::Message(fields) => {
+                self.message(fields, field.and_then(|field| field.msg()), depth)
+            }
+        }
+    }
+
+    fn float(&mut self, v: f64, shortest: impl fmt::Display) -> fmt::Result {
+        if v.is_finite() {
+            return write!(self.w, "{shortest}");
+        }
+        let name = match v {
+            v if v.is_nan() => "NaN",
+            v if v > 0.0 => "Infinity",
+            _ => "-Infinity",
+        };
+        match self.options.non_finite {
+            NonFiniteFloats::Null => self.w.write_str("null"),
+            NonFiniteFloats::String => write!(self.w, "\"{name}\""),
+            NonFiniteFloats::Literal => self.w.write_str(name),
+        }
+    }
+
+    fn string(&mut self, v: &str) -> fmt::Result {
+        self.w.write_char('"')?;
+        for c in v.chars() {
+            match c {
+                '"' => self.w.write_str("\\\"")?,
+                '\\' => self.w.write_str("\\\\")?,
+                '\n' => self.w.write_str("\\n")?,
+                '\r' => self.w.write_str("\\r")?,
+                '\t' => self.w.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(self.w, "\\u{:04x}", c as u32)?,
+                c => self.w.write_char(c)?,
+            }
+        }
+        self.w.write_char('"')
+    }
+
+    fn bytes(&mut self, bytes: &[u8], depth: usize) -> fmt::Result {
+        match self.options.bytes {
+            BytesFormat::Numbers => {
+                self.w.write_char('[')?;
+                for (index, byte) in bytes.iter().enumerate() {
+                    if index > 0 {
+                        self.w.write_char(',')?;
+                    }
+                    self.newline(depth + 1)?;
+                    write!(self.w, "{byte}")?;
+                }
+                if !bytes.is_empty() {
+                    self.newline(depth)?;
+                }
+                self.w.write_char(']')
+            }
+            BytesFormat::Base64 => {
+                self.w.write_char('"')?;
+                self.w.write_str(&base64(bytes))?;
+                self.w.write_char('"')
+            }
+        }
+    }
+
+    /// Writes the elements of an array, which all belong to the same `field`
+    fn array<'v, S: BuildHasher + Default + Clone + core::fmt::Debug + 'v>(
+        &mut self,
+        items: impl Iterator<Item = &'v Value<S>>,
+        field: Option<FieldType<'_, S>>,
+        depth: usize,
+    ) -> fmt::Result {
+        self.w.write_char('[')?;
+        let mut empty = true;
+        for (index, item) in items.enumerate() {
+            if index > 0 {
+                self.w.write_char(',')?;
+            }
+            self.newline(depth + 1)?;
+            self.value(item, field, depth + 1)?;
+            empty = false;
+        }
+        if !empty {
+            self.newline(depth)?;
+        }
+        self.w.write_char(']')
+    }
+
+    /// Writes the fields of a message, which are looked up in its definition if it is known
+    fn message<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &mut self,
+        fields: &MessageValue<S>,
+        msg: Option<MessageType<'_, S>>,
+        depth: usize,
+    ) -> fmt::Result {
+        let field_type = |name: &str| {
+            let (msg, dependency) = msg?;
+            let field = msg.fields().iter().find(|field| field.name() == name)?;
+            Some(FieldType {
+                parent: msg.path(),
+                datatype: field.datatype(),
+                dependency,
+            })
+        };
+        self.object(
+            fields.iter().map(|(k, v)| (k.as_str(), v, field_type(k))),
+            depth,
+        )
+    }
+
+    fn object<'v, 'f, S: BuildHasher + Default + Clone + core::fmt::Debug + 'v + 'f>(
+        &mut self,
+        fields: impl Iterator<Item = (&'v str, &'v Value<S>, Option<FieldType<'f, S>>)>,
+        depth: usize,
+    ) -> fmt::Result {
+        self.w.write_char('{')?;
+        let mut empty = true;
+        for (index, (key, value, field)) in fields.enumerate() {
+            if index > 0 {
+                self.w.write_char(',')?;
+            }
+            self.newline(depth + 1)?;
+            self.string(key)?;
+            self.w
+                .write_str(if self.options.pretty { ": " } else { ":" })?;
+            self.value(value, field, depth + 1)?;
+            empty = false;
+        }
+        if !empty {
+            self.newline(depth)?;
+        }
+        self.w.write_char('}')
+    }
+
+    fn newline(&mut self, depth: usize) -> fmt::Result {
+        if self.options.pretty {
+            write!(self.w, "\n{:indent$}", "", indent = depth * 2)?;
+        }
+        Ok(())
+    }
+}
+
+/// Standard base64 with padding, as defined by RFC 4648
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (u32::from(*byte) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -1,4 +1,4 @@
-//! Crate for describing ROS messages, and handling MSG and SRV files.
+//! Crate for describing ROS messages, and handling MSG, SRV and ACTION files.
 //! <div class="warning">
 //!
 //! This crate is not extensively tested yet and there is a good chance that you will discover issues.
@@ -7,29 +7,45 @@
 //! </div>
 #![warn(missing_docs)]
 
+mod action;
 mod data_type;
 
 mod error;
 mod field_info;
+mod json;
 mod message_path;
 mod msg;
 mod parse_msg;
+mod registry;
 mod srv;
 #[cfg(test)]
 mod tests;
 mod time;
+mod type_description;
 mod value;
+mod value_path;
+mod value_serde;
+mod yaml;
 
 /// Provides functionality by decoding a ROS2 messages when the message definition is
 /// only known at runtime, see [dynamic::DynamicMsg] for more.
 #[cfg(feature = "decode")]
 pub mod dynamic;
 
+pub use action::Action;
 pub use data_type::{DataType, I8Variant, U8Variant};
 pub use error::{Error, Result};
 pub use field_info::{FieldCase, FieldInfo};
-pub use message_path::MessagePath;
+pub use json::{BytesFormat, JsonOptions, NonFiniteFloats, ToJson};
+pub use message_path::{ActionPath, MessagePath, ServicePath};
 pub use msg::Msg;
+pub use registry::MessageRegistry;
 pub use srv::Srv;
 pub use time::{Duration, Time};
-pub use value::{MessageValue, Value};
+pub use type_description::{
+    FieldDescription, FieldType, IndividualTypeDescription, TypeDescription, RIHS01_PREFIX,
+};
+pub use value::{MessageValue, PrimitiveArray, Value};
+pub use value_path::ValuePath;
+pub use value_serde::{from_value, to_value};
+pub use yaml::{ToYaml, YamlOptions};
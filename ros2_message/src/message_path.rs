@@ -92,7 +92,13 @@ impl MessagePath {
                 "srv" => Err(Error::InvalidMessagePath {
                     name: input.to_owned(),
                     reason:
-                        "service names are not valid message paths, please use ServicePath(not yet implemented) instead"
+                        "service names are not valid message paths, please use ServicePath instead"
+                            .into(),
+                }),
+                "action" => Err(Error::InvalidMessagePath {
+                    name: input.to_owned(),
+                    reason:
+                        "action names are not valid message paths, please use ActionPath instead"
                             .into(),
                 }),
                 "msg" => Self::new(package, name),
@@ -140,3 +146,294 @@ impl From<MessagePath> for String {
         format!("{}", src)
     }
 }
+
+/// Path to a ROS service with naming conventions tested.
+///
+/// Services are made up of a request, a response and an event message, which ROS 2 names
+/// `<Name>_Request`, `<Name>_Response` and `<Name>_Event`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String")]
+#[serde(try_from = "&str")]
+pub struct ServicePath {
+    package: String,
+    name: String,
+}
+
+impl ServicePath {
+    /// Create full service path, with naming rules checked
+    ///
+    /// Naming rules are based on [REP 144](https://www.ros.org/reps/rep-0144.html).
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if naming conventions are not met.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::ServicePath;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let service_path = ServicePath::new("foo", "Bar")?;
+    ///
+    /// assert_eq!(service_path.package(), "foo");
+    /// assert_eq!(service_path.name(), "Bar");
+    /// assert_eq!(service_path, "foo/srv/Bar".try_into()?);
+    ///
+    /// assert!(ServicePath::new("0foo", "Bar").is_err());
+    /// assert!(ServicePath::try_from("foo/msg/Bar").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(package: impl Into<String>, name: impl Into<String>) -> Result<Self> {
+        let MessagePath { package, name } = MessagePath::new(package, name)?;
+        Ok(Self { package, name })
+    }
+
+    fn from_combined(input: &str) -> Result<Self> {
+        let parts = input.splitn(3, '/').collect::<Vec<&str>>();
+        match parts[..] {
+            [package, "srv", name] | [package, name] => Self::new(package, name),
+            _ => Err(Error::InvalidMessagePath {
+                name: input.to_owned(),
+                reason: "service path should follow the pattern packageName/srv/serviceName".into(),
+            }),
+        }
+    }
+
+    /// Package that the service is located in.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// Name of the service inside the package.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path of the request message, which is named `<Name>_Request`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::ServicePath;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let service_path = ServicePath::new("foo", "Bar")?;
+    ///
+    /// assert_eq!(service_path.request().to_string(), "foo/Bar_Request");
+    /// assert_eq!(service_path.response().to_string(), "foo/Bar_Response");
+    /// assert_eq!(service_path.event().to_string(), "foo/Bar_Event");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request(&self) -> MessagePath {
+        self.message(REQUEST_SUFFIX)
+    }
+
+    /// Path of the response message, which is named `<Name>_Response`.
+    pub fn response(&self) -> MessagePath {
+        self.message(RESPONSE_SUFFIX)
+    }
+
+    /// Path of the event message, which is named `<Name>_Event`.
+    ///
+    /// Service events are published by ROS 2 service introspection and recorded by rosbag2.
+    pub fn event(&self) -> MessagePath {
+        self.message(EVENT_SUFFIX)
+    }
+
+    fn message(&self, suffix: &str) -> MessagePath {
+        MessagePath {
+            package: self.package.clone(),
+            name: format!("{}{}", self.name, suffix),
+        }
+    }
+}
+
+/// Suffix of the request message of a service.
+pub(crate) const REQUEST_SUFFIX: &str = "_Request";
+/// Suffix of the response message of a service.
+pub(crate) const RESPONSE_SUFFIX: &str = "_Response";
+/// Suffix of the event message of a service.
+pub(crate) const EVENT_SUFFIX: &str = "_Event";
+
+impl Display for ServicePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.package(), self.name())
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ServicePath {
+    type Error = Error;
+
+    fn try_from(value: &'a str) -> Result<Self> {
+        Self::from_combined(value)
+    }
+}
+
+impl From<ServicePath> for String {
+    fn from(src: ServicePath) -> Self {
+        format!("{}", src)
+    }
+}
+
+/// Path to a ROS action with naming conventions tested.
+///
+/// Actions are made up of goal, result and feedback messages, from which ROS 2 derives the
+/// `<Name>_SendGoal` and `<Name>_GetResult` services and the `<Name>_FeedbackMessage` message
+/// that are actually sent.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String")]
+#[serde(try_from = "&str")]
+pub struct ActionPath {
+    package: String,
+    name: String,
+}
+
+impl ActionPath {
+    /// Create full action path, with naming rules checked
+    ///
+    /// Naming rules are based on [REP 144](https://www.ros.org/reps/rep-0144.html).
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if naming conventions are not met.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::ActionPath;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let action_path = ActionPath::new("foo", "Bar")?;
+    ///
+    /// assert_eq!(action_path.package(), "foo");
+    /// assert_eq!(action_path.name(), "Bar");
+    /// assert_eq!(action_path, "foo/action/Bar".try_into()?);
+    ///
+    /// assert!(ActionPath::new("0foo", "Bar").is_err());
+    /// assert!(ActionPath::try_from("foo/srv/Bar").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(package: impl Into<String>, name: impl Into<String>) -> Result<Self> {
+        let MessagePath { package, name } = MessagePath::new(package, name)?;
+        Ok(Self { package, name })
+    }
+
+    fn from_combined(input: &str) -> Result<Self> {
+        let parts = input.splitn(3, '/').collect::<Vec<&str>>();
+        match parts[..] {
+            [package, "action", name] | [package, name] => Self::new(package, name),
+            _ => Err(Error::InvalidMessagePath {
+                name: input.to_owned(),
+                reason: "action path should follow the pattern packageName/action/actionName"
+                    .into(),
+            }),
+        }
+    }
+
+    /// Package that the action is located in.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// Name of the action inside the package.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path of the goal message, which is named `<Name>_Goal`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::ActionPath;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let action_path = ActionPath::new("foo", "Bar")?;
+    ///
+    /// assert_eq!(action_path.goal().to_string(), "foo/Bar_Goal");
+    /// assert_eq!(action_path.send_goal().to_string(), "foo/Bar_SendGoal");
+    /// assert_eq!(action_path.feedback_message().to_string(), "foo/Bar_FeedbackMessage");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn goal(&self) -> MessagePath {
+        self.message(GOAL_SUFFIX)
+    }
+
+    /// Path of the result message, which is named `<Name>_Result`.
+    pub fn result(&self) -> MessagePath {
+        self.message(RESULT_SUFFIX)
+    }
+
+    /// Path of the feedback message, which is named `<Name>_Feedback`.
+    pub fn feedback(&self) -> MessagePath {
+        self.message(FEEDBACK_SUFFIX)
+    }
+
+    /// Path of the feedback message published on the feedback topic, which is named
+    /// `<Name>_FeedbackMessage` and holds the goal id next to the feedback.
+    pub fn feedback_message(&self) -> MessagePath {
+        self.message(FEEDBACK_MESSAGE_SUFFIX)
+    }
+
+    /// Path of the service that sends goals, which is named `<Name>_SendGoal`.
+    pub fn send_goal(&self) -> ServicePath {
+        self.service(SEND_GOAL_SUFFIX)
+    }
+
+    /// Path of the service that requests results, which is named `<Name>_GetResult`.
+    pub fn get_result(&self) -> ServicePath {
+        self.service(GET_RESULT_SUFFIX)
+    }
+
+    fn message(&self, suffix: &str) -> MessagePath {
+        MessagePath {
+            package: self.package.clone(),
+            name: format!("{}{}", self.name, suffix),
+        }
+    }
+
+    fn service(&self, suffix: &str) -> ServicePath {
+        ServicePath {
+            package: self.package.clone(),
+            name: format!("{}{}", self.name, suffix),
+        }
+    }
+}
+
+/// Suffix of the goal message of an action.
+pub(crate) const GOAL_SUFFIX: &str = "_Goal";
+/// Suffix of the result message of an action.
+pub(crate) const RESULT_SUFFIX: &str = "_Result";
+/// Suffix of the feedback message of an action.
+pub(crate) const FEEDBACK_SUFFIX: &str = "_Feedback";
+/// Suffix of the feedback message with goal id of an action.
+pub(crate) const FEEDBACK_MESSAGE_SUFFIX: &str = "_FeedbackMessage";
+/// Suffix of the send goal service of an action.
+pub(crate) const SEND_GOAL_SUFFIX: &str = "_SendGoal";
+/// Suffix of the get result service of an action.
+pub(crate) const GET_RESULT_SUFFIX: &str = "_GetResult";
+
+impl Display for ActionPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.package(), self.name())
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ActionPath {
+    type Error = Error;
+
+    fn try_from(value: &'a str) -> Result<Self> {
+        Self::from_combined(value)
+    }
+}
+
+impl From<ActionPath> for String {
+    fn from(src: ActionPath) -> Self {
+        format!("{}", src)
+    }
+}
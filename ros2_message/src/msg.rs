@@ -270,20 +270,6 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Msg<S> {
     pub fn has_header(&self) -> bool {
         self.fields.iter().any(FieldInfo::is_header)
     }
-
-    pub(crate) fn to_random_state(self) -> Msg<RandomState> {
-        let Self {
-            path,
-            fields,
-            source,
-        } = self;
-
-        Msg {
-            path,
-            fields: fields.into_iter().map(FieldInfo::to_random_state).collect(),
-            source,
-        }
-    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -6,10 +6,14 @@ use std::hash::{BuildHasher, RandomState};
 
 static IGNORE_WHITESPACE: &str = r"\s*";
 static ANY_WHITESPACE: &str = r"\s+";
-static FIELD_TYPE: &str = r"([a-zA-Z0-9_/]+)";
+static FIELD_TYPE: &str = r"([a-zA-Z0-9_/]+(?:<=[0-9]+)?)";
+static STRING_TYPE: &str = r"(w?string(?:<=[0-9]+)?)";
 static FIELD_NAME: &str = r"([a-zA-Z][a-zA-Z0-9_]*)";
 static EMPTY_BRACKETS: &str = r"\[\s*\]";
 static NUMBER_BRACKETS: &str = r"\[\s*([0-9]+)\s*\]";
+static BOUNDED_BRACKETS: &str = r"\[\s*<=\s*([0-9]+)\s*\]";
+static ANY_BRACKETS: &str = r"\[\s*(<=)?\s*([0-9]*)\s*\]";
+static SCALAR_VALUE: &str = r"(-?[0-9\.eE\+\-]+|[tT]rue|[fF]alse)";
 
 #[derive(Debug, PartialEq)]
 struct FieldLine {
@@ -17,6 +21,17 @@ struct FieldLine {
     field_name: String,
 }
 
+/// Splits a `srv` or `action` definition into its sections, which are separated by `---` lines.
+pub fn split_sections(data: &str) -> Vec<&str> {
+    lazy_static! {
+        static ref RE_SPLIT: Regex = regex::RegexBuilder::new("^---$")
+            .multi_line(true)
+            .build()
+            .expect("Invalid regex `^---$`");
+    }
+    RE_SPLIT.split(data).collect()
+}
+
 #[inline]
 pub fn match_lines<S: BuildHasher + Default + Clone + core::fmt::Debug>(
     data: &str,
@@ -43,6 +58,14 @@ fn match_line<S: BuildHasher + Default + Clone + core::fmt::Debug>(
             FieldCase::Default(data),
         ));
     }
+    if let Some((info, case, data)) = match_array_default(data.trim()) {
+        return Some(FieldInfo::with_default(
+            &info.field_type,
+            &info.field_name,
+            case,
+            data,
+        ));
+    }
 
     let data = match strip_useless(data) {
         Ok(v) => v,
@@ -73,6 +96,13 @@ fn match_line<S: BuildHasher + Default + Clone + core::fmt::Debug>(
             FieldCase::Array(count),
         ));
     }
+    if let Some((info, bound)) = match_bounded_vector_field(data) {
+        return Some(FieldInfo::new(
+            &info.field_type,
+            &info.field_name,
+            FieldCase::BoundedVector(bound),
+        ));
+    }
     if let Some((info, data)) = match_const_numeric(data) {
         return Some(FieldInfo::new(
             &info.field_type,
@@ -94,8 +124,8 @@ fn match_line<S: BuildHasher + Default + Clone + core::fmt::Debug>(
 fn match_const_string(data: &str) -> Option<(FieldLine, String)> {
     lazy_static! {
         static ref MATCHER: String = format!(
-            r"^(string){}{}{}={}(.*)$",
-            ANY_WHITESPACE, FIELD_NAME, IGNORE_WHITESPACE, IGNORE_WHITESPACE
+            r"^{}{}{}{}={}(.*)$",
+            STRING_TYPE, ANY_WHITESPACE, FIELD_NAME, IGNORE_WHITESPACE, IGNORE_WHITESPACE
         );
         static ref RE: Regex = Regex::new(&MATCHER).unwrap();
     }
@@ -115,8 +145,8 @@ fn match_const_string(data: &str) -> Option<(FieldLine, String)> {
 fn match_default_string(data: &str) -> Option<(FieldLine, String)> {
     lazy_static! {
         static ref MATCHER: String = format!(
-            r"^(string){}{}{} {}(.*)$",
-            ANY_WHITESPACE, FIELD_NAME, IGNORE_WHITESPACE, IGNORE_WHITESPACE
+            r"^{}{}{}{} {}(.*)$",
+            STRING_TYPE, ANY_WHITESPACE, FIELD_NAME, IGNORE_WHITESPACE, IGNORE_WHITESPACE
         );
         static ref RE: Regex = Regex::new(&MATCHER).unwrap();
     }
@@ -124,12 +154,51 @@ fn match_default_string(data: &str) -> Option<(FieldLine, String)> {
         Some(v) => v,
         None => return None,
     };
+    let value = captures.get(3).unwrap().as_str();
+    // Quoted strings can be followed by a comment
+    let value = match split_literal(value) {
+        Some((literal, rest)) if value.starts_with(['"', '\'']) && is_comment(rest) => literal,
+        _ => value,
+    };
     Some((
         FieldLine {
             field_type: captures.get(1).unwrap().as_str().into(),
             field_name: captures.get(2).unwrap().as_str().into(),
         },
-        captures.get(3).unwrap().as_str().into(),
+        value.into(),
+    ))
+}
+
+fn match_array_default(data: &str) -> Option<(FieldLine, FieldCase, String)> {
+    lazy_static! {
+        static ref MATCHER: String = format!(
+            r"^{}{}{}{}{}{}(\[.*)$",
+            FIELD_TYPE, IGNORE_WHITESPACE, ANY_BRACKETS, ANY_WHITESPACE, FIELD_NAME, ANY_WHITESPACE
+        );
+        static ref RE: Regex = Regex::new(&MATCHER).unwrap();
+    }
+    let captures = match RE.captures(data) {
+        Some(v) => v,
+        None => return None,
+    };
+    let (literal, rest) = split_literal(captures.get(5).unwrap().as_str())?;
+    if !is_comment(rest) {
+        return None;
+    }
+    let length = captures.get(3).unwrap().as_str();
+    let case = match (captures.get(2), length.parse().ok()) {
+        (None, None) if length.is_empty() => FieldCase::Vector,
+        (None, Some(length)) => FieldCase::Array(length),
+        (Some(_), Some(bound)) => FieldCase::BoundedVector(bound),
+        _ => return None,
+    };
+    Some((
+        FieldLine {
+            field_type: captures.get(1).unwrap().as_str().into(),
+            field_name: captures.get(4).unwrap().as_str().into(),
+        },
+        case,
+        literal.into(),
     ))
 }
 
@@ -187,11 +256,37 @@ fn match_array_field(data: &str) -> Option<(FieldLine, usize)> {
     ))
 }
 
+fn match_bounded_vector_field(data: &str) -> Option<(FieldLine, usize)> {
+    lazy_static! {
+        static ref MATCHER: String = format!(
+            "^{}{}{}{}{}$",
+            FIELD_TYPE, IGNORE_WHITESPACE, BOUNDED_BRACKETS, ANY_WHITESPACE, FIELD_NAME
+        );
+        static ref RE: Regex = Regex::new(&MATCHER).unwrap();
+    }
+    let captures = match RE.captures(data) {
+        Some(v) => v,
+        None => return None,
+    };
+    Some((
+        FieldLine {
+            field_type: captures.get(1).unwrap().as_str().into(),
+            field_name: captures.get(3).unwrap().as_str().into(),
+        },
+        captures.get(2).unwrap().as_str().parse().unwrap(),
+    ))
+}
+
 fn match_const_numeric(data: &str) -> Option<(FieldLine, String)> {
     lazy_static! {
         static ref MATCHER: String = format!(
-            r"^{}{}{}{}={}(-?[0-9\.eE\+\-]+)$",
-            FIELD_TYPE, ANY_WHITESPACE, FIELD_NAME, IGNORE_WHITESPACE, IGNORE_WHITESPACE
+            r"^{}{}{}{}={}{}$",
+            FIELD_TYPE,
+            ANY_WHITESPACE,
+            FIELD_NAME,
+            IGNORE_WHITESPACE,
+            IGNORE_WHITESPACE,
+            SCALAR_VALUE
         );
         static ref RE: Regex = Regex::new(&MATCHER).unwrap();
     }
@@ -211,8 +306,13 @@ fn match_const_numeric(data: &str) -> Option<(FieldLine, String)> {
 fn match_default_numeric(data: &str) -> Option<(FieldLine, String)> {
     lazy_static! {
         static ref MATCHER: String = format!(
-            r"^{}{}{}{} {}(-?[0-9\.eE\+\-]+)$",
-            FIELD_TYPE, ANY_WHITESPACE, FIELD_NAME, IGNORE_WHITESPACE, IGNORE_WHITESPACE
+            r"^{}{}{}{} {}{}$",
+            FIELD_TYPE,
+            ANY_WHITESPACE,
+            FIELD_NAME,
+            IGNORE_WHITESPACE,
+            IGNORE_WHITESPACE,
+            SCALAR_VALUE
         );
         static ref RE: Regex = Regex::new(&MATCHER).unwrap();
     }
@@ -229,6 +329,41 @@ fn match_default_numeric(data: &str) -> Option<(FieldLine, String)> {
     ))
 }
 
+/// Splits a quoted string or an array literal from the rest of the line, respecting quotes
+/// and escape sequences.
+fn split_literal(data: &str) -> Option<(&str, &str)> {
+    let mut chars = data.char_indices();
+    let (_, first) = chars.next()?;
+    let mut quote = match first {
+        '"' | '\'' => Some(first),
+        '[' => None,
+        _ => return None,
+    };
+    let mut escaped = false;
+    for (i, c) in chars {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => {
+                quote = None;
+                if first == q {
+                    return Some(data.split_at(i + 1));
+                }
+            }
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ']') => return Some(data.split_at(i + 1)),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[inline]
+fn is_comment(data: &str) -> bool {
+    let data = data.trim();
+    data.is_empty() || data.starts_with('#')
+}
+
 #[inline]
 fn strip_useless(data: &str) -> Result<&str> {
     Ok(data
@@ -1,4 +1,5 @@
 use super::*;
+use crate::{DataType, Value};
 
 #[test]
 fn match_field_matches_legal_field() {
@@ -36,6 +37,20 @@ fn match_array_field_matches_legal_field() {
     );
 }
 
+#[test]
+fn match_bounded_vector_field_matches_legal_field() {
+    assert_eq!(
+        (
+            FieldLine {
+                field_type: "string<=8".into(),
+                field_name: "myname".into(),
+            },
+            3,
+        ),
+        match_bounded_vector_field("string<=8 [ <= 3 ]   myname").unwrap()
+    );
+}
+
 #[test]
 fn match_const_string_matches_legal_field() {
     assert_eq!(
@@ -109,6 +124,116 @@ fn match_line_works_on_legal_data() {
     );
 }
 
+#[test]
+fn match_line_works_on_bounded_and_wide_types() {
+    let cases = [
+        (
+            "string<=10 name",
+            DataType::BoundedString(10),
+            FieldCase::Unit,
+        ),
+        (
+            "int32[<=5] values",
+            DataType::I32,
+            FieldCase::BoundedVector(5),
+        ),
+        (
+            "string<=8[<=3] labels",
+            DataType::BoundedString(8),
+            FieldCase::BoundedVector(3),
+        ),
+        ("wstring text", DataType::WString, FieldCase::Unit),
+        (
+            "wstring<=4[2] pair",
+            DataType::BoundedWString(4),
+            FieldCase::Array(2),
+        ),
+        ("wchar[] letters", DataType::WChar, FieldCase::Vector),
+        (
+            "wstring<=5 GREETING=hello",
+            DataType::BoundedWString(5),
+            FieldCase::Const("hello".into()),
+        ),
+    ];
+
+    for (line, datatype, case) in cases {
+        let field = match_line::<RandomState>(line).unwrap().unwrap();
+        assert_eq!(field.datatype(), &datatype);
+        assert_eq!(field.case(), &case);
+        assert_eq!(field.to_string(), line);
+        assert_eq!(
+            match_line::<RandomState>(&field.to_string())
+                .unwrap()
+                .unwrap(),
+            field
+        );
+    }
+
+    assert!(match_line::<RandomState>("int32<=5 value")
+        .unwrap()
+        .is_err());
+}
+
+#[test]
+fn match_line_parses_array_and_string_defaults() {
+    let field = match_line::<RandomState>("int32[] samples [-200, -100, 0, 100, 200] # comment")
+        .unwrap()
+        .unwrap();
+    assert_eq!(field.case(), &FieldCase::Vector);
+    assert_eq!(
+        field.default_value(),
+        Some(&Value::Array(
+            [-200, -100, 0, 100, 200].map(Value::I32).to_vec()
+        ))
+    );
+    assert_eq!(
+        field.to_string(),
+        "int32[] samples [-200, -100, 0, 100, 200]"
+    );
+
+    let field = match_line::<RandomState>(r#"string[3] names ["a", "b, c", 'd"']"#)
+        .unwrap()
+        .unwrap();
+    assert_eq!(field.case(), &FieldCase::Array(3));
+    assert_eq!(
+        field.default_value(),
+        Some(&Value::Array(vec![
+            Value::String("a".into()),
+            Value::String("b, c".into()),
+            Value::String("d\"".into()),
+        ]))
+    );
+
+    let field =
+        match_line::<RandomState>(r#"string s "hello \"world\" # not a comment"  # comment"#)
+            .unwrap()
+            .unwrap();
+    assert_eq!(
+        field.default_value(),
+        Some(&Value::String(r#"hello "world" # not a comment"#.into()))
+    );
+
+    let field = match_line::<RandomState>("bool[<=2] flags [true, False]")
+        .unwrap()
+        .unwrap();
+    assert_eq!(field.case(), &FieldCase::BoundedVector(2));
+    assert_eq!(
+        field.default_value(),
+        Some(&Value::Array(vec![Value::Bool(true), Value::Bool(false)]))
+    );
+    let field = match_line::<RandomState>("bool flag false")
+        .unwrap()
+        .unwrap();
+    assert_eq!(field.default_value(), Some(&Value::Bool(false)));
+
+    assert!(match_line::<RandomState>("int32[2] pair [1, 2, 3]")
+        .unwrap()
+        .is_err());
+    assert!(match_line::<RandomState>("int32[] values [1, x]")
+        .unwrap()
+        .is_err());
+}
+
 #[test]
 fn match_lines_parses_real_messages() {
     let data = match_lines::<RandomState>(include_str!(
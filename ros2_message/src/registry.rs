@@ -0,0 +1,510 @@
+use crate::{Action, ActionPath, Error, MessagePath, Msg, Result, ServicePath, Srv};
+use derive_where::derive_where;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{BuildHasher, RandomState};
+use std::path::Path;
+
+/// A collection of message, service and action definitions, usually loaded from `msg`, `srv`
+/// and `action` files.
+///
+/// Definitions are looked up in package directories of the `<root>/<package>/msg/<Name>.msg`,
+/// `<root>/<package>/srv/<Name>.srv` and `<root>/<package>/action/<Name>.action` layout, which
+/// is how ROS 2 installs them to the `share` directory of an install prefix.
+///
+/// Dependencies between messages are resolved lazily, since they may be spread across multiple
+/// roots. Use [Self::validate()] once all roots are loaded to find missing or cyclic dependencies.
+///
+/// The `builtin_interfaces/Time` and `builtin_interfaces/Duration` messages that every ROS 2
+/// installation provides are always present, loading a `builtin_interfaces` package replaces them.
+///
+/// # Examples
+///
+/// ```
+/// # use ros2_message::MessagePath;
+/// # type MessageRegistry = ros2_message::MessageRegistry<std::hash::RandomState>;
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut registry = MessageRegistry::new();
+/// registry.add_msg("std_msgs/Header".try_into()?, "builtin_interfaces/Time stamp\nstring frame_id")?;
+///
+/// let dependencies = registry.dependencies(&MessagePath::new("std_msgs", "Header")?)?;
+/// assert_eq!(dependencies.len(), 1);
+/// assert_eq!(dependencies[0].path(), &MessagePath::new("builtin_interfaces", "Time")?);
+/// assert!(registry.validate().is_ok());
+/// # Ok(())
+/// # }
+/// ```
+#[derive_where(Clone, Debug)]
+pub struct MessageRegistry<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
+    messages: HashMap<MessagePath, Msg<S>, S>,
+    services: HashMap<ServicePath, Srv<S>, S>,
+    actions: HashMap<ActionPath, Action<S>, S>,
+}
+
+/// Messages that are part of every ROS 2 installation.
+pub(crate) const BUILTIN_MESSAGES: &[(&str, &str)] = &[
+    ("builtin_interfaces/Time", "int32 sec\nuint32 nanosec"),
+    ("builtin_interfaces/Duration", "int32 sec\nuint32 nanosec"),
+];
+
+/// Separates the definitions of a full message definition, see [MessageRegistry::definition()].
+const DEFINITION_SEPARATOR: &str =
+    "================================================================================";
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Default for MessageRegistry<S> {
+    fn default() -> Self {
+        let mut registry = MessageRegistry {
+            messages: HashMap::default(),
+            services: HashMap::default(),
+            actions: HashMap::default(),
+        };
+        for (path, source) in BUILTIN_MESSAGES {
+            let path = MessagePath::try_from(*path).expect("Invalid builtin message path");
+            registry
+                .add_msg(path, source)
+                .expect("Invalid builtin message definition");
+        }
+
+        registry
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> FromIterator<Msg<S>>
+    for MessageRegistry<S>
+{
+    fn from_iter<T: IntoIterator<Item = Msg<S>>>(iter: T) -> Self {
+        let mut registry = Self::default();
+        for msg in iter {
+            registry.insert_msg(msg);
+        }
+
+        registry
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> MessageRegistry<S> {
+    /// Create a registry that only contains the builtin messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads all definitions of the packages inside of `root`, following the
+    /// `<root>/<package>/msg/<Name>.msg`, `<root>/<package>/srv/<Name>.srv` and
+    /// `<root>/<package>/action/<Name>.action` layout.
+    ///
+    /// Directories without `msg`, `srv` or `action` subdirectories and files of other types are
+    /// ignored.
+    /// Definitions that are already present get replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a directory or file can not be read, or a definition can not be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::{MessagePath, ServicePath};
+    /// # type MessageRegistry = ros2_message::MessageRegistry<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut registry = MessageRegistry::new();
+    /// registry.load_root(concat!(env!("CARGO_MANIFEST_DIR"), "/../msg_examples"))?;
+    ///
+    /// assert!(registry.msg(&MessagePath::new("geometry_msgs", "Pose")?).is_some());
+    /// assert!(registry.srv(&ServicePath::new("roscpp_tutorials", "TwoInts")?).is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_root(&mut self, root: impl AsRef<Path>) -> Result<()> {
+        for package_dir in read_dir(root.as_ref())? {
+            if !package_dir.is_dir() {
+                continue;
+            }
+            let Some(package) = package_dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            for (name, source) in read_definitions(&package_dir.join("msg"), "msg")? {
+                self.add_msg(MessagePath::new(package, name)?, &source)?;
+            }
+            for (name, source) in read_definitions(&package_dir.join("srv"), "srv")? {
+                self.add_srv(ServicePath::new(package, name)?, source)?;
+            }
+            for (name, source) in read_definitions(&package_dir.join("action"), "action")? {
+                self.add_action(ActionPath::new(package, name)?, source)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the definitions of an install prefix, which keeps its packages in `<prefix>/share`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a directory or file can not be read, or a definition can not be parsed.
+    pub fn load_prefix(&mut self, prefix: impl AsRef<Path>) -> Result<()> {
+        self.load_root(prefix.as_ref().join("share"))
+    }
+
+    /// Loads the definitions of every install prefix listed in the `AMENT_PREFIX_PATH`
+    /// environment variable, which is set by sourcing a ROS 2 installation or workspace.
+    ///
+    /// Prefixes listed first take precedence over the ones listed later, like they do for ROS 2.
+    /// Nothing is loaded if the variable is not set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a directory or file can not be read, or a definition can not be parsed.
+    pub fn load_ament_prefix_path(&mut self) -> Result<()> {
+        let Some(prefixes) = std::env::var_os("AMENT_PREFIX_PATH") else {
+            return Ok(());
+        };
+        let prefixes: Vec<_> = std::env::split_paths(&prefixes).collect();
+        for prefix in prefixes.iter().rev() {
+            if prefix.join("share").is_dir() {
+                self.load_prefix(prefix)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses and adds a message definition, replacing an existing one with the same path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message can not be parsed.
+    pub fn add_msg(&mut self, path: MessagePath, source: &str) -> Result<&Msg<S>> {
+        let msg = Msg::new(path, source)?;
+        Ok(self.insert_msg(msg))
+    }
+
+    /// Adds an already parsed message, replacing an existing one with the same path.
+    pub fn insert_msg(&mut self, msg: Msg<S>) -> &Msg<S> {
+        let path = msg.path().clone();
+        self.messages.insert(path.clone(), msg);
+        &self.messages[&path]
+    }
+
+    /// Parses and adds a service definition, replacing an existing one with the same path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the service can not be parsed.
+    pub fn add_srv(&mut self, path: ServicePath, source: impl Into<String>) -> Result<&Srv<S>> {
+        let srv = Srv::new(path, source)?;
+        Ok(self.insert_srv(srv))
+    }
+
+    /// Adds an already parsed service, replacing an existing one with the same path.
+    pub fn insert_srv(&mut self, srv: Srv<S>) -> &Srv<S> {
+        let path = srv.path().clone();
+        self.services.insert(path.clone(), srv);
+        &self.services[&path]
+    }
+
+    /// Parses and adds an action definition, replacing an existing one with the same path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the action can not be parsed.
+    pub fn add_action(
+        &mut self,
+        path: ActionPath,
+        source: impl Into<String>,
+    ) -> Result<&Action<S>> {
+        let action = Action::new(path, source)?;
+        Ok(self.insert_action(action))
+    }
+
+    /// Adds an already parsed action, replacing an existing one with the same path.
+    pub fn insert_action(&mut self, action: Action<S>) -> &Action<S> {
+        let path = action.path().clone();
+        self.actions.insert(path.clone(), action);
+        &self.actions[&path]
+    }
+
+    /// Returns the message of the provided path if present.
+    pub fn msg(&self, path: &MessagePath) -> Option<&Msg<S>> {
+        self.messages.get(path)
+    }
+
+    /// Returns the service of the provided path if present.
+    pub fn srv(&self, path: &ServicePath) -> Option<&Srv<S>> {
+        self.services.get(path)
+    }
+
+    /// Returns the action of the provided path if present.
+    pub fn action(&self, path: &ActionPath) -> Option<&Action<S>> {
+        self.actions.get(path)
+    }
+
+    /// Returns an iterator over all messages in arbitrary order.
+    pub fn messages(&self) -> impl Iterator<Item = &Msg<S>> {
+        self.messages.values()
+    }
+
+    /// Returns an iterator over all services in arbitrary order.
+    pub fn services(&self) -> impl Iterator<Item = &Srv<S>> {
+        self.services.values()
+    }
+
+    /// Returns an iterator over all actions in arbitrary order.
+    pub fn actions(&self) -> impl Iterator<Item = &Action<S>> {
+        self.actions.values()
+    }
+
+    /// Returns all messages that the message of the provided path depends upon, directly or
+    /// indirectly, see [Self::resolve()].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message itself or one of its dependencies is missing, or if the
+    /// dependencies form a cycle.
+    pub fn dependencies(&self, path: &MessagePath) -> Result<Vec<&Msg<S>>> {
+        let msg = self
+            .msg(path)
+            .ok_or_else(|| missing(path.package(), path.name()))?;
+        self.resolve(msg)
+    }
+
+    /// Returns all messages that the provided message depends upon, directly or indirectly.
+    ///
+    /// The message does not need to be part of the registry. Every dependency is listed once,
+    /// in the order they are first encountered by a depth first traversal of the fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of the dependencies is missing, or if they form a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::{Error, MessagePath};
+    /// # type MessageRegistry = ros2_message::MessageRegistry<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut registry = MessageRegistry::new();
+    /// registry.add_msg("foo/Tree".try_into()?, "Node root")?;
+    /// registry.add_msg("foo/Node".try_into()?, "Node[] children")?;
+    ///
+    /// let tree = registry.msg(&MessagePath::new("foo", "Tree")?).unwrap();
+    /// assert!(matches!(registry.resolve(tree), Err(Error::CyclicDependency { .. })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve<'a>(&'a self, msg: &Msg<S>) -> Result<Vec<&'a Msg<S>>> {
+        let mut resolved = vec![];
+        self.collect_dependencies(msg, &mut vec![msg.path().clone()], &mut resolved)?;
+        Ok(resolved)
+    }
+
+    fn collect_dependencies<'a>(
+        &'a self,
+        msg: &Msg<S>,
+        stack: &mut Vec<MessagePath>,
+        resolved: &mut Vec<&'a Msg<S>>,
+    ) -> Result<()> {
+        for path in msg.dependencies() {
+            if let Some(position) = stack.iter().position(|p| *p == path) {
+                let mut cycle = stack[position..].to_vec();
+                cycle.push(path);
+                return Err(Error::CyclicDependency { cycle });
+            }
+            if resolved.iter().any(|dependency| dependency.path() == &path) {
+                continue;
+            }
+
+            let dependency = self
+                .msg(&path)
+                .ok_or_else(|| missing(path.package(), path.name()))?;
+            resolved.push(dependency);
+            stack.push(path);
+            self.collect_dependencies(dependency, stack, resolved)?;
+            stack.pop();
+        }
+
+        Ok(())
+    }
+
+    /// Returns the full definition of a message, which is its source followed by the sources of
+    /// all its dependencies.
+    ///
+    /// This is the format rosbag2 stores message definitions in and [DynamicMsg](crate::dynamic::DynamicMsg)
+    /// is created from. Dependencies are listed once, in the order of [Self::resolve()], each
+    /// preceded by a line of `=` and a `MSG: package/Name` line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message itself or one of its dependencies is missing, or if the
+    /// dependencies form a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::MessagePath;
+    /// # type MessageRegistry = ros2_message::MessageRegistry<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut registry = MessageRegistry::new();
+    /// registry.add_msg("foo/Stamped".try_into()?, "builtin_interfaces/Time stamp\nfloat32 value")?;
+    ///
+    /// assert_eq!(
+    ///     registry.definition(&MessagePath::new("foo", "Stamped")?)?,
+    ///     r#"builtin_interfaces/Time stamp
+    /// float32 value
+    /// ================================================================================
+    /// MSG: builtin_interfaces/Time
+    /// int32 sec
+    /// uint32 nanosec"#,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn definition(&self, path: &MessagePath) -> Result<String> {
+        let msg = self
+            .msg(path)
+            .ok_or_else(|| missing(path.package(), path.name()))?;
+
+        let mut definition = msg.source().to_owned();
+        for dependency in self.resolve(msg)? {
+            definition.push('\n');
+            definition.push_str(DEFINITION_SEPARATOR);
+            definition.push_str("\nMSG: ");
+            definition.push_str(&dependency.path().to_string());
+            definition.push('\n');
+            definition.push_str(dependency.source());
+        }
+
+        Ok(definition)
+    }
+
+    /// Creates a [DynamicMsg](crate::dynamic::DynamicMsg) for the message of the provided path,
+    /// using its [full definition](Self::definition()).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message itself or one of its dependencies is missing, or if the
+    /// dependencies form a cycle.
+    #[cfg(feature = "decode")]
+    pub fn dynamic_msg(&self, path: &MessagePath) -> Result<crate::dynamic::DynamicMsg<S>> {
+        crate::dynamic::DynamicMsg::new(&path.to_string(), &self.definition(path)?)
+    }
+
+    /// Creates a [DynamicSrv](crate::dynamic::DynamicSrv) for the service of the provided path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the service itself or one of its dependencies is missing, or if the
+    /// dependencies form a cycle. A missing service is reported as
+    /// [Error::MessageDependencyMissing] like a missing dependency.
+    #[cfg(feature = "decode")]
+    pub fn dynamic_srv(&self, path: &ServicePath) -> Result<crate::dynamic::DynamicSrv<S>> {
+        let srv = self
+            .srv(path)
+            .ok_or_else(|| missing(path.package(), path.name()))?;
+        let mut dependencies = self.resolve(srv.request())?;
+        dependencies.extend(self.resolve(srv.response())?);
+
+        Ok(crate::dynamic::DynamicSrv::from_srv(
+            srv.clone(),
+            dependencies.into_iter().cloned(),
+        ))
+    }
+
+    /// Creates a [DynamicAction](crate::dynamic::DynamicAction) for the action of the provided path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the action itself or one of its dependencies is missing, or if the
+    /// dependencies form a cycle. A missing action is reported as
+    /// [Error::MessageDependencyMissing] like a missing dependency.
+    #[cfg(feature = "decode")]
+    pub fn dynamic_action(&self, path: &ActionPath) -> Result<crate::dynamic::DynamicAction<S>> {
+        let action = self
+            .action(path)
+            .ok_or_else(|| missing(path.package(), path.name()))?;
+        let mut dependencies = vec![];
+        for msg in [action.goal(), action.result(), action.feedback()] {
+            dependencies.extend(self.resolve(msg)?);
+        }
+
+        Ok(crate::dynamic::DynamicAction::from_action(
+            action.clone(),
+            dependencies.into_iter().cloned(),
+        ))
+    }
+
+    /// Checks that the dependencies of every message, service and action can be resolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first missing or cyclic dependency that is found.
+    pub fn validate(&self) -> Result<()> {
+        for msg in self.messages.values() {
+            self.resolve(msg)?;
+        }
+        for srv in self.services.values() {
+            self.resolve(srv.request())?;
+            self.resolve(srv.response())?;
+        }
+        for action in self.actions.values() {
+            self.resolve(action.goal())?;
+            self.resolve(action.result())?;
+            self.resolve(action.feedback())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error for a message, service or action that is not part of the registry
+fn missing(package: &str, name: &str) -> Error {
+    Error::MessageDependencyMissing {
+        package: package.into(),
+        name: name.into(),
+    }
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let read_error = |err| Error::ReadDefinition {
+        path: dir.to_owned(),
+        err,
+    };
+    let mut entries = fs::read_dir(dir)
+        .map_err(read_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(read_error)?;
+    // Keep loading order independent of the file system
+    entries.sort();
+
+    Ok(entries)
+}
+
+/// Reads all files with the provided extension in `dir`, returning their names without the
+/// extension and their content. A missing directory contains no definitions.
+fn read_definitions(dir: &Path, extension: &str) -> Result<Vec<(String, String)>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut definitions = vec![];
+    for file in read_dir(dir)? {
+        if file.extension().and_then(|e| e.to_str()) != Some(extension) || !file.is_file() {
+            continue;
+        }
+        let Some(name) = file.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let source = fs::read_to_string(&file).map_err(|err| Error::ReadDefinition {
+            path: file.clone(),
+            err,
+        })?;
+        definitions.push((name.to_owned(), source));
+    }
+
+    Ok(definitions)
+}
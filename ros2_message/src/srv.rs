@@ -1,7 +1,6 @@
-use crate::{Error, MessagePath, Msg, Result};
+use crate::parse_msg::split_sections;
+use crate::{Error, Msg, Result, ServicePath};
 use derive_where::derive_where;
-use lazy_static::lazy_static;
-use regex::RegexBuilder;
 use serde_derive::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt;
@@ -14,12 +13,26 @@ use std::hash::{BuildHasher, RandomState};
 #[serde(into = "SrvSerde")]
 #[serde(try_from = "SrvSerde")]
 pub struct Srv<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
-    path: MessagePath,
+    path: ServicePath,
     source: String,
     req: Msg<S>,
     res: Msg<S>,
+    event: Msg<S>,
 }
 
+/// Path and definition of the info that every service event carries.
+pub(crate) const SERVICE_EVENT_INFO: (&str, &str) = (
+    "service_msgs/ServiceEventInfo",
+    r#"uint8 REQUEST_SENT = 0
+uint8 REQUEST_RECEIVED = 1
+uint8 RESPONSE_SENT = 2
+uint8 RESPONSE_RECEIVED = 3
+uint8 event_type
+builtin_interfaces/Time stamp
+char[16] client_gid
+int64 sequence_number"#,
+);
+
 impl<S: BuildHasher + Default + Clone + core::fmt::Debug> fmt::Display for Srv<S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.source.fmt(f)
@@ -57,24 +70,28 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Srv<S> {
     /// )?;
     ///
     /// assert_eq!(service.path(), &"foo/Bar".try_into()?);
+    /// assert_eq!(service.request().path(), &"foo/Bar_Request".try_into()?);
     /// assert_eq!(service.request().fields().len(), 6);
+    /// assert_eq!(service.response().path(), &"foo/Bar_Response".try_into()?);
     /// assert_eq!(service.response().fields().len(), 3);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new(path: MessagePath, source: impl Into<String>) -> Result<Srv<S>> {
+    pub fn new(path: ServicePath, source: impl Into<String>) -> Result<Srv<S>> {
         let source = source.into();
         let (req, res) = Self::build_req_res(&path, &source)?;
+        let event = Self::build_event(&path)?;
         Ok(Srv {
             path,
             source,
             req,
             res,
+            event,
         })
     }
 
     /// Returns the path of the service.
-    pub fn path(&self) -> &MessagePath {
+    pub fn path(&self) -> &ServicePath {
         &self.path
     }
 
@@ -93,14 +110,51 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Srv<S> {
         &self.res
     }
 
-    fn build_req_res(path: &MessagePath, source: &str) -> Result<(Msg<S>, Msg<S>)> {
-        lazy_static! {
-            static ref RE_SPLIT: regex::Regex = RegexBuilder::new("^---$")
-                .multi_line(true)
-                .build()
-                .expect("Invalid regex `^---$`");
-        }
-        let (req, res) = match RE_SPLIT.split(source).collect::<Vec<_>>().as_slice() {
+    /// Returns the event message, which ROS 2 service introspection publishes for every
+    /// request and response.
+    ///
+    /// It holds a `service_msgs/ServiceEventInfo` and either the request or the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Srv = ros2_message::Srv<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let service = Srv::new("foo/Bar".try_into()?, "uint32 a\n---\nuint32 b")?;
+    ///
+    /// assert_eq!(service.event().path(), &"foo/Bar_Event".try_into()?);
+    /// assert_eq!(
+    ///     service.event().dependencies(),
+    ///     vec![
+    ///         "service_msgs/ServiceEventInfo".try_into()?,
+    ///         "foo/Bar_Request".try_into()?,
+    ///         "foo/Bar_Response".try_into()?,
+    ///     ],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn event(&self) -> &Msg<S> {
+        &self.event
+    }
+
+    fn build_event(path: &ServicePath) -> Result<Msg<S>> {
+        let request = path.request();
+        let response = path.response();
+        Msg::new(
+            path.event(),
+            &format!(
+                "{} info\n{}[<=1] request\n{}[<=1] response",
+                SERVICE_EVENT_INFO.0,
+                request.name(),
+                response.name()
+            ),
+        )
+    }
+
+    fn build_req_res(path: &ServicePath, source: &str) -> Result<(Msg<S>, Msg<S>)> {
+        let (req, res) = match split_sections(source).as_slice() {
             &[req] => (req, ""),
             &[req, res] => (req, res),
             &[] => {
@@ -119,15 +173,15 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Srv<S> {
         };
 
         Ok((
-            Msg::new(path.peer(format!("{}Req", path.name())), req)?,
-            Msg::new(path.peer(format!("{}Res", path.name())), res)?,
+            Msg::new(path.request(), req)?,
+            Msg::new(path.response(), res)?,
         ))
     }
 }
 
 #[derive(Serialize, Deserialize)]
 struct SrvSerde {
-    path: MessagePath,
+    path: ServicePath,
     source: String,
 }
 
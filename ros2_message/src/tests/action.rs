@@ -0,0 +1,171 @@
+use crate::{Action, ActionPath, MessagePath, MessageRegistry};
+use std::hash::RandomState;
+
+const MSG_EXAMPLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../msg_examples");
+
+#[test]
+fn action_needs_three_parts() {
+    let path: ActionPath = "foo/Bar".try_into().unwrap();
+    assert!(Action::<RandomState>::new(path.clone(), "int32 a\n---\nint32 b").is_err());
+    assert!(Action::<RandomState>::new(path.clone(), "---\n---\n---").is_err());
+    assert!(Action::<RandomState>::new(path, "---\n---").is_ok());
+}
+
+#[test]
+fn derives_services_and_feedback_message() {
+    let action = Action::<RandomState>::new(
+        "foo/Fibonacci".try_into().unwrap(),
+        "int32 order\n---\nint32[] sequence\n---\nint32[] partial_sequence",
+    )
+    .unwrap();
+
+    let fields = |msg: &crate::Msg<RandomState>| -> Vec<String> {
+        msg.fields().iter().map(ToString::to_string).collect()
+    };
+    assert_eq!(
+        fields(action.send_goal().request()),
+        ["unique_identifier_msgs/UUID goal_id", "Fibonacci_Goal goal"]
+    );
+    assert_eq!(
+        fields(action.send_goal().response()),
+        ["bool accepted", "builtin_interfaces/Time stamp"]
+    );
+    assert_eq!(
+        fields(action.get_result().request()),
+        ["unique_identifier_msgs/UUID goal_id"]
+    );
+    assert_eq!(
+        fields(action.get_result().response()),
+        ["int8 status", "Fibonacci_Result result"]
+    );
+    assert_eq!(
+        fields(action.feedback_message()),
+        [
+            "unique_identifier_msgs/UUID goal_id",
+            "Fibonacci_Feedback feedback"
+        ]
+    );
+    assert_eq!(
+        action.get_result().response().dependencies(),
+        [MessagePath::new("foo", "Fibonacci_Result").unwrap()]
+    );
+    assert_eq!(
+        action.get_result().event().path(),
+        &MessagePath::new("foo", "Fibonacci_GetResult_Event").unwrap()
+    );
+}
+
+#[test]
+fn loads_actions_from_package_directories() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    registry.load_root(MSG_EXAMPLES).unwrap();
+
+    let path = ActionPath::new("action_tutorials_interfaces", "Fibonacci").unwrap();
+    let action = registry.action(&path).unwrap();
+    assert_eq!(action.goal().fields()[0].name(), "order");
+    assert_eq!(registry.actions().count(), 1);
+}
+
+#[test]
+fn paths_reject_other_interface_kinds() {
+    assert!(ActionPath::try_from("foo/action/Bar").is_ok());
+    assert!(ActionPath::try_from("foo/Bar").is_ok());
+    assert!(ActionPath::try_from("foo/msg/Bar").is_err());
+    assert!(MessagePath::try_from("foo/action/Bar").is_err());
+}
+
+#[cfg(feature = "decode")]
+mod dynamic {
+    use super::MSG_EXAMPLES;
+    use crate::dynamic::DynamicAction;
+    use crate::{ActionPath, MessageRegistry, MessageValue, Value};
+    use std::hash::RandomState;
+
+    const FIBONACCI: &str = "int32 order\n---\nint32[] sequence\n---\nint32[] partial_sequence";
+
+    fn message<const N: usize>(
+        fields: [(&str, Value<RandomState>); N],
+    ) -> MessageValue<RandomState> {
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect()
+    }
+
+    fn goal_id() -> Value<RandomState> {
+        [("uuid", Value::Array(vec![Value::U8(3); 16]))]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn decodes_derived_services_and_messages() {
+        let action = DynamicAction::<RandomState>::new(
+            "package/action/Fibonacci_SendGoal_Request",
+            FIBONACCI,
+        )
+        .unwrap();
+        assert_eq!(
+            action.path(),
+            &ActionPath::new("package", "Fibonacci").unwrap()
+        );
+
+        let send_goal = message([
+            ("goal_id", goal_id()),
+            ("goal", [("order", Value::I32(5))].into_iter().collect()),
+        ]);
+        let bytes = action.send_goal().request().encode(&send_goal).unwrap();
+        assert_eq!(
+            action.send_goal().decode_request(&bytes[..]).unwrap(),
+            send_goal
+        );
+
+        let result = message([
+            ("status", Value::I8(4)),
+            (
+                "result",
+                [("sequence", Value::Array(vec![Value::I32(0), Value::I32(1)]))]
+                    .into_iter()
+                    .collect(),
+            ),
+        ]);
+        let bytes = action.get_result().response().encode(&result).unwrap();
+        assert_eq!(
+            action.get_result().decode_response(&bytes[..]).unwrap(),
+            result
+        );
+
+        let feedback = message([
+            ("goal_id", goal_id()),
+            (
+                "feedback",
+                [("partial_sequence", Value::Array(vec![Value::I32(0)]))]
+                    .into_iter()
+                    .collect(),
+            ),
+        ]);
+        let bytes = action.feedback_message().encode(&feedback).unwrap();
+        assert_eq!(
+            action.feedback_message().decode(&bytes[..]).unwrap(),
+            feedback
+        );
+    }
+
+    #[test]
+    fn creates_actions_from_registry() {
+        let mut registry = MessageRegistry::<RandomState>::new();
+        registry.load_root(MSG_EXAMPLES).unwrap();
+
+        let path = ActionPath::new("action_tutorials_interfaces", "Fibonacci").unwrap();
+        let action = registry.dynamic_action(&path).unwrap();
+        let goal_request = message([
+            ("goal_id", goal_id()),
+            ("goal", [("order", Value::I32(2))].into_iter().collect()),
+        ]);
+        let bytes = action.send_goal().request().encode(&goal_request).unwrap();
+        assert_eq!(
+            action.send_goal().decode_request(&bytes[..]).unwrap(),
+            goal_request
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::dynamic::{DecodeOptions, DynamicMsg};
+use crate::{Error, MessageValue, Time, Value};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float32Type, Float64Type, Int32Type, UInt16Type, UInt32Type};
+use arrow_array::Array;
+use arrow_schema::{DataType, Field, Fields, Schema};
+use std::hash::RandomState;
+use std::sync::Arc;
+
+fn message<const N: usize>(fields: [(&str, Value<RandomState>); N]) -> Value<RandomState> {
+    fields.into_iter().collect()
+}
+
+fn track(id: u16, points: &[(f64, f64)]) -> MessageValue<RandomState> {
+    let point = |(x, y): (f64, f64)| message([("x", Value::F64(x)), ("y", Value::F64(y))]);
+    message([
+        (
+            "stamp",
+            message([("sec", Value::I32(5)), ("nanosec", Value::U32(id.into()))]),
+        ),
+        ("received", Value::Time(Time { sec: 6, nsec: 7 })),
+        ("frame_id", Value::String(format!("frame{id}"))),
+        ("letter", Value::U16(id)),
+        ("origin", point((-1.0, 1.0))),
+        (
+            "points",
+            Value::Array(points.iter().copied().map(point).collect()),
+        ),
+        (
+            "gains",
+            Value::Array(vec![Value::F32(0.5), Value::F32(id.into())]),
+        ),
+        ("flags", Value::Array(vec![Value::I32(id.into())])),
+        ("valid", Value::Bool(id.is_multiple_of(2))),
+    ])
+    .try_into_map()
+    .unwrap()
+}
+
+#[test]
+fn arrow_schema_follows_the_message_definition() {
+    let msg_definition = r#"
+uint8 KIND=3
+builtin_interfaces/Time stamp
+time received
+string frame_id
+wchar letter
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains
+int32[<=2] flags
+bool valid
+
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Track", msg_definition)
+            .expect("The message definition was invalid");
+    let point = Fields::from(vec![
+        Field::new("x", DataType::Float64, false),
+        Field::new("y", DataType::Float64, false),
+    ]);
+    let list_of = |datatype| Arc::new(Field::new_list_field(datatype, false));
+
+    let expected = Schema::new(vec![
+        Field::new(
+            "stamp",
+            DataType::Struct(Fields::from(vec![
+                Field::new("sec", DataType::Int32, false),
+                Field::new("nanosec", DataType::UInt32, false),
+            ])),
+            false,
+        ),
+        Field::new(
+            "received",
+            DataType::Struct(Fields::from(vec![
+                Field::new("sec", DataType::UInt32, false),
+                Field::new("nanosec", DataType::UInt32, false),
+            ])),
+            false,
+        ),
+        Field::new("frame_id", DataType::Utf8, false),
+        Field::new("letter", DataType::UInt16, false),
+        Field::new("origin", DataType::Struct(point.clone()), false),
+        Field::new(
+            "points",
+            DataType::List(list_of(DataType::Struct(point))),
+            false,
+        ),
+        Field::new(
+            "gains",
+            DataType::FixedSizeList(list_of(DataType::Float32), 2),
+            false,
+        ),
+        Field::new("flags", DataType::List(list_of(DataType::Int32)), false),
+        Field::new("valid", DataType::Boolean, false),
+    ]);
+
+    assert_eq!(dynamic_message.arrow_schema().unwrap(), expected);
+    assert_eq!(
+        *dynamic_message.record_batch_builder().unwrap().schema(),
+        expected
+    );
+}
+
+#[test]
+fn record_batch_builder_collects_decoded_messages() {
+    let msg_definition = r#"
+uint8 KIND=3
+builtin_interfaces/Time stamp
+time received
+string frame_id
+wchar letter
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains
+int32[<=2] flags
+bool valid
+
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Track", msg_definition)
+            .expect("The message definition was invalid");
+    let mapped = dynamic_message.clone().with_options(DecodeOptions {
+        map_builtin_time: true,
+        primitive_arrays: true,
+        ..Default::default()
+    });
+    let mut builder = dynamic_message.record_batch_builder().unwrap();
+
+    let first = dynamic_message.encode(&track(1, &[])).unwrap();
+    let second = dynamic_message
+        .encode(&track(2, &[(1.0, 2.0), (3.0, 4.0)]))
+        .unwrap();
+    builder
+        .append(&dynamic_message.decode(&first[..]).unwrap())
+        .unwrap();
+    builder
+        .append(&mapped.decode(&second[..]).unwrap())
+        .unwrap();
+    assert_eq!(builder.len(), 2);
+
+    let batch = builder.finish().unwrap();
+    assert!(builder.is_empty());
+    assert_eq!(batch.num_rows(), 2);
+    assert_eq!(batch.num_columns(), 9);
+
+    let stamp = batch.column(0).as_struct();
+    assert_eq!(
+        stamp.column(1).as_primitive::<UInt32Type>().values(),
+        &[1, 2]
+    );
+    let received = batch.column(1).as_struct();
+    assert_eq!(
+        received.column(0).as_primitive::<UInt32Type>().values(),
+        &[6, 6]
+    );
+    let frame_id = batch.column(2).as_string::<i32>();
+    assert_eq!(frame_id.value(0), "frame1");
+    assert_eq!(frame_id.value(1), "frame2");
+    assert_eq!(
+        batch.column(3).as_primitive::<UInt16Type>().values(),
+        &[1, 2]
+    );
+
+    let points = batch.column(5).as_list::<i32>();
+    assert_eq!(points.value_offsets(), &[0, 0, 2]);
+    let xs = points.values().as_struct().column(0).clone();
+    assert_eq!(xs.as_primitive::<Float64Type>().values(), &[1.0, 3.0]);
+
+    let gains = batch.column(6).as_fixed_size_list();
+    assert_eq!(
+        gains.values().as_primitive::<Float32Type>().values(),
+        &[0.5, 1.0, 0.5, 2.0]
+    );
+    let flags = batch.column(7).as_list::<i32>();
+    assert_eq!(flags.values().as_primitive::<Int32Type>().values(), &[1, 2]);
+    let valid = batch.column(8).as_boolean();
+    assert!(!valid.value(0) && valid.value(1));
+    assert_eq!(batch.column(8).null_count(), 0);
+
+    // Finished builders start a new batch
+    builder.append(&track(3, &[(5.0, 6.0)])).unwrap();
+    let batch = builder.finish().unwrap();
+    assert_eq!(batch.num_rows(), 1);
+    assert_eq!(batch.column(5).as_list::<i32>().value_offsets(), &[0, 1]);
+}
+
+#[test]
+fn mismatched_messages_leave_the_builder_unchanged() {
+    let msg_definition = r#"
+uint8 KIND=3
+builtin_interfaces/Time stamp
+time received
+string frame_id
+wchar letter
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains
+int32[<=2] flags
+bool valid
+
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Track", msg_definition)
+            .expect("The message definition was invalid");
+    let mut builder = dynamic_message.record_batch_builder().unwrap();
+    builder.append(&track(1, &[(1.0, 2.0)])).unwrap();
+
+    let mut invalid = track(2, &[(1.0, 2.0), (3.0, 4.0)]);
+    let Value::Array(points) = &mut invalid["points"] else {
+        unreachable!()
+    };
+    points[1] = message([("x", Value::F32(3.0)), ("y", Value::F64(4.0))]);
+
+    match builder.append(&invalid).unwrap_err() {
+        Error::ArrowConversion { path, reason } => {
+            assert_eq!(path, "points[1].x");
+            assert_eq!(reason, "expected a value of type `Float64`");
+        }
+        err => panic!("Expected a conversion error, got {err:?}"),
+    }
+
+    let mut missing = track(2, &[]);
+    missing.shift_remove("valid");
+    assert_eq!(
+        builder.append(&missing).unwrap_err().to_string(),
+        "failed to convert field `valid` to Arrow: the field is missing"
+    );
+
+    let mut wrong_length = track(2, &[]);
+    wrong_length["gains"] = Value::Array(vec![Value::F32(1.0)]);
+    assert_eq!(
+        builder.append(&wrong_length).unwrap_err().to_string(),
+        "failed to convert field `gains` to Arrow: expected an array of 2 elements, got 1"
+    );
+
+    let batch = builder.finish().unwrap();
+    assert_eq!(batch.num_rows(), 1);
+    assert_eq!(batch.column(5).as_list::<i32>().values().len(), 1);
+}
+
+#[test]
+fn messages_containing_themselves_have_no_schema() {
+    let dynamic_message = DynamicMsg::<RandomState>::new("package/msg/Tree", "Tree[] children")
+        .expect("The message definition was invalid");
+
+    match dynamic_message.arrow_schema().unwrap_err() {
+        Error::CyclicDependency { cycle } => {
+            let cycle: Vec<_> = cycle.iter().map(ToString::to_string).collect();
+            assert_eq!(cycle, ["package/Tree", "package/Tree"]);
+        }
+        err => panic!("Expected a cyclic dependency, got {err:?}"),
+    }
+}
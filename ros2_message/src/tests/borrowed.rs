@@ -0,0 +1,168 @@
+use crate::dynamic::{DecodeOptions, DynamicMsg, PrimitiveArrayRef, ValueRef};
+use crate::{Time, Value};
+use std::borrow::Cow;
+use std::hash::RandomState;
+
+const BYTES: [u8; 48] = [
+    0x00, 0x01, 0, 0, // CDR_LE
+    10, 0, 0, 0, 20, 0, 0, 0, // stamp
+    4, 0, 0, 0, b'm', b'a', b'p', 0, // frame_id
+    2, 0, 0, 0, 0, 0, 0, 63, 0, 0, 192, 63, // ranges
+    2, 0, 0, 0, 2, 0, 0, 0, b'a', 0, 0, 0, 0, 0, 0, 0, // labels
+];
+
+#[test]
+fn borrowed_decoding_matches_owned_decoding() {
+    let msg_definition = r#"
+std_msgs/Header header
+float32[] ranges
+string[] labels
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("sensor_msgs/msg/Scan", msg_definition)
+            .expect("The message definition was invalid")
+            .with_options(DecodeOptions {
+                primitive_arrays: true,
+                ..Default::default()
+            });
+
+    let message = dynamic_message
+        .decode_borrowed(&BYTES)
+        .expect("The supplied bytes do not match the message definition");
+    let owned = dynamic_message.decode(&BYTES[..]).unwrap();
+
+    assert_eq!(ValueRef::Message(message).to_value(), Value::Message(owned));
+}
+
+#[test]
+fn borrowed_decoding_borrows_strings_and_bytes() {
+    let msg_definition = r#"
+std_msgs/Header header
+float32[] ranges
+string[] labels
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("sensor_msgs/msg/Scan", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message.decode_borrowed(&BYTES).unwrap();
+
+    let header = message["header"].as_map().unwrap();
+    let ValueRef::String(Cow::Borrowed(frame_id)) = &header["frame_id"] else {
+        panic!("The frame id should be borrowed");
+    };
+    assert_eq!(*frame_id, "map");
+    assert_eq!(frame_id.as_ptr(), BYTES[16..].as_ptr());
+
+    let ValueRef::PrimitiveArray(PrimitiveArrayRef::F32(ranges)) = &message["ranges"] else {
+        panic!("The ranges should be a primitive array");
+    };
+    assert_eq!(ranges.as_ref(), &[0.5, 1.5]);
+    let labels = message["labels"].as_slice().unwrap();
+    assert_eq!(labels[0].as_str(), Some("a"));
+    assert_eq!(labels[1].as_str(), Some(""));
+
+    let dynamic_message = dynamic_message.with_options(DecodeOptions {
+        map_builtin_time: true,
+        ..Default::default()
+    });
+    let message = dynamic_message.decode_borrowed(&BYTES).unwrap();
+    assert_eq!(
+        message["header"].as_map().unwrap()["stamp"],
+        ValueRef::Time(Time { sec: 10, nsec: 20 })
+    );
+}
+
+#[test]
+fn borrowed_decoding_converts_big_endian_arrays() {
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Samples", "uint8[] data\nint32[2] counts")
+            .expect("The message definition was invalid");
+    let bytes = [
+        0x00u8, 0x00, 0, 0, 0, 0, 0, 3, 1, 2, 3, 0, 255, 255, 255, 254, 0, 0, 0, 5,
+    ];
+
+    let message = dynamic_message.decode_borrowed(&bytes).unwrap();
+    assert_eq!(message["data"].as_bytes(), Some(&bytes[8..11]));
+    assert_eq!(
+        message["counts"],
+        ValueRef::PrimitiveArray(PrimitiveArrayRef::I32(Cow::Owned(vec![-2, 5])))
+    );
+}
+
+#[test]
+fn borrowed_decoding_of_mutable_messages() {
+    let dynamic_message: DynamicMsg<RandomState> = DynamicMsg::new(
+        "example_msgs/Custom",
+        "uint8 a\nuint8 CONST=3\nuint32 b\nstring c",
+    )
+    .expect("failed to build DynamicMsg");
+    let bytes = [
+        0u8, 0x0b, 0, 0, // PL_CDR2_LE
+        35, 0, 0, 0, // DHEADER
+        1, 0, 0, 0x20, 0x44, 0x33, 0x22, 0x11, // b with length code 2
+        0, 0, 0, 0, 9, 0, 0, 0, // a with length code 0 + padding
+        7, 0, 0, 0x20, 1, 2, 3, 4, // unknown member 7
+        2, 0, 0, 0x50, 3, 0, 0, 0, b'h', b'i', 0, // c with length code 5
+    ];
+
+    let message = dynamic_message.decode_borrowed(&bytes).unwrap();
+    assert_eq!(message["a"], ValueRef::U8(9));
+    assert_eq!(message["CONST"], ValueRef::U8(3));
+    assert_eq!(message["b"], ValueRef::U32(0x11223344));
+    assert_eq!(message["c"].as_str(), Some("hi"));
+}
+
+#[test]
+fn borrowed_decoding_rejects_truncated_and_trailing_data() {
+    let msg_definition = r#"
+std_msgs/Header header
+float32[] ranges
+string[] labels
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("sensor_msgs/msg/Scan", msg_definition)
+            .expect("The message definition was invalid");
+
+    assert!(dynamic_message.decode_borrowed(&BYTES[..2]).is_err());
+    assert!(dynamic_message.decode_borrowed(&BYTES[..30]).is_err());
+
+    let mut bytes = BYTES.to_vec();
+    bytes.extend([1, 2, 3, 4]);
+    assert!(dynamic_message.decode_borrowed(&bytes).is_err());
+}
@@ -0,0 +1,340 @@
+use crate::dynamic::{DynamicMsg, Encapsulation};
+use crate::{from_value, to_value, Error, Time, Value};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::RandomState;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Cloud<'a> {
+    frame_id: &'a str,
+    stamp: Time,
+    points: Vec<Point>,
+    data: &'a [u8],
+}
+
+fn cloud() -> Cloud<'static> {
+    Cloud {
+        frame_id: "map",
+        stamp: Time { sec: 12, nsec: 34 },
+        points: vec![
+            Point {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+            Point {
+                x: -1.0,
+                y: 0.5,
+                z: 0.0,
+            },
+        ],
+        data: &[1, 2, 3],
+    }
+}
+
+#[test]
+fn serde_round_trips_through_all_encapsulations() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id
+time stamp
+geometry_msgs/Point[] points
+uint8[] data
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Cloud", msg_definition)
+            .expect("The message definition was invalid");
+
+    for encapsulation in [
+        Encapsulation::CdrLe,
+        Encapsulation::CdrBe,
+        Encapsulation::Cdr2Le,
+        Encapsulation::DelimitedCdr2Be,
+        Encapsulation::PlCdr2Le,
+    ] {
+        let bytes = dynamic_message
+            .serialize_with_encapsulation(&cloud(), encapsulation)
+            .unwrap();
+
+        let message = dynamic_message.decode(&bytes[..]).unwrap();
+        assert_eq!(
+            dynamic_message
+                .encode_with_encapsulation(&message, encapsulation)
+                .unwrap(),
+            bytes,
+            "{encapsulation}"
+        );
+
+        let decoded: Cloud = dynamic_message.deserialize(&bytes).unwrap();
+        assert_eq!(decoded, cloud(), "{encapsulation}");
+    }
+}
+
+#[test]
+fn deserializing_skips_fields_missing_from_the_type() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id
+time stamp
+geometry_msgs/Point[] points
+uint8[] data
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Cloud", msg_definition)
+            .expect("The message definition was invalid");
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Stamped {
+        stamp: Time,
+    }
+
+    for encapsulation in [Encapsulation::CdrLe, Encapsulation::PlCdr2Le] {
+        let bytes = dynamic_message
+            .serialize_with_encapsulation(&cloud(), encapsulation)
+            .unwrap();
+
+        let decoded: Stamped = dynamic_message.deserialize(&bytes).unwrap();
+        assert_eq!(decoded.stamp, Time { sec: 12, nsec: 34 });
+
+        // Maps receive every field except constants
+        let fields: HashMap<String, serde_json::Value> =
+            dynamic_message.deserialize(&bytes).unwrap();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields["points"][1]["y"], 0.5);
+    }
+}
+
+#[test]
+fn deserializing_reports_the_path_of_mismatched_fields() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id
+time stamp
+geometry_msgs/Point[] points
+uint8[] data
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Cloud", msg_definition)
+            .expect("The message definition was invalid");
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct IntegerPoint {
+        x: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct IntegerCloud {
+        points: Vec<IntegerPoint>,
+    }
+
+    let bytes = dynamic_message.serialize(&cloud()).unwrap();
+
+    match dynamic_message
+        .deserialize::<IntegerCloud>(&bytes)
+        .unwrap_err()
+    {
+        Error::DecodingError {
+            path,
+            datatype,
+            err,
+            ..
+        } => {
+            assert_eq!(path, "points[0].x");
+            assert_eq!(datatype, "float64");
+            assert_eq!(
+                err.to_string(),
+                "invalid type: floating point `1.0`, expected i32"
+            );
+        }
+        err => panic!("Expected a decoding error, got {err:?}"),
+    }
+}
+
+#[test]
+fn serializing_checks_values_against_the_definition() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id
+time stamp
+geometry_msgs/Point[] points
+uint8[] data
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Cloud", msg_definition)
+            .expect("The message definition was invalid");
+    #[derive(Serialize)]
+    struct Labeled {
+        frame_id: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Extra<'a> {
+        #[serde(flatten)]
+        cloud: Cloud<'a>,
+        extra: bool,
+    }
+
+    let encoding_error = |err| match err {
+        Error::EncodingError { msg, field, reason } => (msg.to_string(), field, reason),
+        err => panic!("Expected an encoding error, got {err:?}"),
+    };
+
+    let err = dynamic_message
+        .serialize(&Labeled { frame_id: 1 })
+        .unwrap_err();
+    assert_eq!(
+        encoding_error(err),
+        (
+            "package/Cloud".to_owned(),
+            "frame_id".to_owned(),
+            "expected a value of type `string`".to_owned()
+        )
+    );
+
+    let err = dynamic_message
+        .serialize(&Extra {
+            cloud: cloud(),
+            extra: true,
+        })
+        .unwrap_err();
+    assert_eq!(encoding_error(err).1, "extra");
+
+    let mut cloud = cloud();
+    cloud.stamp.sec = 0;
+    let mut fields = serde_json::to_value(&cloud).unwrap();
+    fields["points"][1]["z"] = "up".into();
+    let err = dynamic_message.serialize(&fields).unwrap_err();
+    assert_eq!(
+        encoding_error(err),
+        (
+            "geometry_msgs/Point".to_owned(),
+            "z".to_owned(),
+            "expected a value of type `float64`".to_owned()
+        )
+    );
+
+    fields.as_object_mut().unwrap().remove("points");
+    let err = dynamic_message.serialize(&fields).unwrap_err();
+    assert_eq!(encoding_error(err).1, "points");
+}
+
+#[test]
+fn serializing_converts_integers_that_fit() {
+    let dynamic_message =
+        DynamicMsg::<RandomState>::new("package/msg/Numbers", "uint16 small\nfloat32 real")
+            .unwrap();
+
+    let bytes = dynamic_message
+        .serialize(&serde_json::json!({ "small": 258, "real": 2 }))
+        .unwrap();
+    assert_eq!(bytes, [0x00, 0x01, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0x40]);
+
+    let err = dynamic_message
+        .serialize(&serde_json::json!({ "small": -1, "real": 2 }))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to encode field `small` of message `package/Numbers`: \
+         -1 is out of range for a value of type `uint16`"
+    );
+}
+
+#[test]
+fn decoded_values_convert_to_structs_and_back() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id
+time stamp
+geometry_msgs/Point[] points
+uint8[] data
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Cloud", msg_definition)
+            .expect("The message definition was invalid");
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct OwnedCloud {
+        frame_id: String,
+        stamp: Time,
+        points: Vec<Point>,
+        data: Vec<u8>,
+    }
+
+    let bytes = dynamic_message.serialize(&cloud()).unwrap();
+    let message = dynamic_message.decode(&bytes[..]).unwrap();
+
+    let owned: OwnedCloud = from_value(crate::Value::Message(message)).unwrap();
+    assert_eq!(owned.points, cloud().points);
+    assert_eq!(owned.data, [1, 2, 3]);
+
+    let Ok(crate::Value::Message(message)) = to_value(&owned) else {
+        panic!("Expected a message");
+    };
+    assert_eq!(dynamic_message.encode(&message).unwrap(), bytes);
+}
+
+#[test]
+fn byte_arrays_serialize_as_bytes() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Blob<'a> {
+        #[serde(serialize_with = "serialize_bytes")]
+        payload: &'a [u8],
+    }
+
+    fn serialize_bytes<S: serde::Serializer>(v: &&[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(v)
+    }
+
+    let dynamic_message =
+        DynamicMsg::<RandomState>::new("package/msg/Blob", "byte[] payload").unwrap();
+    let blob = Blob {
+        payload: &[0x01, 0xff],
+    };
+
+    let bytes = dynamic_message.serialize(&blob).unwrap();
+    assert_eq!(bytes, [0x00, 0x01, 0, 0, 2, 0, 0, 0, 0x01, 0xff]);
+    let message = dynamic_message.decode(&bytes[..]).unwrap();
+    assert_eq!(
+        message["payload"],
+        Value::Array(vec![Value::I8(1), Value::I8(-1)])
+    );
+
+    let decoded: Blob = dynamic_message.deserialize(&bytes).unwrap();
+    assert_eq!(decoded, blob);
+}
@@ -1,5 +1,5 @@
-use crate::dynamic::DynamicMsg;
-use crate::Value;
+use crate::dynamic::{DecodeOptions, DynamicMsg, Encapsulation, Endianness};
+use crate::{Duration, Error, Time, Value};
 use std::hash::RandomState;
 use std::io::Cursor;
 
@@ -105,3 +105,550 @@ fn decode_empty_message() {
 
     assert!(decoded.is_empty(), "Empty message should yield no fields");
 }
+
+#[test]
+fn decoding_big_endian_message() {
+    let msg_definition = r#"
+builtin_interfaces/Time stamp
+float32 value
+string label
+float64[] samples
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+            "#;
+
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/SmallMsg", msg_definition)
+            .expect("The message definition was invalid");
+    let (message, encapsulation) = dynamic_message
+        .decode_with_encapsulation(
+            &[
+                0x00u8, 0x00, 0, 0, 102, 136, 47, 157, 0, 0, 0, 42, 64, 73, 15, 219, 0, 0, 0, 3,
+                b'a', b'b', 0, 0, 0, 0, 0, 1, 63, 240, 0, 0, 0, 0, 0, 0,
+            ][..],
+        )
+        .expect("The supplied bytes do not match the message definition");
+
+    assert_eq!(encapsulation, Encapsulation::CdrBe);
+    assert_eq!(encapsulation.endianness(), Endianness::Big);
+    assert_eq!(message["value"], Value::F32(core::f32::consts::PI));
+    assert_eq!(message["label"], Value::String("ab".into()));
+    assert_eq!(message["samples"], Value::Array(vec![Value::F64(1.0)]));
+    let stamp = message["stamp"].as_map().unwrap();
+    assert_eq!(stamp["sec"], Value::I32(1720201117));
+    assert_eq!(stamp["nanosec"], Value::U32(42));
+}
+
+#[test]
+fn decoding_rejects_unknown_encapsulation() {
+    let msg: DynamicMsg<RandomState> =
+        DynamicMsg::new("std_msgs/msg/Empty", "").expect("failed to build DynamicMsg");
+
+    assert!(msg.decode(&[0x42u8, 0x42, 0, 0][..]).is_err());
+}
+
+#[test]
+fn decoding_xcdr2_caps_alignment_and_reads_collection_headers() {
+    let msg: DynamicMsg<RandomState> =
+        DynamicMsg::new("example_msgs/Custom", "uint8 a\nfloat64 b\nstring[] names")
+            .expect("failed to build DynamicMsg");
+
+    let (message, encapsulation) = msg
+        .decode_with_encapsulation(
+            &[
+                0u8, 0x07, 0, 0, // CDR2_LE
+                1, 0, 0, 0, // a + padding to 4 bytes only
+                0, 0, 0, 0, 0, 0, 240, 63, // b
+                10, 0, 0, 0, // DHEADER of names
+                1, 0, 0, 0, 2, 0, 0, 0, b'x', 0, // names
+            ][..],
+        )
+        .expect("XCDR2 message should decode");
+
+    assert_eq!(encapsulation, Encapsulation::Cdr2Le);
+    assert_eq!(message["a"], Value::U8(1));
+    assert_eq!(message["b"], Value::F64(1.0));
+    assert_eq!(message["names"], Value::Array(vec!["x".to_owned().into()]));
+}
+
+#[test]
+fn decoding_delimited_xcdr2_skips_unknown_appended_members() {
+    let msg: DynamicMsg<RandomState> = DynamicMsg::new(
+        "example_msgs/Outer",
+        "uint16 a\nInner inner\n====\nMSG: example_msgs/Inner\nuint32 x",
+    )
+    .expect("failed to build DynamicMsg");
+
+    let message = msg
+        .decode(
+            &[
+                0u8, 0x09, 0, 0, // D_CDR2_LE
+                16, 0, 0, 0, // DHEADER of Outer
+                5, 0, 0, 0, // a + padding
+                8, 0, 0, 0, // DHEADER of Inner
+                7, 0, 0, 0, // x
+                9, 9, 9, 9, // member unknown to this definition
+            ][..],
+        )
+        .expect("appendable message should decode");
+
+    assert_eq!(message["a"], Value::U16(5));
+    assert_eq!(message["inner"].as_map().unwrap()["x"], Value::U32(7));
+}
+
+#[test]
+fn decoding_parameterized_xcdr2_reorders_members() {
+    let msg: DynamicMsg<RandomState> = DynamicMsg::new(
+        "example_msgs/Custom",
+        "uint8 a\nuint8 CONST=3\nuint32 b\nstring c",
+    )
+    .expect("failed to build DynamicMsg");
+
+    let message = msg
+        .decode(
+            &[
+                0u8, 0x0b, 0, 0, // PL_CDR2_LE
+                35, 0, 0, 0, // DHEADER
+                1, 0, 0, 0x20, 0x44, 0x33, 0x22, 0x11, // b with length code 2
+                0, 0, 0, 0, 9, 0, 0, 0, // a with length code 0 + padding
+                7, 0, 0, 0x20, 1, 2, 3, 4, // unknown member 7
+                2, 0, 0, 0x50, 3, 0, 0, 0, b'h', b'i', 0, // c with length code 5
+            ][..],
+        )
+        .expect("mutable message should decode");
+
+    assert_eq!(message["a"], Value::U8(9));
+    assert_eq!(message["CONST"], Value::U8(3));
+    assert_eq!(message["b"], Value::U32(0x11223344));
+    assert_eq!(message["c"], Value::String("hi".into()));
+}
+
+#[test]
+fn decoding_members_longer_than_announced_fails() {
+    let msg: DynamicMsg<RandomState> = DynamicMsg::new("example_msgs/Custom", "uint32 b\nuint8 a")
+        .expect("failed to build DynamicMsg");
+    let data = [
+        0u8, 0x0b, 0, 0, // PL_CDR2_LE
+        16, 0, 0, 0, // DHEADER
+        0, 0, 0, 0, 0x44, 0x33, 0x22, 0x11, // b with length code 0
+        1, 0, 0, 0, 9, 0, 0, 0, // a with length code 0 + padding
+    ];
+
+    let errors = [
+        msg.decode(&data[..]).unwrap_err(),
+        msg.decode_borrowed(&data).unwrap_err(),
+    ];
+    for error in errors {
+        let Error::DecodingError { path, err, .. } = error else {
+            panic!("Expected a decoding error, got {error:?}");
+        };
+        assert_eq!(path, "b");
+        assert!(err.to_string().contains("past the end"), "{err}");
+    }
+}
+
+#[test]
+fn decoding_duration() {
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Timeout", "uint8 id\nduration timeout")
+            .expect("The message definition was invalid");
+    let message = dynamic_message
+        .decode(
+            &[
+                0x00u8, 0x01, 0, 0, 7, 0, 0, 0, 0xfb, 0xff, 0xff, 0xff, 0xf4, 0x01, 0, 0,
+            ][..],
+        )
+        .expect("The supplied bytes do not match the message definition");
+
+    assert_eq!(message["id"], Value::U8(7));
+    assert_eq!(
+        message["timeout"],
+        Value::Duration(Duration { sec: -5, nsec: 500 })
+    );
+}
+
+#[test]
+fn decoding_maps_builtin_time_messages_when_enabled() {
+    let msg_definition = r#"
+builtin_interfaces/Time stamp
+builtin_interfaces/Duration[] timeouts
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: builtin_interfaces/Duration
+
+int32 sec
+uint32 nanosec
+            "#;
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0xff,
+        0xff, 0xff, 0xff, 3, 0, 0, 0,
+    ];
+
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Timeouts", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+    let stamp = message["stamp"].as_map().unwrap();
+    assert_eq!(stamp["sec"], Value::I32(10));
+    assert_eq!(stamp["nanosec"], Value::U32(20));
+
+    let dynamic_message = dynamic_message.with_options(DecodeOptions {
+        map_builtin_time: true,
+        ..Default::default()
+    });
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+    assert_eq!(message["stamp"], Value::Time(Time { sec: 10, nsec: 20 }));
+    assert_eq!(
+        message["timeouts"],
+        Value::Array(vec![
+            Value::Duration(Duration { sec: 1, nsec: 2 }),
+            Value::Duration(Duration { sec: -1, nsec: 3 }),
+        ])
+    );
+    assert_eq!(dynamic_message.encode(&message).unwrap(), bytes);
+}
+
+#[test]
+fn decoding_keeps_builtin_time_messages_out_of_range() {
+    let msg_definition = r#"
+builtin_interfaces/Time stamp
+builtin_interfaces/Duration[] timeouts
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: builtin_interfaces/Duration
+
+int32 sec
+uint32 nanosec
+            "#;
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 0xff, 0xff, 0xff, 0xff, 20, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0,
+        0x80,
+    ];
+
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Timeouts", msg_definition)
+            .expect("The message definition was invalid")
+            .with_options(DecodeOptions {
+                map_builtin_time: true,
+                ..Default::default()
+            });
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+    let stamp = message["stamp"].as_map().unwrap();
+    assert_eq!(stamp["sec"], Value::I32(-1));
+    assert_eq!(stamp["nanosec"], Value::U32(20));
+    let timeout = message["timeouts"].as_slice().unwrap()[0].as_map().unwrap();
+    assert_eq!(timeout["nanosec"], Value::U32(0x8000_0000));
+    assert_eq!(dynamic_message.encode(&message).unwrap(), bytes);
+
+    let borrowed = dynamic_message.decode_borrowed(&bytes).unwrap();
+    assert_eq!(borrowed["stamp"].to_value(), Value::Message(stamp.clone()));
+
+    // Builtin values out of range of the messages are not wrapped around either
+    let mut message = message;
+    message["stamp"] = Value::Time(Time {
+        sec: u32::MAX,
+        nsec: 0,
+    });
+    assert_eq!(
+        dynamic_message.encode(&message).unwrap_err().to_string(),
+        "failed to encode field `sec` of message `builtin_interfaces/Time`: expected a value of type `int32`"
+    );
+}
+
+#[test]
+fn decoding_bounded_and_wide_types() {
+    let msg_definition = "string<=3 name\nint32[<=2] values\nwstring text\nwchar letter";
+    #[rustfmt::skip]
+    let bytes = [
+        0x00u8, 0x01, 0, 0,
+        5, 0, 0, 0, b'a', b'b', b'c', b'd', 0, 0, 0, 0,
+        3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0,
+        2, 0, 0, 0, 0x68, 0, 0xe9, 0,
+        0x78, 0,
+    ];
+
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Bounded", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("Bounds are only validated on request");
+    assert_eq!(message["name"], Value::String("abcd".into()));
+    assert_eq!(
+        message["values"],
+        Value::Array(vec![Value::I32(1), Value::I32(2), Value::I32(3)])
+    );
+    assert_eq!(message["text"], Value::String("hé".into()));
+    assert_eq!(message["letter"], Value::U16(u16::from(b'x')));
+
+    let dynamic_message = dynamic_message.with_options(DecodeOptions {
+        validate_bounds: true,
+        ..Default::default()
+    });
+    let err = dynamic_message.decode(&bytes[..]).unwrap_err();
+    assert!(err.to_string().contains("exceeds its bound of 3"));
+}
+
+#[test]
+fn decoding_shared_nested_messages() {
+    let msg_definition = r#"
+package/Point start
+package/Segment[2] segments
+
+================================================================================
+MSG: package/Segment
+
+Point from
+Point to
+
+================================================================================
+MSG: package/Point
+
+int16 x
+int16 y
+            "#;
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 7, 0, 8, 0, 9, 0, 10, 0,
+    ];
+
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Path", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+
+    let point = |x, y| {
+        Value::Message(
+            [
+                ("x".to_owned(), Value::I16(x)),
+                ("y".to_owned(), Value::I16(y)),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    };
+    assert_eq!(message["start"], point(1, 2));
+    let segments = message["segments"].as_slice().unwrap();
+    assert_eq!(segments[0].as_map().unwrap()["from"], point(3, 4));
+    assert_eq!(segments[1].as_map().unwrap()["to"], point(9, 10));
+    assert_eq!(dynamic_message.encode(&message).unwrap(), bytes);
+}
+
+#[test]
+fn decoding_fails_for_missing_dependency() {
+    let dynamic_message: DynamicMsg<RandomState> = DynamicMsg::new(
+        "package/msg/Wrapper",
+        "int8 value\nother_package/Missing missing",
+    )
+    .expect("Missing dependencies are only reported when decoding");
+
+    let err = dynamic_message
+        .decode(&[0x00u8, 0x01, 0, 0, 1, 0, 0, 0][..])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::MessageDependencyMissing { package, name }
+            if package == "other_package" && name == "Missing"
+    ));
+}
+
+#[test]
+fn decoding_primitive_arrays_in_bulk() {
+    let msg_definition =
+        "uint8[] data\nbool[2] flags\nint16 offset\nfloat32[] points\nstring[] names";
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 3, 0, 0, 0, 1, 2, 3, 1, 0, 0, 7, 0, 2, 0, 0, 0, 0, 0, 0, 63, 0, 0, 192,
+        63, 1, 0, 0, 0, 2, 0, 0, 0, b'a', 0,
+    ];
+
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Bulk", msg_definition)
+            .expect("The message definition was invalid");
+    let per_element = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+
+    let dynamic_message = dynamic_message.with_options(DecodeOptions {
+        primitive_arrays: true,
+        ..Default::default()
+    });
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+
+    assert_eq!(message["data"].as_bytes(), Some(&[1u8, 2, 3][..]));
+    assert_eq!(message["flags"].as_bool_slice(), Some(&[true, false][..]));
+    assert_eq!(message["offset"], Value::I16(7));
+    assert_eq!(message["points"].as_f32_slice(), Some(&[0.5f32, 1.5][..]));
+    // Only numbers and bools are decoded to primitive arrays
+    assert_eq!(
+        message["names"],
+        Value::Array(vec![Value::String("a".into())])
+    );
+
+    for (name, value) in &message {
+        assert_eq!(
+            value.clone().try_into_vec(),
+            per_element[name].clone().try_into_vec()
+        );
+    }
+    // Primitive arrays have the same content as arrays of their elements
+    assert_eq!(message, per_element);
+    assert!(message["data"].as_slice().is_none());
+    assert_eq!(per_element["data"].as_slice().map(<[_]>::len), Some(3));
+    assert_eq!(dynamic_message.encode(&message).unwrap(), bytes);
+}
+
+#[test]
+fn decoding_big_endian_primitive_arrays() {
+    let dynamic_message: DynamicMsg<RandomState> = DynamicMsg::new(
+        "package/msg/Samples",
+        "uint8 id\nfloat64[] samples\nint32[2] counts",
+    )
+    .expect("The message definition was invalid")
+    .with_options(DecodeOptions {
+        primitive_arrays: true,
+        ..Default::default()
+    });
+    let bytes = [
+        0x00u8, 0x00, 0, 0, 9, 0, 0, 0, 0, 0, 0, 1, 63, 240, 0, 0, 0, 0, 0, 0, 255, 255, 255, 254,
+        0, 0, 0, 5,
+    ];
+
+    let message = dynamic_message
+        .decode(&bytes[..])
+        .expect("The supplied bytes do not match the message definition");
+    assert_eq!(message["samples"].as_f64_slice(), Some(&[1.0][..]));
+    assert_eq!(message["counts"].as_i32_slice(), Some(&[-2, 5][..]));
+    assert_eq!(
+        dynamic_message
+            .encode_with_encapsulation(&message, Encapsulation::CdrBe)
+            .unwrap(),
+        bytes
+    );
+
+    let err = dynamic_message.decode(&bytes[..bytes.len() - 2]);
+    assert!(err.is_err());
+}
+
+#[test]
+fn decoding_preserves_field_order() {
+    let msg: DynamicMsg<RandomState> = DynamicMsg::new(
+        "example_msgs/Custom",
+        "uint8 z\nuint8 CONST=3\nuint32 b\nstring a",
+    )
+    .expect("failed to build DynamicMsg");
+
+    let message = msg
+        .decode(
+            &[
+                0u8, 0x0b, 0, 0, // PL_CDR2_LE
+                35, 0, 0, 0, // DHEADER
+                1, 0, 0, 0x20, 0x44, 0x33, 0x22, 0x11, // b with length code 2
+                0, 0, 0, 0, 9, 0, 0, 0, // z with length code 0 + padding
+                7, 0, 0, 0x20, 1, 2, 3, 4, // unknown member 7
+                2, 0, 0, 0x50, 3, 0, 0, 0, b'h', b'i', 0, // a with length code 5
+            ][..],
+        )
+        .expect("mutable message should decode");
+
+    assert_eq!(
+        message.keys().collect::<Vec<_>>(),
+        vec!["z", "CONST", "b", "a"]
+    );
+    assert_eq!(
+        format!("{}", Value::Message(message)),
+        "\nz: 9\nCONST: 3\nb: 287454020\na: \"hi\""
+    );
+}
+
+#[test]
+fn decoding_errors_report_the_field_path() {
+    let msg_definition = r#"
+geometry_msgs/TransformStamped[] transforms
+
+================================================================================
+MSG: geometry_msgs/TransformStamped
+string child_frame_id
+geometry_msgs/Transform transform
+
+================================================================================
+MSG: geometry_msgs/Transform
+geometry_msgs/Quaternion rotation
+
+================================================================================
+MSG: geometry_msgs/Quaternion
+float64 x
+float64 w
+"#;
+    let bytes = [
+        0x00u8, 0x01, 0, 0, // CDR_LE
+        2, 0, 0, 0, // transforms length
+        2, 0, 0, 0, b'a', 0, 0, 0, 0, 0, 0, 0, // child_frame_id + padding
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 240, 63, // rotation
+        2, 0, 0, 0, b'b', 0, 0, 0, // child_frame_id + padding
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // rotation is truncated
+    ];
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("tf2_msgs/msg/TFMessage", msg_definition)
+            .expect("The message definition was invalid");
+
+    let err = dynamic_message.decode(&bytes[..]).unwrap_err();
+    let crate::Error::DecodingError {
+        path,
+        datatype,
+        offset,
+        encapsulation,
+        ..
+    } = &err
+    else {
+        panic!("Expected a decoding error, got {err:?}");
+    };
+    assert_eq!(path, "transforms[1].transform.rotation.w");
+    assert_eq!(datatype, "float64");
+    assert_eq!(*offset, 56);
+    assert_eq!(*encapsulation, Some(Encapsulation::CdrLe));
+    assert_eq!(
+        err.to_string(),
+        "failed to decode field `transforms[1].transform.rotation.w` of type `float64` at byte 56 of CDR_LE data: failed to fill whole buffer"
+    );
+
+    let borrowed = dynamic_message.decode_borrowed(&bytes[..]).unwrap_err();
+    assert!(matches!(
+        borrowed,
+        crate::Error::DecodingError { path, .. } if path == "transforms[1].transform.rotation.w"
+    ));
+
+    let err = dynamic_message.decode(&bytes[..2]).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::DecodingError { ref path, encapsulation: None, .. } if path.is_empty()
+    ));
+    assert_eq!(
+        err.to_string(),
+        "failed to decode message at byte 0: failed to fill whole buffer"
+    );
+}
@@ -0,0 +1,224 @@
+use crate::dynamic::{DynamicMsg, Encapsulation};
+use crate::{Error, MessageValue, Time, Value};
+use std::hash::RandomState;
+
+const CAMERA_INFO_SCHEMA: &str = r#"std_msgs/Header header
+uint32 height
+uint32 width
+string distortion_model
+float64[] d
+float64[9] k
+uint8 BINNING=1
+sensor_msgs/RegionOfInterest[] rois
+================================================================================
+MSG: std_msgs/Header
+builtin_interfaces/Time stamp
+string frame_id
+================================================================================
+MSG: builtin_interfaces/Time
+int32 sec
+uint32 nanosec
+================================================================================
+MSG: sensor_msgs/RegionOfInterest
+uint32 x_offset
+uint32 y_offset
+uint32 height
+uint32 width
+bool do_rectify
+"#;
+
+fn region_of_interest(x_offset: u32, do_rectify: bool) -> Value<RandomState> {
+    [
+        ("x_offset", Value::U32(x_offset)),
+        ("y_offset", Value::U32(2)),
+        ("height", Value::U32(3)),
+        ("width", Value::U32(4)),
+        ("do_rectify", Value::Bool(do_rectify)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn camera_info() -> MessageValue<RandomState> {
+    let stamp: Value<RandomState> = [("sec", Value::I32(1720201117)), ("nanosec", Value::U32(42))]
+        .into_iter()
+        .collect();
+    let header: Value<RandomState> = [
+        ("stamp", stamp),
+        ("frame_id", Value::String("camera".into())),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut message = MessageValue::default();
+    message.insert("header".into(), header);
+    message.insert("height".into(), Value::U32(480));
+    message.insert("width".into(), Value::U32(640));
+    message.insert("distortion_model".into(), Value::String("plumb_bob".into()));
+    message.insert("d".into(), vec![0.1f64, -0.2, 0.001].into());
+    message.insert("k".into(), [1.0f64; 9].into());
+    message.insert(
+        "rois".into(),
+        Value::Array(vec![
+            region_of_interest(1, true),
+            region_of_interest(5, false),
+        ]),
+    );
+    message
+}
+
+#[test]
+fn encoding_round_trips_through_the_decoder() {
+    let msg: DynamicMsg<RandomState> =
+        DynamicMsg::new("sensor_msgs/CameraInfo", CAMERA_INFO_SCHEMA).unwrap();
+
+    let bytes = msg.encode(&camera_info()).unwrap();
+    let decoded = msg.decode(&bytes[..]).unwrap();
+
+    let mut expected = camera_info();
+    expected.insert("BINNING".into(), Value::U8(1));
+    assert_eq!(decoded, expected);
+
+    let unmapped = msg.decode_unmapped(&bytes[..]).unwrap();
+    assert_eq!(msg.encode_unmapped(&unmapped).unwrap(), bytes);
+}
+
+#[test]
+fn encoding_writes_header_padding_and_string_terminators() {
+    let msg: DynamicMsg<RandomState> = DynamicMsg::new(
+        "example_msgs/Custom",
+        "uint8 flag\nstring label\nfloat64 value\nint16[] empty\nuint32 last",
+    )
+    .unwrap();
+
+    let mut message = MessageValue::default();
+    message.insert("flag".into(), Value::U8(7));
+    message.insert("label".into(), Value::String("hi".into()));
+    message.insert("value".into(), Value::F64(1.0));
+    message.insert("empty".into(), Value::Array(vec![]));
+    message.insert("last".into(), Value::U32(9));
+
+    assert_eq!(
+        msg.encode(&message).unwrap(),
+        [
+            0, 1, 0, 0, // encapsulation header
+            7, 0, 0, 0, // flag + padding
+            3, 0, 0, 0, b'h', b'i', 0, 0, 0, 0, 0, 0, // label + padding
+            0, 0, 0, 0, 0, 0, 240, 63, // value
+            0, 0, 0, 0, // empty sequence length
+            9, 0, 0, 0, // last
+        ]
+    );
+}
+
+#[test]
+fn encoding_rejects_values_not_matching_the_definition() {
+    let msg: DynamicMsg<RandomState> =
+        DynamicMsg::new("example_msgs/Custom", "int32 data\nfloat32[2] values").unwrap();
+
+    let mut message = MessageValue::default();
+    message.insert("data".into(), Value::U32(42));
+    message.insert("values".into(), vec![1.0f32, 2.0].into());
+    assert!(matches!(
+        msg.encode(&message),
+        Err(Error::EncodingError { field, .. }) if field == "data"
+    ));
+
+    message.insert("data".into(), Value::I32(42));
+    message.insert("values".into(), vec![1.0f32].into());
+    assert!(matches!(
+        msg.encode(&message),
+        Err(Error::EncodingError { field, .. }) if field == "values"
+    ));
+
+    message.shift_remove("values");
+    assert!(msg.encode(&message).is_err());
+
+    message.insert("values".into(), Value::Time(Time::new()));
+    assert!(msg.encode(&message).is_err());
+}
+
+#[test]
+fn encoding_preserves_the_decoded_encapsulation() {
+    let msg: DynamicMsg<RandomState> =
+        DynamicMsg::new("sensor_msgs/CameraInfo", CAMERA_INFO_SCHEMA).unwrap();
+
+    let little = msg.encode(&camera_info()).unwrap();
+    let big = msg
+        .encode_with_encapsulation(&camera_info(), Encapsulation::CdrBe)
+        .unwrap();
+    assert_eq!(&big[..4], &[0, 0, 0, 0]);
+    assert_ne!(little, big);
+
+    let (decoded, encapsulation) = msg.decode_with_encapsulation(&big[..]).unwrap();
+    assert_eq!(encapsulation, Encapsulation::CdrBe);
+    assert_eq!(
+        msg.encode_with_encapsulation(&decoded, encapsulation)
+            .unwrap(),
+        big
+    );
+    assert_eq!(msg.encode(&decoded).unwrap(), little);
+}
+
+#[test]
+fn encoding_round_trips_all_xcdr2_encapsulations() {
+    let msg: DynamicMsg<RandomState> =
+        DynamicMsg::new("sensor_msgs/CameraInfo", CAMERA_INFO_SCHEMA).unwrap();
+
+    let mut expected = camera_info();
+    expected.insert("BINNING".into(), Value::U8(1));
+
+    for encapsulation in [
+        Encapsulation::Cdr2Be,
+        Encapsulation::Cdr2Le,
+        Encapsulation::DelimitedCdr2Be,
+        Encapsulation::DelimitedCdr2Le,
+        Encapsulation::PlCdr2Be,
+        Encapsulation::PlCdr2Le,
+    ] {
+        let bytes = msg
+            .encode_with_encapsulation(&camera_info(), encapsulation)
+            .unwrap();
+        assert_eq!(
+            msg.decode_with_encapsulation(&bytes[..]).unwrap(),
+            (expected.clone(), encapsulation)
+        );
+    }
+}
+
+#[test]
+fn encoding_validates_bounds_and_round_trips_wide_strings() {
+    let dynamic_message: DynamicMsg<RandomState> = DynamicMsg::new(
+        "package/msg/Bounded",
+        "string<=3 name\nwstring<=2[<=2] texts\nwchar letter",
+    )
+    .unwrap();
+
+    let mut message = MessageValue::<RandomState>::default();
+    message.insert("name".into(), Value::String("abc".into()));
+    // The emoji takes up two UTF-16 code units, which still fits the bound
+    message.insert(
+        "texts".into(),
+        Value::Array(vec![Value::String("hé".into()), Value::String("😀".into())]),
+    );
+    message.insert("letter".into(), Value::U16(0x78));
+
+    let bytes = dynamic_message.encode(&message).unwrap();
+    assert_eq!(dynamic_message.decode(&bytes[..]).unwrap(), message);
+
+    message.insert(
+        "texts".into(),
+        Value::Array(vec![Value::String("abc".into())]),
+    );
+    assert!(matches!(
+        dynamic_message.encode(&message),
+        Err(Error::EncodingError { field, .. }) if field == "texts"
+    ));
+
+    message.insert("texts".into(), Value::Array(vec![]));
+    message.insert("name".into(), Value::String("abcd".into()));
+    assert!(matches!(
+        dynamic_message.encode(&message),
+        Err(Error::EncodingError { field, .. }) if field == "name"
+    ));
+}
@@ -276,3 +276,32 @@ fn deserialize_field_info_ensures_correct_const_value() {
     )
     .is_err());
 }
+
+#[test]
+fn field_info_with_array_default_round_trips_through_serde() {
+    let field =
+        FieldInfo::<RandomState>::with_default("float32", "abc", FieldCase::Array(2), "[1.5, -2]")
+            .unwrap();
+    let json = serde_json::to_value(&field).unwrap();
+    assert_eq!(
+        json,
+        serde_json::from_str::<serde_json::Value>(
+            r#"
+            {
+                "datatype": "float32",
+                "name": "abc",
+                "case": { "Array": 2 },
+                "default": "[1.5, -2]"
+            }
+            "#,
+        )
+        .unwrap(),
+    );
+
+    let field: FieldInfo<RandomState> = serde_json::from_str(&json.to_string()).unwrap();
+    assert_eq!(format!("{}", field), "float32[2] abc [1.5, -2]");
+    assert_eq!(
+        field.default_value(),
+        Some(&Value::Array(vec![Value::F32(1.5), Value::F32(-2.0)]))
+    );
+}
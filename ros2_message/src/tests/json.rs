@@ -0,0 +1,209 @@
+use crate::{
+    BytesFormat, Duration, JsonOptions, MessageValue, NonFiniteFloats, PrimitiveArray, Time,
+    ToJson, Value,
+};
+use std::hash::RandomState;
+
+fn message() -> MessageValue<RandomState> {
+    let header: Value<RandomState> = [
+        ("stamp", Value::Time(Time { sec: 10, nsec: 20 })),
+        ("frame_id", Value::String("base\t\"link\"\n".into())),
+    ]
+    .into_iter()
+    .collect();
+
+    [
+        ("header", header),
+        ("timeout", Value::Duration(Duration { sec: -1, nsec: 0 })),
+        ("count", Value::U64(u64::MAX)),
+        ("offset", Value::I8(-3)),
+        ("scale", Value::F64(1.0)),
+        ("ratio", Value::F32(0.1)),
+        ("valid", Value::Bool(true)),
+        (
+            "data",
+            Value::PrimitiveArray(PrimitiveArray::U8(vec![104, 105])),
+        ),
+        (
+            "ids",
+            Value::Array(vec![Value::U8(1), Value::U8(2), Value::U8(3)]),
+        ),
+        (
+            "samples",
+            Value::PrimitiveArray(PrimitiveArray::I16(vec![-1, 2])),
+        ),
+        ("empty", Value::Array(vec![])),
+    ]
+    .into_iter()
+    .collect::<Value<RandomState>>()
+    .try_into_map()
+    .unwrap()
+}
+
+#[test]
+fn json_uses_plain_values_in_field_order() {
+    let json = message().to_json();
+
+    assert_eq!(
+        json,
+        r#"{"header":{"stamp":{"sec":10,"nanosec":20},"frame_id":"base\t\"link\"\n"},"timeout":{"sec":-1,"nanosec":0},"count":18446744073709551615,"offset":-3,"scale":1.0,"ratio":0.1,"valid":true,"data":[104,105],"ids":[1,2,3],"samples":[-1,2],"empty":[]}"#
+    );
+
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed["header"]["frame_id"], "base\t\"link\"\n");
+    assert_eq!(Value::Message(message()).to_json(), json);
+}
+
+#[test]
+fn json_writes_bytes_as_base64() {
+    let options = JsonOptions {
+        bytes: BytesFormat::Base64,
+        ..Default::default()
+    };
+    let json = message().to_json_with_options(&options);
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(parsed["data"], "aGk=");
+    // Without the message definition only primitive arrays are known to be bytes
+    assert_eq!(parsed["ids"], serde_json::json!([1, 2, 3]));
+    assert_eq!(parsed["empty"], serde_json::json!([]));
+
+    for (bytes, encoded) in [
+        (&b""[..], ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foobar", "Zm9vYmFy"),
+        (&[0xff, 0xfe, 0x00, 0x3e], "//4APg=="),
+    ] {
+        let value = Value::<RandomState>::PrimitiveArray(PrimitiveArray::U8(bytes.to_vec()));
+        assert_eq!(
+            value.to_json_with_options(&options),
+            format!("\"{encoded}\"")
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "decode")]
+fn json_writes_byte_fields_of_the_message_definition_as_bytes() {
+    use crate::dynamic::{DecodeOptions, DynamicMsg};
+
+    let dynamic_message = DynamicMsg::<RandomState>::new(
+        "package/msg/Packet",
+        r#"
+uint8[] data
+byte[] raw
+char[2] letters
+int8[] signed
+uint8[] empty
+Chunk[] chunks
+
+================================================================================
+MSG: package/Chunk
+uint8[] data
+"#,
+    )
+    .unwrap();
+    let chunk: Value<RandomState> = [("data", Value::Array(vec![Value::U8(0xff)]))]
+        .into_iter()
+        .collect();
+    let message = [
+        ("data", Value::Array(vec![Value::U8(104), Value::U8(105)])),
+        ("raw", Value::Array(vec![Value::I8(-1), Value::I8(1)])),
+        (
+            "letters",
+            Value::Array(vec![Value::U8(b'o'), Value::U8(b'k')]),
+        ),
+        ("signed", Value::Array(vec![Value::I8(-1)])),
+        ("empty", Value::Array(vec![])),
+        ("chunks", Value::Array(vec![chunk])),
+    ]
+    .into_iter()
+    .collect::<Value<RandomState>>()
+    .try_into_map()
+    .unwrap();
+    let base64 = JsonOptions {
+        bytes: BytesFormat::Base64,
+        ..Default::default()
+    };
+
+    let expected = r#"{"data":"aGk=","raw":"/wE=","letters":"b2s=","signed":[-1],"empty":"","chunks":[{"data":"/w=="}]}"#;
+    assert_eq!(
+        dynamic_message.to_json_with_options(&message, &base64),
+        expected
+    );
+    assert_eq!(
+        dynamic_message.to_json(&message),
+        r#"{"data":[104,105],"raw":[255,1],"letters":[111,107],"signed":[-1],"empty":[],"chunks":[{"data":[255]}]}"#
+    );
+
+    // Bytes decoded in bulk are written the same way
+    let bulk = dynamic_message.clone().with_options(DecodeOptions {
+        primitive_arrays: true,
+        ..Default::default()
+    });
+    let bytes = dynamic_message.encode(&message).unwrap();
+    let decoded = bulk.decode(&bytes[..]).unwrap();
+    assert_eq!(bulk.to_json_with_options(&decoded, &base64), expected);
+}
+
+#[test]
+fn json_writes_non_finite_floats_as_configured() {
+    let value = Value::<RandomState>::PrimitiveArray(PrimitiveArray::F64(vec![
+        f64::NAN,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        -0.5,
+    ]));
+    let with = |non_finite| {
+        value.to_json_with_options(&JsonOptions {
+            non_finite,
+            ..Default::default()
+        })
+    };
+
+    assert_eq!(with(NonFiniteFloats::Null), "[null,null,null,-0.5]");
+    assert_eq!(
+        with(NonFiniteFloats::String),
+        r#"["NaN","Infinity","-Infinity",-0.5]"#
+    );
+    assert_eq!(
+        with(NonFiniteFloats::Literal),
+        "[NaN,Infinity,-Infinity,-0.5]"
+    );
+}
+
+#[test]
+fn json_can_be_pretty_printed() {
+    let value: Value<RandomState> = [
+        ("stamp", Value::Time(Time { sec: 1, nsec: 2 })),
+        (
+            "points",
+            Value::Array(vec![Value::F32(1.5), Value::F32(-2.0)]),
+        ),
+        ("empty", Value::Message(MessageValue::default())),
+    ]
+    .into_iter()
+    .collect();
+
+    let json = value.to_json_with_options(&JsonOptions {
+        pretty: true,
+        ..Default::default()
+    });
+
+    assert_eq!(
+        json,
+        r#"{
+  "stamp": {
+    "sec": 1,
+    "nanosec": 2
+  },
+  "points": [
+    1.5,
+    -2.0
+  ],
+  "empty": {}
+}"#
+    );
+}
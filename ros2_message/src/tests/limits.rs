@@ -0,0 +1,289 @@
+use crate::dynamic::{DecodeOptions, DynamicMsg, Endianness, Framing};
+use crate::{Error, Value};
+use std::hash::RandomState;
+
+fn decoding_error(err: Error) -> (String, String) {
+    match err {
+        Error::DecodingError { path, err, .. } => (path, err.to_string()),
+        err => panic!("Expected a decoding error, got {err:?}"),
+    }
+}
+
+// A single value followed by a sequence that claims almost 4 billion elements
+const CORRUPTED_SEQUENCE: [u8; 16] = [
+    0x00, 0x01, 0, 0, // CDR_LE
+    1, 0, 0, 0, // value + padding
+    0xf0, 0xff, 0xff, 0xff, // samples length
+    1, 2, 3, 4, // samples
+];
+
+#[test]
+fn decoding_corrupted_sequence_lengths_fails_without_allocating() {
+    for definition in [
+        "uint8 value\nfloat64[] samples",
+        "uint8 value\nstring[] samples",
+        "uint8 value\nwstring[] samples",
+        "uint8 value\nbool[] samples",
+    ] {
+        let dynamic_message = DynamicMsg::<RandomState>::new("package/msg/Tree", definition)
+            .expect("The message definition was invalid");
+        let bulk = dynamic_message.clone().with_options(DecodeOptions {
+            primitive_arrays: true,
+            ..Default::default()
+        });
+
+        for err in [
+            dynamic_message.decode(&CORRUPTED_SEQUENCE[..]).unwrap_err(),
+            bulk.decode(&CORRUPTED_SEQUENCE[..]).unwrap_err(),
+            bulk.decode_borrowed(&CORRUPTED_SEQUENCE).unwrap_err(),
+        ] {
+            // Values are decoded one by one unless they are read in bulk
+            let path = decoding_error(err).0;
+            assert!(path.starts_with("samples"), "{definition}: {path}");
+        }
+    }
+}
+
+#[test]
+fn decoding_corrupted_lengths_of_empty_elements_fails_without_allocating() {
+    let dynamic_message = DynamicMsg::<RandomState>::new(
+        "package/msg/Flags",
+        "Flag[] items\nuint16 value\n\n===\nMSG: package/Flag\nuint8 ON=1",
+    )
+    .expect("The message definition was invalid");
+    let limited = dynamic_message.clone().with_options(DecodeOptions {
+        max_bytes: Some(16),
+        ..Default::default()
+    });
+
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 0xf0, 0xff, 0xff, 0xff, 1, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let projection = dynamic_message.projection(["items"]).unwrap();
+    for err in [
+        limited.decode(&bytes[..]).unwrap_err(),
+        limited.decode_borrowed(&bytes).unwrap_err(),
+        dynamic_message.decode_borrowed(&bytes).unwrap_err(),
+        limited
+            .decode_projected(&bytes[..], &projection)
+            .unwrap_err(),
+        limited
+            .deserialize::<serde_json::Value>(&bytes)
+            .unwrap_err(),
+    ] {
+        assert_eq!(
+            decoding_error(err),
+            (
+                "items".to_owned(),
+                "The array of 4294967280 elements without data is longer than the 8 bytes left"
+                    .to_owned()
+            )
+        );
+    }
+
+    // Without a limit the number of bytes left is unknown, unless the payload has a known size
+    let mut concatenated = dynamic_message.decode_stream(&bytes[..], Framing::Concatenated);
+    for err in [
+        dynamic_message.decode(&bytes[..]).unwrap_err(),
+        dynamic_message.decode_unmapped(&bytes[..]).unwrap_err(),
+        concatenated.next().unwrap().unwrap_err(),
+    ] {
+        assert_eq!(
+            decoding_error(err),
+            (
+                "items".to_owned(),
+                "The array of 4294967280 elements without data exceeds the limit of 65536 elements"
+                    .to_owned()
+            )
+        );
+    }
+    let mut stream = [16u8, 0, 0, 0].to_vec();
+    stream.extend_from_slice(&bytes);
+    let mut length_prefixed =
+        dynamic_message.decode_stream(&stream[..], Framing::LengthPrefixed(Endianness::Little));
+    assert_eq!(
+        decoding_error(length_prefixed.next().unwrap().unwrap_err()),
+        (
+            "items".to_owned(),
+            "The array of 4294967280 elements without data is longer than the 8 bytes left"
+                .to_owned()
+        )
+    );
+
+    // Elements without data take no space as long as their number is plausible
+    let bytes = [0x00u8, 0x01, 0, 0, 2, 0, 0, 0, 7, 0];
+    let message = limited.decode_borrowed(&bytes).unwrap();
+    assert_eq!(message["value"], crate::dynamic::ValueRef::U16(7));
+    let flag: Value<RandomState> = [("ON", Value::U8(1))].into_iter().collect();
+    for message in [
+        limited.decode(&bytes[..]).unwrap(),
+        dynamic_message.decode(&bytes[..]).unwrap(),
+    ] {
+        assert_eq!(
+            message["items"],
+            Value::Array(vec![flag.clone(), flag.clone()])
+        );
+    }
+}
+
+#[test]
+fn decoding_corrupted_string_lengths_fails_without_allocating() {
+    let dynamic_message =
+        DynamicMsg::<RandomState>::new("package/msg/Tree", "string label\nwstring wide")
+            .expect("The message definition was invalid");
+
+    let bytes = [0x00u8, 0x01, 0, 0, 0xf0, 0xff, 0xff, 0xff, b'a', 0];
+    let err = dynamic_message.decode(&bytes[..]).unwrap_err();
+    assert_eq!(decoding_error(err).0, "label");
+
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 0, 0, 0, 0, 0xf0, 0xff, 0xff, 0xff, b'a', 0,
+    ];
+    let err = dynamic_message.decode(&bytes[..]).unwrap_err();
+    assert_eq!(decoding_error(err).0, "wide");
+}
+
+#[test]
+fn decoding_unterminated_strings_fails() {
+    let dynamic_message = DynamicMsg::<RandomState>::new("package/msg/Tree", "string label")
+        .expect("The message definition was invalid");
+    let bytes = [0x00u8, 0x01, 0, 0, 2, 0, 0, 0, b'a', b'b'];
+
+    for err in [
+        dynamic_message.decode(&bytes[..]).unwrap_err(),
+        dynamic_message.decode_borrowed(&bytes).unwrap_err(),
+    ] {
+        assert_eq!(
+            decoding_error(err),
+            (
+                "label".to_owned(),
+                "The string is not terminated by a null byte".to_owned()
+            )
+        );
+    }
+}
+
+#[test]
+fn decoding_limits_the_payload_size() {
+    let dynamic_message =
+        DynamicMsg::<RandomState>::new("package/msg/Tree", "uint8 value\nfloat64[] samples")
+            .expect("The message definition was invalid");
+    let bytes = [
+        0x00u8, 0x01, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let limited = |max_bytes| {
+        dynamic_message.clone().with_options(DecodeOptions {
+            max_bytes: Some(max_bytes),
+            ..Default::default()
+        })
+    };
+
+    assert!(limited(28).decode(&bytes[..]).is_ok());
+    assert!(limited(28).decode_borrowed(&bytes).is_ok());
+
+    let err = limited(27).decode(&bytes[..]).unwrap_err();
+    assert_eq!(
+        decoding_error(err),
+        (
+            "samples[1]".to_owned(),
+            "The message exceeds the limit of 27 bytes".to_owned()
+        )
+    );
+    assert!(limited(27).decode_borrowed(&bytes).is_err());
+    assert!(limited(27)
+        .decode_projected(
+            &bytes[..],
+            &dynamic_message.projection(["samples"]).unwrap()
+        )
+        .is_err());
+
+    // Trailing data beyond the limit is not read either
+    let padded = [&bytes[..], &[0; 4]].concat();
+    assert!(limited(28).decode(&padded[..]).is_err());
+
+    let mut stream = [28u8, 0, 0, 0].to_vec();
+    stream.extend_from_slice(&bytes);
+    let limited = limited(27);
+    let mut messages =
+        limited.decode_stream(&stream[..], Framing::LengthPrefixed(Endianness::Little));
+    assert!(messages.next().unwrap().is_err());
+}
+
+#[test]
+fn decoding_limits_the_sequence_length() {
+    let dynamic_message =
+        DynamicMsg::<RandomState>::new("package/msg/Tree", "uint8 value\nuint16[] samples")
+            .expect("The message definition was invalid");
+    let bytes = [0x00u8, 0x01, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 1, 0, 2, 0, 3, 0];
+    let limited = |max_sequence_length| {
+        dynamic_message.clone().with_options(DecodeOptions {
+            max_sequence_length: Some(max_sequence_length),
+            ..Default::default()
+        })
+    };
+
+    assert!(limited(3).decode(&bytes[..]).is_ok());
+
+    for err in [
+        limited(2).decode(&bytes[..]).unwrap_err(),
+        limited(2).decode_borrowed(&bytes).unwrap_err(),
+    ] {
+        assert_eq!(
+            decoding_error(err),
+            (
+                "samples".to_owned(),
+                "The sequence of length 3 exceeds the limit of 2 elements".to_owned()
+            )
+        );
+    }
+}
+
+#[test]
+fn decoding_limits_the_nesting_depth() {
+    let dynamic_message =
+        DynamicMsg::<RandomState>::new("package/msg/Tree", "uint8 value\nTree[] children")
+            .expect("The message definition was invalid");
+    let bytes = [
+        0x00u8, 0x01, 0, 0, // CDR_LE
+        1, 0, 0, 0, 1, 0, 0, 0, // root
+        2, 0, 0, 0, 1, 0, 0, 0, // child
+        3, 0, 0, 0, 0, 0, 0, 0, // grandchild
+    ];
+    let limited = |max_depth| {
+        dynamic_message.clone().with_options(DecodeOptions {
+            max_depth: Some(max_depth),
+            ..Default::default()
+        })
+    };
+
+    let message = limited(3).decode(&bytes[..]).unwrap();
+    assert_eq!(
+        Value::Message(message)
+            .get_path("children[0].children[0].value")
+            .unwrap()
+            .as_deref(),
+        Some(&Value::U8(3))
+    );
+
+    let projection = dynamic_message.projection(["value"]).unwrap();
+    for err in [
+        limited(2).decode(&bytes[..]).unwrap_err(),
+        limited(2).decode_borrowed(&bytes).unwrap_err(),
+        limited(2)
+            .decode_projected(
+                &bytes[..],
+                &dynamic_message.projection(["children"]).unwrap(),
+            )
+            .unwrap_err(),
+    ] {
+        assert_eq!(
+            decoding_error(err),
+            (
+                "children[0].children[0]".to_owned(),
+                "The message is nested 3 levels deep, which exceeds the limit of 2".to_owned()
+            )
+        );
+    }
+    // Projections stop reading after the last selected field
+    assert!(limited(2).decode_projected(&bytes[..], &projection).is_ok());
+}
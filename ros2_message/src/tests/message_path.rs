@@ -1,4 +1,4 @@
-use crate::MessagePath;
+use crate::{MessagePath, ServicePath};
 
 #[test]
 fn package_names_must_be_at_least_two_characters() {
@@ -64,3 +64,31 @@ fn deserialize_checks_if_package_name_is_valid() {
     assert!(serde_json::from_str::<MessagePath>("\"foo_123/MessageName\"").is_ok());
     assert!(serde_json::from_str::<MessagePath>("\"Foo_123/MessageName\"").is_err());
 }
+
+#[test]
+fn service_paths_accept_srv_infix() {
+    let path = ServicePath::try_from("foo_123/srv/ServiceName").unwrap();
+    assert_eq!(path, ServicePath::new("foo_123", "ServiceName").unwrap());
+    assert_eq!(path, ServicePath::try_from("foo_123/ServiceName").unwrap());
+    assert_eq!(format!("{}", path), "foo_123/ServiceName");
+    assert!(ServicePath::try_from("foo_123/msg/ServiceName").is_err());
+    assert!(ServicePath::try_from("Foo_123/srv/ServiceName").is_err());
+    assert!(MessagePath::try_from("foo_123/srv/ServiceName").is_err());
+}
+
+#[test]
+fn service_paths_name_their_messages() {
+    let path = ServicePath::new("foo_123", "ServiceName").unwrap();
+    assert_eq!(
+        path.request(),
+        MessagePath::new("foo_123", "ServiceName_Request").unwrap()
+    );
+    assert_eq!(
+        path.response(),
+        MessagePath::new("foo_123", "ServiceName_Response").unwrap()
+    );
+    assert_eq!(
+        path.event(),
+        MessagePath::new("foo_123", "ServiceName_Event").unwrap()
+    );
+}
@@ -1,8 +1,33 @@
+mod action;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "decode")]
+mod borrowed;
+#[cfg(feature = "decode")]
+mod cdr_serde;
 mod data_type;
 #[cfg(feature = "decode")]
 mod decode;
+#[cfg(feature = "decode")]
+mod encode;
 mod field_info;
+mod json;
+#[cfg(feature = "decode")]
+mod limits;
 mod message_path;
 mod msg;
+#[cfg(all(feature = "decode", feature = "yaml"))]
+mod parse_yaml;
+#[cfg(feature = "decode")]
+mod projection;
+mod registry;
+#[cfg(feature = "decode")]
+mod service;
+#[cfg(feature = "decode")]
+mod stream;
 mod time;
+mod type_description;
 mod value;
+mod value_path;
+mod value_serde;
+mod yaml;
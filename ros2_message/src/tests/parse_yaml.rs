@@ -0,0 +1,244 @@
+use crate::dynamic::DynamicMsg;
+use crate::{Duration, Error, Time, ToYaml, Value};
+use std::hash::RandomState;
+
+fn point(x: f64, y: f64, z: f64) -> Value<RandomState> {
+    [
+        ("x", Value::F64(x)),
+        ("y", Value::F64(y)),
+        ("z", Value::F64(z)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn invalid_yaml(dynamic_message: &DynamicMsg<RandomState>, yaml: &str) -> (String, String) {
+    match dynamic_message.parse_yaml(yaml).unwrap_err() {
+        Error::InvalidYaml { path, reason } => (path, reason),
+        err => panic!("Expected invalid YAML, got {err:?}"),
+    }
+}
+
+#[test]
+fn parse_yaml_fills_in_defaults() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id "base"
+time stamp
+duration timeout
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains [1.5, -2]
+int16[3] offsets
+uint8[<=2] flags
+string<=3 code
+wchar letter
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y 1.0
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Scan", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message.parse_yaml("").unwrap();
+
+    let expected: Value<RandomState> = [
+        ("KIND", Value::U8(3)),
+        ("frame_id", Value::String("base".into())),
+        ("stamp", Value::Time(Time { sec: 0, nsec: 0 })),
+        ("timeout", Value::Duration(Duration { sec: 0, nsec: 0 })),
+        ("origin", point(0.0, 1.0, 0.0)),
+        ("points", Value::Array(vec![])),
+        (
+            "gains",
+            Value::Array(vec![Value::F32(1.5), Value::F32(-2.0)]),
+        ),
+        ("offsets", Value::Array(vec![Value::I16(0); 3])),
+        ("flags", Value::Array(vec![])),
+        ("code", Value::String("".into())),
+        ("letter", Value::U16(0)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(Value::Message(message), expected);
+    assert_eq!(
+        Value::Message(dynamic_message.parse_yaml("{}").unwrap()),
+        expected
+    );
+}
+
+#[test]
+fn parse_yaml_reads_ros2_topic_pub_arguments() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id "base"
+time stamp
+duration timeout
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains [1.5, -2]
+int16[3] offsets
+uint8[<=2] flags
+string<=3 code
+wchar letter
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y 1.0
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Scan", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message
+        .parse_yaml(
+            "{frame_id: 42, stamp: {sec: 5, nanosec: 6}, timeout: {sec: -1}, \
+             origin: {z: 2}, points: [{x: 1.5}, {y: .inf}], offsets: [1, -2, 3], \
+             flags: [255], code: abc, letter: é}",
+        )
+        .unwrap();
+
+    assert_eq!(message["frame_id"], Value::String("42".into()));
+    assert_eq!(message["stamp"], Value::Time(Time { sec: 5, nsec: 6 }));
+    assert_eq!(
+        message["timeout"],
+        Value::Duration(Duration { sec: -1, nsec: 0 })
+    );
+    assert_eq!(message["origin"], point(0.0, 1.0, 2.0));
+    assert_eq!(
+        message["points"],
+        Value::Array(vec![point(1.5, 1.0, 0.0), point(0.0, f64::INFINITY, 0.0)])
+    );
+    assert_eq!(
+        message["offsets"],
+        Value::Array(vec![Value::I16(1), Value::I16(-2), Value::I16(3)])
+    );
+    assert_eq!(message["flags"], Value::Array(vec![Value::U8(255)]));
+    assert_eq!(message["letter"], Value::U16(0xe9));
+    dynamic_message
+        .encode(&message)
+        .expect("Parsed messages can be encoded");
+}
+
+#[test]
+fn parse_yaml_reads_ros2_topic_echo_output() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id "base"
+time stamp
+duration timeout
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains [1.5, -2]
+int16[3] offsets
+uint8[<=2] flags
+string<=3 code
+wchar letter
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y 1.0
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Scan", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message
+        .parse_yaml("points: [{x: 1, y: 2, z: 3}]\ncode: 'yes'\nstamp: {sec: 7, nanosec: 8}")
+        .unwrap();
+
+    let mut echoed = message.to_yaml();
+    // `ros2 topic echo` prints constants, which cannot be set
+    assert!(echoed.starts_with("KIND: 3\n"));
+    echoed.replace_range(..echoed.find('\n').unwrap() + 1, "");
+
+    assert_eq!(dynamic_message.parse_yaml(&echoed).unwrap(), message);
+}
+
+#[test]
+fn parse_yaml_reports_the_failing_field() {
+    let msg_definition = r#"
+uint8 KIND=3
+string frame_id "base"
+time stamp
+duration timeout
+geometry_msgs/Point origin
+geometry_msgs/Point[] points
+float32[2] gains [1.5, -2]
+int16[3] offsets
+uint8[<=2] flags
+string<=3 code
+wchar letter
+
+================================================================================
+MSG: geometry_msgs/Point
+float64 x
+float64 y 1.0
+float64 z
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Scan", msg_definition)
+            .expect("The message definition was invalid");
+    for (yaml, path, reason) in [
+        ("[1, 2]", "", "expected a message of type `package/Scan`"),
+        (
+            "unknown: 1",
+            "unknown",
+            "the field does not exist in the message definition",
+        ),
+        ("KIND: 3", "KIND", "constants cannot be set"),
+        (
+            "points: [{}, {x: true}]",
+            "points[1].x",
+            "expected a value of type `float64`",
+        ),
+        (
+            "flags: [1, 256]",
+            "flags[1]",
+            "256 is out of range for a value of type `uint8`",
+        ),
+        (
+            "flags: [1, 2, 3]",
+            "flags",
+            "the sequence of length 3 exceeds its bound of 2",
+        ),
+        (
+            "offsets: [1]",
+            "offsets",
+            "expected an array of 3 elements, got 1",
+        ),
+        (
+            "code: abcd",
+            "code",
+            "the string of length 4 exceeds its bound of 3",
+        ),
+        ("gains: 1.0", "gains", "expected an array of `float32`"),
+        (
+            "stamp: {sec: -1}",
+            "stamp.sec",
+            "-1 is out of range for a value of type `uint32`",
+        ),
+    ] {
+        let (actual_path, actual_reason) = invalid_yaml(&dynamic_message, yaml);
+        assert_eq!(actual_path, path, "{yaml}");
+        assert_eq!(actual_reason, reason, "{yaml}");
+    }
+
+    assert_eq!(invalid_yaml(&dynamic_message, "origin: {x: 1.0").0, "");
+
+    let err = dynamic_message.parse_yaml("a: b\n---\nc: d").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to parse YAML: expected a single document"
+    );
+    let err = dynamic_message.parse_yaml("code: abcd").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "failed to parse YAML at field `code`: the string of length 4 exceeds its bound of 3"
+    );
+}
@@ -0,0 +1,224 @@
+use crate::dynamic::{DecodeOptions, DynamicMsg};
+use crate::{Error, Time, Value};
+use std::hash::RandomState;
+
+const BYTES: [u8; 77] = [
+    0x00, 0x01, 0, 0, // CDR_LE
+    10, 0, 0, 0, 20, 0, 0, 0, // stamp
+    4, 0, 0, 0, b'm', b'a', b'p', 0, // frame_id
+    2, 0, 0, 0, 0, 0, 0, 0, // samples length + padding
+    0, 0, 0, 0, 0, 0, 240, 63, 0, 0, 0, 0, 0, 0, 0, 64, // samples
+    2, 0, 0, 0, // points length
+    0, 0, 0, 63, 2, 0, 0, 0, b'a', 0, 0, 0, // points[0]
+    0, 0, 192, 63, 1, 0, 0, 0, 0, 0, 0, 0, // points[1] + padding
+    1, 0, 0, 0, 0, // label
+];
+
+#[test]
+fn projected_decoding_selects_nested_fields() {
+    let msg_definition = r#"
+std_msgs/Header header
+float64[] samples
+geometry_msgs/Point[] points
+string label
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+
+float32 x
+string name
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Samples", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message.decode(&BYTES[..]).unwrap();
+
+    let projection = dynamic_message
+        .projection(["header.stamp", "points.name", "label"])
+        .unwrap();
+    let projected = dynamic_message
+        .decode_projected(&BYTES[..], &projection)
+        .unwrap();
+
+    assert_eq!(projected.len(), 3);
+    let header = projected["header"].as_map().unwrap();
+    assert_eq!(header.len(), 1);
+    assert_eq!(
+        header["stamp"],
+        message["header"].as_map().unwrap()["stamp"]
+    );
+    let points = projected["points"].as_slice().unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(
+        points[0].as_map().unwrap()["name"],
+        Value::String("a".into())
+    );
+    assert!(!points[1].as_map().unwrap().contains_key("x"));
+    assert_eq!(projected["label"], message["label"]);
+}
+
+#[test]
+fn projected_decoding_stops_after_the_last_selected_field() {
+    let msg_definition = r#"
+std_msgs/Header header
+float64[] samples
+geometry_msgs/Point[] points
+string label
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+
+float32 x
+string name
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Samples", msg_definition)
+            .expect("The message definition was invalid");
+    let dynamic_message = dynamic_message.with_options(DecodeOptions {
+        map_builtin_time: true,
+        ..Default::default()
+    });
+
+    let projection = dynamic_message.projection(["header.stamp"]).unwrap();
+    let projected = dynamic_message
+        .decode_projected(&BYTES[..12], &projection)
+        .unwrap();
+    assert_eq!(
+        projected["header"].as_map().unwrap()["stamp"],
+        Value::Time(Time { sec: 10, nsec: 20 })
+    );
+
+    let projection = dynamic_message.projection(["label"]).unwrap();
+    assert!(dynamic_message
+        .decode_projected(&BYTES[..BYTES.len() - 1], &projection)
+        .is_err());
+}
+
+#[test]
+fn projected_decoding_of_whole_message_matches_decoding() {
+    let msg_definition = r#"
+std_msgs/Header header
+float64[] samples
+geometry_msgs/Point[] points
+string label
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+
+float32 x
+string name
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Samples", msg_definition)
+            .expect("The message definition was invalid");
+    let message = dynamic_message.decode(&BYTES[..]).unwrap();
+
+    let projection = dynamic_message
+        .projection(["header", "header.frame_id", "samples", "points", "label"])
+        .unwrap();
+    let projected = dynamic_message
+        .decode_projected(&BYTES[..], &projection)
+        .unwrap();
+    assert_eq!(projected, message);
+}
+
+#[test]
+fn projected_decoding_of_mutable_messages() {
+    let dynamic_message: DynamicMsg<RandomState> = DynamicMsg::new(
+        "example_msgs/Custom",
+        "uint8 a\nuint8 CONST=3\nuint32 b\nstring c",
+    )
+    .expect("failed to build DynamicMsg");
+    let bytes = [
+        0u8, 0x0b, 0, 0, // PL_CDR2_LE
+        35, 0, 0, 0, // DHEADER
+        1, 0, 0, 0x20, 0x44, 0x33, 0x22, 0x11, // b with length code 2
+        0, 0, 0, 0, 9, 0, 0, 0, // a with length code 0 + padding
+        7, 0, 0, 0x20, 1, 2, 3, 4, // unknown member 7
+        2, 0, 0, 0x50, 3, 0, 0, 0, b'h', b'i', 0, // c with length code 5
+    ];
+
+    let projection = dynamic_message.projection(["c", "CONST"]).unwrap();
+    let projected = dynamic_message
+        .decode_projected(&bytes[..], &projection)
+        .unwrap();
+    assert_eq!(projected.len(), 2);
+    assert_eq!(projected["c"], Value::String("hi".into()));
+    assert_eq!(projected["CONST"], Value::U8(3));
+}
+
+#[test]
+fn projection_rejects_invalid_paths() {
+    let msg_definition = r#"
+std_msgs/Header header
+float64[] samples
+geometry_msgs/Point[] points
+string label
+
+================================================================================
+MSG: std_msgs/Header
+
+builtin_interfaces/Time stamp
+string frame_id
+
+================================================================================
+MSG: builtin_interfaces/Time
+
+int32 sec
+uint32 nanosec
+
+================================================================================
+MSG: geometry_msgs/Point
+
+float32 x
+string name
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Samples", msg_definition)
+            .expect("The message definition was invalid");
+
+    assert!(matches!(
+        dynamic_message.projection(["header.seq"]),
+        Err(Error::InvalidFieldPath { path, .. }) if path == "header.seq"
+    ));
+    assert!(matches!(
+        dynamic_message.projection(["label.length"]),
+        Err(Error::InvalidFieldPath { .. })
+    ));
+}
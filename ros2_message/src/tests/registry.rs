@@ -0,0 +1,152 @@
+use crate::{Error, MessagePath, MessageRegistry};
+use std::fs;
+use std::hash::RandomState;
+
+const MSG_EXAMPLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../msg_examples");
+
+fn path(path: &str) -> MessagePath {
+    path.try_into().expect("Unexpected bad message path")
+}
+
+#[test]
+fn loads_messages_and_services_from_package_directories() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    registry.load_root(MSG_EXAMPLES).unwrap();
+
+    // The examples and the builtin messages
+    assert_eq!(registry.messages().count(), 17);
+    assert_eq!(registry.services().count(), 6);
+
+    let dependencies: Vec<_> = registry
+        .dependencies(&path("geometry_msgs/PoseStamped"))
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.path().to_string())
+        .collect();
+    assert_eq!(
+        dependencies,
+        [
+            "std_msgs/Header",
+            "geometry_msgs/Pose",
+            "geometry_msgs/Point",
+            "geometry_msgs/Quaternion",
+        ]
+    );
+
+    let srv = registry
+        .srv(&"roscpp_tutorials/TwoInts".try_into().unwrap())
+        .unwrap();
+    assert_eq!(srv.request().fields().len(), 2);
+    assert!(registry.resolve(srv.response()).unwrap().is_empty());
+}
+
+#[test]
+fn reports_missing_and_cyclic_dependencies() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    registry.load_root(MSG_EXAMPLES).unwrap();
+
+    // geometry_msgs/Twist is not part of the examples
+    assert!(matches!(
+        registry.validate(),
+        Err(Error::MessageDependencyMissing { package, name })
+            if package == "geometry_msgs" && name == "Twist"
+    ));
+    registry
+        .add_msg(
+            path("geometry_msgs/Twist"),
+            "Vector3 linear\nVector3 angular",
+        )
+        .unwrap();
+    registry.validate().unwrap();
+
+    registry.add_msg(path("foo/A"), "B b").unwrap();
+    registry
+        .add_msg(path("foo/B"), "uint8 x\nfoo/C[] c")
+        .unwrap();
+    registry.add_msg(path("foo/C"), "A a").unwrap();
+    let err = registry.dependencies(&path("foo/A")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cyclic message dependency: foo/A -> foo/B -> foo/C -> foo/A"
+    );
+    assert!(matches!(
+        registry.validate(),
+        Err(Error::CyclicDependency { .. })
+    ));
+}
+
+#[test]
+fn loads_install_prefixes() {
+    let prefix = std::env::temp_dir().join(format!("ros2_message_prefix_{}", std::process::id()));
+    let msg_dir = prefix.join("share/custom_msgs/msg");
+    fs::create_dir_all(&msg_dir).unwrap();
+    fs::write(
+        msg_dir.join("Reading.msg"),
+        "std_msgs/Header header\nfloat64 value",
+    )
+    .unwrap();
+    fs::write(msg_dir.join("Reading.idl"), "not a msg file").unwrap();
+    // Packages without definitions are skipped
+    fs::create_dir_all(prefix.join("share/Not A Package")).unwrap();
+
+    let mut registry = MessageRegistry::<RandomState>::new();
+    let result = registry.load_prefix(&prefix);
+    fs::remove_dir_all(&prefix).unwrap();
+    result.unwrap();
+
+    assert_eq!(registry.messages().count(), 3);
+    let msg = registry.msg(&path("custom_msgs/Reading")).unwrap();
+    assert_eq!(msg.fields().len(), 2);
+    assert!(matches!(
+        registry.load_root(prefix),
+        Err(Error::ReadDefinition { .. })
+    ));
+}
+
+#[test]
+fn builds_full_definitions() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    registry.load_root(MSG_EXAMPLES).unwrap();
+    registry
+        .add_msg(
+            path("foo/Marker"),
+            "std_msgs/Header header\ngeometry_msgs/Pose[] poses\nbuiltin_interfaces/Duration lifetime\nPose pose\n\nstd_msgs/Header other",
+        )
+        .unwrap();
+    registry.add_msg(path("foo/Pose"), "float64 x").unwrap();
+
+    let definition = registry.definition(&path("foo/Marker")).unwrap();
+    let sections: Vec<_> = definition
+        .split(&format!("\n{}\n", "=".repeat(80)))
+        .collect();
+    assert_eq!(sections.len(), 7);
+    assert_eq!(
+        sections[0],
+        registry.msg(&path("foo/Marker")).unwrap().source()
+    );
+    let headers: Vec<_> = sections[1..]
+        .iter()
+        .map(|section| section.lines().next().unwrap())
+        .collect();
+    assert_eq!(
+        headers,
+        [
+            "MSG: std_msgs/Header",
+            "MSG: geometry_msgs/Pose",
+            "MSG: geometry_msgs/Point",
+            "MSG: geometry_msgs/Quaternion",
+            "MSG: builtin_interfaces/Duration",
+            "MSG: foo/Pose",
+        ]
+    );
+
+    #[cfg(feature = "decode")]
+    {
+        let dynamic_msg = registry.dynamic_msg(&path("foo/Marker")).unwrap();
+        assert_eq!(dynamic_msg.msg().fields().len(), 5);
+        assert_eq!(
+            dynamic_msg.dependency(&path("geometry_msgs/Point")),
+            registry.msg(&path("geometry_msgs/Point"))
+        );
+    }
+}
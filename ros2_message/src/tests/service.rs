@@ -0,0 +1,112 @@
+use crate::dynamic::DynamicSrv;
+use crate::{MessageRegistry, MessageValue, ServicePath, Value};
+use std::hash::RandomState;
+
+const FIND_DEFINITION: &str = r#"string query
+---
+foo_msgs/Item[] items
+================================================================================
+MSG: foo_msgs/Item
+string name
+uint32 count
+"#;
+
+fn item(name: &str, count: u32) -> Value<RandomState> {
+    [
+        ("name", Value::String(name.into())),
+        ("count", Value::U32(count)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn message<const N: usize>(fields: [(&str, Value<RandomState>); N]) -> MessageValue<RandomState> {
+    fields
+        .into_iter()
+        .map(|(name, value)| (name.to_owned(), value))
+        .collect()
+}
+
+#[test]
+fn decodes_requests_and_responses() {
+    let service = DynamicSrv::<RandomState>::new("foo_msgs/srv/Find", FIND_DEFINITION).unwrap();
+    assert_eq!(
+        service.path(),
+        &ServicePath::new("foo_msgs", "Find").unwrap()
+    );
+    assert_eq!(service.request().msg().path().name(), "Find_Request");
+    assert_eq!(service.response().msg().path().name(), "Find_Response");
+
+    let request = message([("query", Value::String("bolt".into()))]);
+    let bytes = service.request().encode(&request).unwrap();
+    assert_eq!(service.decode_request(&bytes[..]).unwrap(), request);
+
+    let response = message([("items", Value::Array(vec![item("bolt", 3), item("nut", 5)]))]);
+    let bytes = service.response().encode(&response).unwrap();
+    assert_eq!(service.decode_response(&bytes[..]).unwrap(), response);
+}
+
+#[test]
+fn decodes_service_events() {
+    let service =
+        DynamicSrv::<RandomState>::new("foo_msgs/srv/Find_Event", FIND_DEFINITION).unwrap();
+
+    let info: Value<RandomState> = [
+        ("event_type", Value::U8(2)),
+        (
+            "stamp",
+            [("sec", Value::I32(10)), ("nanosec", Value::U32(20))]
+                .into_iter()
+                .collect(),
+        ),
+        ("client_gid", Value::Array(vec![Value::U8(7); 16])),
+        ("sequence_number", Value::I64(1)),
+    ]
+    .into_iter()
+    .collect();
+    let event = message([
+        ("info", info),
+        ("request", Value::Array(vec![])),
+        (
+            "response",
+            Value::Array(vec![[("items", Value::Array(vec![item("nut", 5)]))]
+                .into_iter()
+                .collect()]),
+        ),
+    ]);
+
+    let bytes = service.event().encode(&event).unwrap();
+    let decoded = service.decode_event(&bytes[..]).unwrap();
+    assert_eq!(decoded["request"], event["request"]);
+    assert_eq!(decoded["response"], event["response"]);
+
+    // Constants of the event info are part of the decoded message
+    let info = decoded["info"].as_map().unwrap();
+    assert_eq!(info["event_type"], Value::U8(2));
+    assert_eq!(info["RESPONSE_SENT"], Value::U8(2));
+    assert_eq!(info["sequence_number"], Value::I64(1));
+}
+
+#[test]
+fn creates_services_from_registry() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    registry
+        .add_msg(
+            "foo_msgs/Item".try_into().unwrap(),
+            "string name\nuint32 count",
+        )
+        .unwrap();
+    let path = ServicePath::new("foo_msgs", "Find").unwrap();
+    registry
+        .add_srv(path.clone(), "string query\n---\nItem[] items")
+        .unwrap();
+
+    let service = registry.dynamic_srv(&path).unwrap();
+    let response = message([("items", Value::Array(vec![item("bolt", 1)]))]);
+    let bytes = service.response().encode(&response).unwrap();
+    assert_eq!(service.decode_response(&bytes[..]).unwrap(), response);
+
+    assert!(registry
+        .dynamic_srv(&ServicePath::new("foo_msgs", "Missing").unwrap())
+        .is_err());
+}
@@ -0,0 +1,156 @@
+use crate::dynamic::{DynamicMsg, Encapsulation, Endianness, Framing};
+use crate::{Error, Value};
+use std::hash::RandomState;
+use std::io::BufReader;
+
+#[test]
+fn streaming_concatenated_messages() {
+    let msg_definition = r#"
+uint32 id
+string label
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Labeled", msg_definition)
+            .expect("The message definition was invalid");
+    let bytes = [
+        0x00, 0x01, 0, 2, // CDR_LE with 2 bytes padding
+        1, 0, 0, 0, 2, 0, 0, 0, b'a', 0, 0, 0, // id + label + padding
+        0x00, 0x00, 0, 0, // CDR_BE
+        0, 0, 0, 2, 0, 0, 0, 4, b'x', b'y', b'z', 0, // id + label
+    ];
+
+    let messages = dynamic_message
+        .decode_stream(BufReader::new(&bytes[..]), Framing::Concatenated)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].message["id"], Value::U32(1));
+    assert_eq!(messages[0].message["label"], Value::String("a".into()));
+    assert_eq!(messages[0].encapsulation, Encapsulation::CdrLe);
+    assert_eq!((messages[0].offset, messages[0].size), (0, 16));
+    assert_eq!(messages[1].message["label"], Value::String("xyz".into()));
+    assert_eq!(messages[1].encapsulation, Encapsulation::CdrBe);
+    assert_eq!((messages[1].offset, messages[1].size), (16, 16));
+}
+
+#[test]
+fn streaming_encoded_messages() {
+    let msg_definition = r#"
+uint32 id
+string label
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Labeled", msg_definition)
+            .expect("The message definition was invalid");
+    let mut bytes = Vec::new();
+    let mut sizes = Vec::new();
+    for (id, label) in [(3u32, "first"), (4, ""), (5, "third")] {
+        let message = [
+            ("id".to_owned(), Value::U32(id)),
+            ("label".to_owned(), Value::String(label.to_owned())),
+        ]
+        .into_iter()
+        .collect();
+        let encoded = dynamic_message.encode(&message).unwrap();
+        sizes.push(encoded.len());
+        bytes.extend(encoded);
+    }
+
+    let mut stream = dynamic_message.decode_stream(&bytes[..], Framing::Concatenated);
+    let ids = stream
+        .by_ref()
+        .map(|m| {
+            let m = m.unwrap();
+            (m.message["id"].clone(), m.size)
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        ids,
+        [
+            (Value::U32(3), sizes[0]),
+            (Value::U32(4), sizes[1]),
+            (Value::U32(5), sizes[2])
+        ]
+    );
+    assert_eq!(stream.offset(), bytes.len());
+}
+
+#[test]
+fn streaming_length_prefixed_messages_skips_invalid_payloads() {
+    let msg_definition = r#"
+uint32 id
+string label
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Labeled", msg_definition)
+            .expect("The message definition was invalid");
+    let bytes = [
+        12, 0, 0, 0, // length
+        0x00, 0x01, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, // valid
+        8, 0, 0, 0, // length
+        0x00, 0x01, 0, 0, 2, 0, 0, 0, // label is missing
+        13, 0, 0, 0, // length
+        0x00, 0x01, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0, 0, // valid
+    ];
+
+    let messages = dynamic_message
+        .decode_stream(&bytes[..], Framing::LengthPrefixed(Endianness::Little))
+        .collect::<Vec<_>>();
+
+    assert_eq!(messages.len(), 3);
+    let first = messages[0].as_ref().unwrap();
+    assert_eq!(first.message["id"], Value::U32(1));
+    assert_eq!((first.offset, first.size), (0, 16));
+    assert!(messages[1].is_err());
+    let third = messages[2].as_ref().unwrap();
+    assert_eq!(third.message["id"], Value::U32(3));
+    assert_eq!(third.message["label"], Value::String("".into()));
+    assert_eq!((third.offset, third.size), (28, 17));
+}
+
+#[test]
+fn streaming_stops_after_truncated_message() {
+    let msg_definition = r#"
+uint32 id
+string label
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Labeled", msg_definition)
+            .expect("The message definition was invalid");
+    let bytes = [
+        0x00, 0x01, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, // complete
+        0x00, 0x01, 0, 0, 2, 0, // truncated
+    ];
+
+    let mut stream = dynamic_message.decode_stream(&bytes[..], Framing::Concatenated);
+
+    assert_eq!(stream.next().unwrap().unwrap().size, 12);
+    assert!(matches!(
+        stream.next(),
+        Some(Err(Error::DecodingError { .. }))
+    ));
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn streaming_stops_after_truncated_length_prefix() {
+    let msg_definition = r#"
+uint32 id
+string label
+"#;
+    let dynamic_message: DynamicMsg<RandomState> =
+        DynamicMsg::new("package/msg/Labeled", msg_definition)
+            .expect("The message definition was invalid");
+    let bytes = [8, 0, 0, 0, 0x00, 0x01, 0, 0];
+
+    let mut stream =
+        dynamic_message.decode_stream(&bytes[..], Framing::LengthPrefixed(Endianness::Little));
+
+    assert!(matches!(
+        stream.next(),
+        Some(Err(Error::DecodingError { .. }))
+    ));
+    assert!(stream.next().is_none());
+}
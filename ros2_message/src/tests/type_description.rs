@@ -0,0 +1,191 @@
+use crate::{Error, MessagePath, MessageRegistry, Msg, Srv};
+use std::hash::RandomState;
+
+fn path(path: &str) -> MessagePath {
+    path.try_into().expect("Unexpected bad message path")
+}
+
+fn type_names(names: &[crate::IndividualTypeDescription]) -> Vec<&str> {
+    names.iter().map(|d| d.type_name.as_str()).collect()
+}
+
+#[test]
+fn type_hashes_match_ros2() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    let header = registry
+        .add_msg(
+            path("std_msgs/Header"),
+            "builtin_interfaces/Time stamp\nstring frame_id",
+        )
+        .unwrap()
+        .clone();
+
+    assert_eq!(
+        header.type_hash(&registry).unwrap(),
+        "RIHS01_f49fb3ae2cf070f793645ff749683ac6b06203e41c891e17701b1cb597ce6a01"
+    );
+    assert_eq!(
+        registry
+            .msg(&path("builtin_interfaces/Time"))
+            .unwrap()
+            .type_hash(&registry)
+            .unwrap(),
+        "RIHS01_b106235e25a4c5ed35098aa0a61a3ee9c9b18d197f398b0e4206cea9acf9c197"
+    );
+    // Constants and comments are not part of the hash
+    let string = Msg::<RandomState>::new(
+        path("std_msgs/String"),
+        "# some text\nstring data\nuint8 FOO=1",
+    )
+    .unwrap();
+    assert_eq!(
+        string.type_hash(&registry).unwrap(),
+        "RIHS01_df668c740482bbd48fb39d76a70dfd4bd59db1288021743503259e948f6b1a18"
+    );
+}
+
+#[test]
+fn describes_field_types() {
+    let mut registry = MessageRegistry::<RandomState>::new();
+    registry.add_msg(path("foo/Inner"), "").unwrap();
+    let msg = Msg::<RandomState>::new(
+        path("foo/Outer"),
+        r#"byte a
+char b
+float64[3] c
+string<=5[<=2] d
+wstring[] e
+Inner f
+time g
+bool h true"#,
+    )
+    .unwrap();
+    let description = msg.type_description(&registry).unwrap();
+
+    let fields: Vec<_> = description
+        .type_description
+        .fields
+        .iter()
+        .map(|field| {
+            let field_type = &field.field_type;
+            (
+                field.name.as_str(),
+                field_type.type_id,
+                field_type.capacity,
+                field_type.string_capacity,
+                field_type.nested_type_name.as_str(),
+                field.default_value.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("a", 16, 0, 0, "", ""),
+            ("b", 3, 0, 0, "", ""),
+            ("c", 59, 3, 0, "", ""),
+            ("d", 117, 2, 5, "", ""),
+            ("e", 162, 0, 0, "", ""),
+            ("f", 1, 0, 0, "foo/msg/Inner", ""),
+            ("g", 1, 0, 0, "builtin_interfaces/msg/Time", ""),
+            ("h", 15, 0, 0, "", "true"),
+        ]
+    );
+    assert_eq!(
+        type_names(&description.referenced_type_descriptions),
+        ["builtin_interfaces/msg/Time", "foo/msg/Inner"]
+    );
+
+    // Empty messages get a placeholder member
+    let inner = &description.referenced_type_descriptions[1];
+    assert_eq!(inner.fields.len(), 1);
+    assert_eq!(inner.fields[0].name, "structure_needs_at_least_one_member");
+    assert_eq!(inner.fields[0].field_type.type_id, 3);
+}
+
+#[test]
+fn describes_services_with_events() {
+    let registry = MessageRegistry::<RandomState>::new();
+    let srv = Srv::<RandomState>::new(
+        "std_srvs/Trigger".try_into().unwrap(),
+        r#"---
+bool success   # indicate successful run of triggered service
+string message # informational, e.g. for error messages"#,
+    )
+    .unwrap();
+    let description = srv.type_description(&registry).unwrap();
+
+    assert_eq!(
+        description.type_description.type_name,
+        "std_srvs/srv/Trigger"
+    );
+    assert_eq!(
+        type_names(&description.referenced_type_descriptions),
+        [
+            "builtin_interfaces/msg/Time",
+            "service_msgs/msg/ServiceEventInfo",
+            "std_srvs/srv/Trigger_Event",
+            "std_srvs/srv/Trigger_Request",
+            "std_srvs/srv/Trigger_Response",
+        ]
+    );
+
+    let event = &description.referenced_type_descriptions[2];
+    let event_fields: Vec<_> = event
+        .fields
+        .iter()
+        .map(|field| {
+            (
+                field.name.as_str(),
+                field.field_type.type_id,
+                field.field_type.capacity,
+                field.field_type.nested_type_name.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        event_fields,
+        [
+            ("info", 1, 0, "service_msgs/msg/ServiceEventInfo"),
+            ("request", 97, 1, "std_srvs/srv/Trigger_Request"),
+            ("response", 97, 1, "std_srvs/srv/Trigger_Response"),
+        ]
+    );
+    let request = &description.referenced_type_descriptions[3];
+    assert_eq!(
+        request.fields[0].name,
+        "structure_needs_at_least_one_member"
+    );
+    let response = &description.referenced_type_descriptions[4];
+    assert_eq!(
+        response
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.field_type.type_id))
+            .collect::<Vec<_>>(),
+        [("success", 15), ("message", 17)]
+    );
+
+    // The hash covers the event and its service_msgs/ServiceEventInfo too
+    let add_two_ints = Srv::<RandomState>::new(
+        "example_interfaces/AddTwoInts".try_into().unwrap(),
+        "int64 a\nint64 b\n---\nint64 sum",
+    )
+    .unwrap();
+    assert_eq!(
+        add_two_ints.type_hash(&registry).unwrap(),
+        "RIHS01_e118de6bf5eeb66a2491b5bda11202e7b68f198d6f67922cf30364858239c81a"
+    );
+}
+
+#[test]
+fn missing_dependencies_are_reported() {
+    let registry = MessageRegistry::<RandomState>::new();
+    let msg = Msg::<RandomState>::new(path("foo/Outer"), "Inner inner").unwrap();
+
+    assert!(matches!(
+        msg.type_hash(&registry),
+        Err(Error::MessageDependencyMissing { package, name })
+            if package == "foo" && name == "Inner"
+    ));
+}
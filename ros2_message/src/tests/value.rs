@@ -1,4 +1,4 @@
-use crate::{MessageValue, Time, Value};
+use crate::{MessageValue, PrimitiveArray, Time, Value};
 
 #[test]
 fn display() {
@@ -53,29 +53,56 @@ fn display() {
         format!("{}", Value::Message(message)),
         r#"
 header: 
-  frame_id: "abc"
   seq: 22
   stamp: 123.100000000
+  frame_id: "abc"
 poses: 
   - 
-    orientation: 
-      w: 7
-      x: 4
-      y: 5
-      z: 6
     position: 
       x: 1
       y: 2
       z: 3
+    orientation: 
+      x: 4
+      y: 5
+      z: 6
+      w: 7
   - 
+    position: 
+      x: 8
+      y: 9
+      z: 10
     orientation: 
-      w: 14
       x: 11
       y: 12
       z: 13
-    position: 
-      x: 8
-      y: 9
-      z: 10"#
+      w: 14"#
     )
 }
+
+#[test]
+fn primitive_arrays_equal_arrays_of_their_elements() {
+    let array = |values: Vec<Value>| Value::Array(values);
+    let floats = Value::PrimitiveArray(PrimitiveArray::F32(vec![0.5, -1.0]));
+
+    assert_eq!(floats, array(vec![Value::F32(0.5), Value::F32(-1.0)]));
+    assert_eq!(array(vec![Value::F32(0.5), Value::F32(-1.0)]), floats);
+    assert_ne!(floats, array(vec![Value::F32(0.5)]));
+    assert_ne!(floats, array(vec![Value::F32(0.5), Value::F32(1.0)]));
+    assert_ne!(floats, array(vec![Value::F64(0.5), Value::F64(-1.0)]));
+    assert_ne!(
+        floats,
+        Value::PrimitiveArray(PrimitiveArray::F64(vec![0.5, -1.0]))
+    );
+    assert_eq!(
+        Value::PrimitiveArray(PrimitiveArray::U8(vec![])),
+        array(vec![])
+    );
+
+    let nested =
+        |data| Value::from_iter([("images", array(vec![Value::from_iter([("data", data)])]))]);
+    assert_eq!(
+        nested(Value::PrimitiveArray(PrimitiveArray::U8(vec![7]))),
+        nested(array(vec![Value::U8(7)]))
+    );
+}
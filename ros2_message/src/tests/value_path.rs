@@ -0,0 +1,169 @@
+use crate::{Error, MessageValue, PrimitiveArray, Time, ValuePath};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+type Value = crate::Value<std::hash::RandomState>;
+
+fn transform(frame_id: &str, x: f64) -> Value {
+    Value::from_iter([
+        (
+            "header",
+            Value::from_iter([
+                ("frame_id", Value::String(frame_id.into())),
+                ("stamp", Value::Time(Time { sec: 3, nsec: 4 })),
+            ]),
+        ),
+        ("translation", Value::from_iter([("x", x), ("y", -x)])),
+    ])
+}
+
+fn message() -> Value {
+    Value::from_iter([
+        (
+            "transforms",
+            Value::Array(vec![
+                transform("map", 1.0),
+                transform("odom", 2.0),
+                transform("base_link", 3.0),
+            ]),
+        ),
+        (
+            "ranges",
+            Value::PrimitiveArray(PrimitiveArray::F32(vec![0.5, 1.5, 2.5])),
+        ),
+    ])
+}
+
+fn query(path: &str) -> Vec<Value> {
+    message()
+        .query(path)
+        .unwrap()
+        .into_iter()
+        .map(Cow::into_owned)
+        .collect()
+}
+
+#[test]
+fn get_path_selects_nested_fields() {
+    let message = message();
+
+    assert_eq!(
+        message
+            .get_path("transforms[1].translation.x")
+            .unwrap()
+            .as_deref(),
+        Some(&Value::F64(2.0))
+    );
+    assert_eq!(
+        message
+            .get_path("transforms[-1].header.frame_id")
+            .unwrap()
+            .as_deref(),
+        Some(&Value::String("base_link".into()))
+    );
+    assert_eq!(
+        message
+            .get_path("transforms[0].header.stamp.nanosec")
+            .unwrap()
+            .as_deref(),
+        Some(&Value::U32(4))
+    );
+    assert_eq!(
+        message.get_path("ranges[1]").unwrap().as_deref(),
+        Some(&Value::F32(1.5))
+    );
+    assert!(message.get_path("transforms[3]").unwrap().is_none());
+    assert!(message.get_path("transforms[-4]").unwrap().is_none());
+    assert!(message.get_path("ranges.x").unwrap().is_none());
+}
+
+#[test]
+fn query_selects_multiple_values() {
+    assert_eq!(
+        query("transforms[*].header.frame_id"),
+        vec![
+            Value::String("map".into()),
+            Value::String("odom".into()),
+            Value::String("base_link".into()),
+        ]
+    );
+    assert_eq!(
+        query("transforms[1:].translation.x"),
+        vec![Value::F64(2.0), Value::F64(3.0)]
+    );
+    assert_eq!(
+        query("transforms[:-2].translation.x"),
+        vec![Value::F64(1.0)]
+    );
+    assert_eq!(
+        query("transforms[0].translation.*"),
+        vec![Value::F64(1.0), Value::F64(-1.0)]
+    );
+    assert_eq!(query("ranges[-2:]"), vec![Value::F32(1.5), Value::F32(2.5)]);
+    assert!(query("transforms[2:1]").is_empty());
+}
+
+#[test]
+fn paths_start_at_decoded_messages() {
+    let Value::Message(message) = message() else {
+        unreachable!()
+    };
+    let message: MessageValue<_> = message;
+
+    let path = ValuePath::try_from("transforms[*].translation.y").unwrap();
+    assert_eq!(path.query_in(&message).len(), 3);
+    assert_eq!(path.get_in(&message).as_deref(), Some(&Value::F64(-1.0)));
+}
+
+#[test]
+fn paths_are_displayed_in_canonical_form() {
+    for path in [
+        "pose.position.x",
+        "points[3].x",
+        "transforms[*].header.frame_id",
+        "points[-2:].x",
+        "points[:1]",
+        "[0].*",
+    ] {
+        assert_eq!(ValuePath::try_from(path).unwrap().to_string(), path);
+    }
+    assert_eq!(
+        "points[ 1 : -1 ]".parse::<ValuePath>().unwrap().to_string(),
+        "points[1:-1]"
+    );
+}
+
+#[test]
+fn parsing_errors_point_at_the_bad_segment() {
+    let reason = |path: &str| match ValuePath::try_from(path) {
+        Err(Error::InvalidFieldPath { reason, .. }) => reason,
+        other => panic!("Expected an error for `{path}`, got {other:?}"),
+    };
+
+    assert_eq!(reason(""), "expected a field name at byte 0 in segment ``");
+    assert_eq!(
+        reason("pose..x"),
+        "expected a field name at byte 5 in segment `.`"
+    );
+    assert_eq!(
+        reason("points[x].y"),
+        "`x` is not a valid index in segment `[x]`"
+    );
+    assert_eq!(reason("points[]"), "expected an index in segment `[]`");
+    assert_eq!(
+        reason("points[1]x"),
+        "expected `.` or `[` at byte 9 in segment `x`"
+    );
+    assert_eq!(
+        reason("a[0]é"),
+        "expected `.` or `[` at byte 4 in segment `é`"
+    );
+    assert_eq!(
+        reason("pose.pösition"),
+        "field names may only contain letters, digits and `_` in segment `.pösition`"
+    );
+    assert_eq!(
+        reason("pose.posi-tion"),
+        "field names may only contain letters, digits and `_` in segment `.posi-tion`"
+    );
+}
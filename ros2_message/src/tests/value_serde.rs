@@ -0,0 +1,149 @@
+use crate::{from_value, to_value, Duration, Error, PrimitiveArray, Time, Value};
+use serde::Deserialize as _;
+use serde_derive::{Deserialize, Serialize};
+use std::hash::RandomState;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Mode {
+    Idle,
+    Target { x: f64, y: f64 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Status {
+    label: String,
+    stamp: Time,
+    timeout: Duration,
+    readings: Vec<i32>,
+    #[serde(with = "bytes")]
+    raw: Vec<u8>,
+    mode: Mode,
+    fallback: Mode,
+}
+
+mod bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(v)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        Vec::deserialize(deserializer)
+    }
+}
+
+fn status() -> Status {
+    Status {
+        label: "ok".into(),
+        stamp: Time { sec: 1, nsec: 2 },
+        timeout: Duration { sec: -3, nsec: 0 },
+        readings: vec![4, -5],
+        raw: vec![6, 7],
+        mode: Mode::Idle,
+        fallback: Mode::Target { x: 1.0, y: 2.0 },
+    }
+}
+
+#[test]
+fn to_value_produces_natural_values() {
+    let value: Value<RandomState> = to_value(&status()).unwrap();
+
+    let expected: Value<RandomState> = [
+        ("label", Value::String("ok".into())),
+        ("stamp", Value::Time(Time { sec: 1, nsec: 2 })),
+        ("timeout", Value::Duration(Duration { sec: -3, nsec: 0 })),
+        (
+            "readings",
+            Value::Array(vec![Value::I32(4), Value::I32(-5)]),
+        ),
+        ("raw", Value::PrimitiveArray(PrimitiveArray::U8(vec![6, 7]))),
+        ("mode", Value::String("Idle".into())),
+        (
+            "fallback",
+            [(
+                "Target",
+                [("x", Value::F64(1.0)), ("y", Value::F64(2.0))]
+                    .into_iter()
+                    .collect::<Value<RandomState>>(),
+            )]
+            .into_iter()
+            .collect(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn values_deserialize_into_structs() {
+    let value: Value<RandomState> = to_value(&status()).unwrap();
+
+    assert_eq!(from_value::<Status, _>(value.clone()).unwrap(), status());
+    assert_eq!(Status::deserialize(&value).unwrap(), status());
+}
+
+#[test]
+fn borrowed_values_lend_strings_and_bytes() {
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        label: &'a str,
+        raw: &'a [u8],
+    }
+
+    let value: Value<RandomState> = to_value(&status()).unwrap();
+    let borrowed = Borrowed::deserialize(&value).unwrap();
+
+    assert_eq!(borrowed.label, "ok");
+    assert_eq!(borrowed.raw, [6, 7]);
+}
+
+#[test]
+fn primitive_arrays_deserialize_as_sequences() {
+    let value: Value<RandomState> = [("readings", PrimitiveArray::F32(vec![1.5, 2.5]))]
+        .into_iter()
+        .collect();
+
+    #[derive(Deserialize)]
+    struct Readings {
+        readings: [f32; 2],
+    }
+
+    assert_eq!(
+        from_value::<Readings, _>(value).unwrap().readings,
+        [1.5, 2.5]
+    );
+}
+
+#[test]
+fn mismatched_values_fail_to_deserialize() {
+    let value: Value<RandomState> = [("label", Value::U8(1))].into_iter().collect();
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Labeled {
+        label: String,
+    }
+
+    match from_value::<Labeled, _>(value).unwrap_err() {
+        Error::Deserialization(reason) => {
+            assert_eq!(reason, "invalid type: integer `1`, expected a string")
+        }
+        err => panic!("Expected a deserialization error, got {err:?}"),
+    }
+}
+
+#[test]
+fn values_without_representation_fail_to_serialize() {
+    #[derive(Serialize)]
+    struct Optional {
+        value: Option<u8>,
+    }
+
+    assert!(to_value::<RandomState, _>(&Optional { value: Some(1) }).is_ok());
+    assert!(matches!(
+        to_value::<RandomState, _>(&Optional { value: None }),
+        Err(Error::Serialization(_))
+    ));
+}
@@ -0,0 +1,168 @@
+use crate::{Duration, MessageValue, PrimitiveArray, Time, ToYaml, Value, YamlOptions};
+use std::hash::RandomState;
+
+fn message<const N: usize>(fields: [(&str, Value<RandomState>); N]) -> Value<RandomState> {
+    fields.into_iter().collect()
+}
+
+#[test]
+fn yaml_matches_ros2_topic_echo_layout() {
+    let pose = |x, y, tags: &[&str]| {
+        message([
+            (
+                "position",
+                message([("x", Value::F64(x)), ("y", Value::F64(y))]),
+            ),
+            (
+                "tags",
+                Value::Array(tags.iter().map(|t| Value::String(t.to_string())).collect()),
+            ),
+            ("empty", Value::PrimitiveArray(PrimitiveArray::U8(vec![]))),
+        ])
+    };
+    let value = message([
+        (
+            "header",
+            message([
+                ("stamp", Value::Time(Time { sec: 1, nsec: 2 })),
+                ("frame_id", Value::String("map".into())),
+            ]),
+        ),
+        (
+            "poses",
+            Value::Array(vec![pose(1.0, -2.5, &["a", "yes"]), pose(0.0, 0.0, &[])]),
+        ),
+        (
+            "matrix",
+            Value::Array(vec![
+                Value::PrimitiveArray(PrimitiveArray::I32(vec![1, 2])),
+                Value::Array(vec![
+                    Value::I32(3),
+                    Value::PrimitiveArray(PrimitiveArray::I32(vec![4, 5])),
+                ]),
+                Value::Array(vec![]),
+            ]),
+        ),
+        ("text", Value::String("two\nlines".into())),
+        ("nothing", Value::Message(MessageValue::default())),
+    ]);
+
+    let expected = "\
+header:
+  stamp:
+    sec: 1
+    nanosec: 2
+  frame_id: map
+poses:
+- position:
+    x: 1.0
+    y: -2.5
+  tags:
+  - a
+  - 'yes'
+  empty: []
+- position:
+    x: 0.0
+    y: 0.0
+  tags: []
+  empty: []
+matrix:
+- - 1
+  - 2
+- - 3
+  - - 4
+    - 5
+- []
+text: 'two
+
+  lines'
+nothing: {}
+";
+    assert_eq!(value.to_yaml(), expected);
+    assert_eq!(value.try_into_map().unwrap().to_yaml(), expected);
+}
+
+#[test]
+fn yaml_quotes_strings_like_pyyaml() {
+    for (string, expected) in [
+        ("", "''"),
+        ("base_link", "base_link"),
+        ("two words", "two words"),
+        ("yes", "'yes'"),
+        ("Off", "'Off'"),
+        ("null", "'null'"),
+        ("1", "'1'"),
+        ("1.5", "'1.5'"),
+        ("1e5", "1e5"),
+        ("2024-01-02", "'2024-01-02'"),
+        ("a: b", "'a: b'"),
+        ("a:b", "a:b"),
+        ("- a", "'- a'"),
+        ("a #b", "'a #b'"),
+        ("a#b", "a#b"),
+        (" lead", "' lead'"),
+        ("...", "'...'"),
+        ("it's", "it's"),
+        ("'quoted'", "'''quoted'''"),
+        ("ünïcode", "ünïcode"),
+        ("tab\there", r#""tab\there""#),
+        ("bell\x07", r#""bell\a""#),
+        ("a \nb", r#""a \nb""#),
+        ("\u{feff}bom", r#""\uFEFFbom""#),
+    ] {
+        let value = message([("k", Value::String(string.into()))]);
+        assert_eq!(value.to_yaml(), format!("k: {expected}\n"), "{string:?}");
+    }
+}
+
+#[test]
+fn yaml_formats_numbers_like_python() {
+    for (value, expected) in [
+        (Value::F64(0.0), "0.0"),
+        (Value::F64(-0.0), "-0.0"),
+        (Value::F64(100.0), "100.0"),
+        (Value::F64(0.001), "0.001"),
+        (Value::F64(1e-5), "1.0e-05"),
+        (Value::F64(-1.5e-7), "-1.5e-07"),
+        (Value::F64(1e15), "1000000000000000.0"),
+        (Value::F64(1e16), "1.0e+16"),
+        (Value::F64(2.5e16), "2.5e+16"),
+        (Value::F64(f64::NAN), ".nan"),
+        (Value::F64(f64::NEG_INFINITY), "-.inf"),
+        (Value::F32(0.1), "0.10000000149011612"),
+        (Value::I8(-3), "-3"),
+        (Value::U64(u64::MAX), "18446744073709551615"),
+        (Value::Bool(true), "true"),
+    ] {
+        let field = message([("k", value.clone())]);
+        assert_eq!(field.to_yaml(), format!("k: {expected}\n"), "{value:?}");
+        // Plain scalars on their own end the document explicitly
+        assert_eq!(value.to_yaml(), format!("{expected}\n...\n"), "{value:?}");
+    }
+
+    let value = message([("timeout", Value::Duration(Duration { sec: -1, nsec: 5 }))]);
+    assert_eq!(value.to_yaml(), "timeout:\n  sec: -1\n  nanosec: 5\n");
+    assert_eq!(Value::<RandomState>::String("".into()).to_yaml(), "''\n");
+}
+
+#[test]
+fn yaml_can_be_truncated_and_separated() {
+    let value = message([
+        ("name", Value::String("abcdef".into())),
+        ("short", Value::String("ab".into())),
+        (
+            "data",
+            Value::PrimitiveArray(PrimitiveArray::U8(vec![1, 2, 3])),
+        ),
+        ("names", Value::Array(vec![Value::String("ünïcode".into())])),
+    ]);
+    let options = YamlOptions {
+        truncate_length: Some(2),
+        separator: true,
+    };
+
+    assert_eq!(
+        value.to_yaml_with_options(&options),
+        "name: ab...\nshort: ab\ndata:\n- 1\n- 2\n- '...'\nnames:\n- ün...\n---\n"
+    );
+}
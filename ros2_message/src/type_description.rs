@@ -0,0 +1,507 @@
+use crate::srv::SERVICE_EVENT_INFO;
+use crate::{DataType, Error, FieldCase, FieldInfo, I8Variant, MessagePath, MessageRegistry};
+use crate::{Msg, Result, Srv, U8Variant};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::hash::BuildHasher;
+
+/// Prefix of type hashes computed with the first version of the ROS 2 hashing scheme.
+pub const RIHS01_PREFIX: &str = "RIHS01_";
+
+/// Type description of a message or service and every type it references, as defined by
+/// [REP 2011](https://ros.org/reps/rep-2011.html) and the `type_description_interfaces/TypeDescription`
+/// message.
+///
+/// ROS 2 identifies types by the [hash](Self::type_hash()) of this description, which is
+/// advertised with every topic and stored in the metadata of rosbag2 recordings.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TypeDescription {
+    /// Description of the type itself.
+    pub type_description: IndividualTypeDescription,
+    /// Descriptions of all types referenced directly or indirectly, sorted by their type name.
+    pub referenced_type_descriptions: Vec<IndividualTypeDescription>,
+}
+
+/// Description of a single type without the types it references.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IndividualTypeDescription {
+    /// Fully qualified name of the type, like `std_msgs/msg/String`.
+    pub type_name: String,
+    /// Fields of the type in definition order, constants are not part of the description.
+    pub fields: Vec<FieldDescription>,
+}
+
+/// Description of one field of a type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FieldDescription {
+    /// Name of the field.
+    pub name: String,
+    /// Type of the field.
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    /// Default value as written in the definition, or an empty string.
+    ///
+    /// Defaults do not contribute to the type hash.
+    pub default_value: String,
+}
+
+/// Type of a field, see `type_description_interfaces/FieldType` for the meaning of the ids.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FieldType {
+    /// Id of the element type, offset by the kind of array the field is.
+    pub type_id: u8,
+    /// Length of fixed arrays and bound of bounded sequences, zero otherwise.
+    pub capacity: u64,
+    /// Bound of bounded strings, zero otherwise.
+    pub string_capacity: u64,
+    /// Fully qualified name of nested types, empty for primitive types.
+    pub nested_type_name: String,
+}
+
+const FIELD_TYPE_NESTED_TYPE: u8 = 1;
+const FIELD_TYPE_INT8: u8 = 2;
+const FIELD_TYPE_UINT8: u8 = 3;
+const FIELD_TYPE_INT16: u8 = 4;
+const FIELD_TYPE_UINT16: u8 = 5;
+const FIELD_TYPE_INT32: u8 = 6;
+const FIELD_TYPE_UINT32: u8 = 7;
+const FIELD_TYPE_INT64: u8 = 8;
+const FIELD_TYPE_UINT64: u8 = 9;
+const FIELD_TYPE_FLOAT: u8 = 10;
+const FIELD_TYPE_DOUBLE: u8 = 11;
+const FIELD_TYPE_WCHAR: u8 = 14;
+const FIELD_TYPE_BOOLEAN: u8 = 15;
+const FIELD_TYPE_BYTE: u8 = 16;
+const FIELD_TYPE_STRING: u8 = 17;
+const FIELD_TYPE_WSTRING: u8 = 18;
+const FIELD_TYPE_BOUNDED_STRING: u8 = 21;
+const FIELD_TYPE_BOUNDED_WSTRING: u8 = 22;
+
+const FIELD_TYPE_ARRAY_OFFSET: u8 = 48;
+const FIELD_TYPE_BOUNDED_SEQUENCE_OFFSET: u8 = 96;
+const FIELD_TYPE_UNBOUNDED_SEQUENCE_OFFSET: u8 = 144;
+
+/// Member that the ROS 2 IDL adds to messages without fields, since empty structures are not
+/// allowed.
+const EMPTY_STRUCTURE_MEMBER: &str = "structure_needs_at_least_one_member";
+
+impl TypeDescription {
+    /// Returns the representation of the description that is hashed, which is compact JSON
+    /// without the default values of fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::MessageRegistry;
+    /// # type Msg = ros2_message::Msg<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let message = Msg::new("std_msgs/String".try_into()?, "string data")?;
+    /// let description = message.type_description(&MessageRegistry::new())?;
+    ///
+    /// assert_eq!(
+    ///     description.hashable_representation(),
+    ///     r#"{"type_description": {"type_name": "std_msgs/msg/String", "fields": [{"name": "data", "type": {"type_id": 17, "capacity": 0, "string_capacity": 0, "nested_type_name": ""}}]}, "referenced_type_descriptions": []}"#,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hashable_representation(&self) -> String {
+        let mut json = String::from("{\"type_description\": ");
+        self.type_description.write_hashable(&mut json);
+        json.push_str(", \"referenced_type_descriptions\": [");
+        for (i, description) in self.referenced_type_descriptions.iter().enumerate() {
+            if i > 0 {
+                json.push_str(", ");
+            }
+            description.write_hashable(&mut json);
+        }
+        json.push_str("]}");
+        json
+    }
+
+    /// Returns the `RIHS01_` prefixed SHA-256 type hash of the description.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::MessageRegistry;
+    /// # type Msg = ros2_message::Msg<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let message = Msg::new("std_msgs/String".try_into()?, "string data")?;
+    ///
+    /// assert_eq!(
+    ///     message.type_description(&MessageRegistry::new())?.type_hash(),
+    ///     "RIHS01_df668c740482bbd48fb39d76a70dfd4bd59db1288021743503259e948f6b1a18",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn type_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.hashable_representation());
+        format!("{}{}", RIHS01_PREFIX, hex::encode(hasher.finalize()))
+    }
+}
+
+impl IndividualTypeDescription {
+    // Names are ROS identifiers, so nothing needs to be escaped
+    fn write_hashable(&self, json: &mut String) {
+        write!(
+            json,
+            "{{\"type_name\": \"{}\", \"fields\": [",
+            self.type_name
+        )
+        .unwrap();
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                json.push_str(", ");
+            }
+            let field_type = &field.field_type;
+            write!(
+                json,
+                "{{\"name\": \"{}\", \"type\": {{\"type_id\": {}, \"capacity\": {}, \"string_capacity\": {}, \"nested_type_name\": \"{}\"}}}}",
+                field.name,
+                field_type.type_id,
+                field_type.capacity,
+                field_type.string_capacity,
+                field_type.nested_type_name,
+            )
+            .unwrap();
+        }
+        json.push_str("]}");
+    }
+
+    fn nested(type_name: String, fields: &[(&str, u8, u64, String)]) -> Self {
+        Self {
+            type_name,
+            fields: fields
+                .iter()
+                .map(
+                    |(name, type_id, capacity, nested_type_name)| FieldDescription {
+                        name: (*name).into(),
+                        field_type: FieldType {
+                            type_id: *type_id,
+                            capacity: *capacity,
+                            string_capacity: 0,
+                            nested_type_name: nested_type_name.clone(),
+                        },
+                        default_value: String::new(),
+                    },
+                )
+                .collect(),
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Msg<S> {
+    /// Returns the type description of this message, including all messages it references.
+    ///
+    /// Dependencies are looked up in the passed in registry.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if some dependency is missing in the registry, or if the
+    /// dependencies form a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::MessageRegistry;
+    /// # type Msg = ros2_message::Msg<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let message = Msg::new("foo/Stamped".try_into()?, "builtin_interfaces/Time stamp\nint32[<=3] values [1]")?;
+    /// let description = message.type_description(&MessageRegistry::new())?;
+    ///
+    /// assert_eq!(description.type_description.type_name, "foo/msg/Stamped");
+    /// assert_eq!(description.type_description.fields[1].field_type.type_id, 102);
+    /// assert_eq!(description.type_description.fields[1].field_type.capacity, 3);
+    /// assert_eq!(description.type_description.fields[1].default_value, "[1]");
+    /// assert_eq!(description.referenced_type_descriptions.len(), 1);
+    /// assert_eq!(
+    ///     description.referenced_type_descriptions[0].type_name,
+    ///     "builtin_interfaces/msg/Time",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn type_description(&self, registry: &MessageRegistry<S>) -> Result<TypeDescription> {
+        let mut referenced = vec![];
+        collect_referenced(self, registry, &mut referenced)?;
+        Ok(TypeDescription {
+            type_description: describe(msg_type_name(self.path()), self),
+            referenced_type_descriptions: sorted(referenced),
+        })
+    }
+
+    /// Returns the `RIHS01_` prefixed type hash of this message, see [Self::type_description()].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if some dependency is missing in the registry, or if the
+    /// dependencies form a cycle.
+    pub fn type_hash(&self, registry: &MessageRegistry<S>) -> Result<String> {
+        Ok(self.type_description(registry)?.type_hash())
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Srv<S> {
+    /// Returns the type description of this service, including its request, response and event
+    /// messages and all messages they reference.
+    ///
+    /// Dependencies are looked up in the passed in registry. The `service_msgs/ServiceEventInfo`
+    /// message of service events is taken from the registry if present.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if some dependency is missing in the registry, or if the
+    /// dependencies form a cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ros2_message::MessageRegistry;
+    /// # type Srv = ros2_message::Srv<std::hash::RandomState>;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let service = Srv::new("foo/AddTwoInts".try_into()?, "int64 a\nint64 b\n---\nint64 sum")?;
+    /// let description = service.type_description(&MessageRegistry::new())?;
+    ///
+    /// let fields: Vec<_> = description
+    ///     .type_description
+    ///     .fields
+    ///     .iter()
+    ///     .map(|field| field.field_type.nested_type_name.as_str())
+    ///     .collect();
+    /// assert_eq!(
+    ///     fields,
+    ///     ["foo/srv/AddTwoInts_Request", "foo/srv/AddTwoInts_Response", "foo/srv/AddTwoInts_Event"],
+    /// );
+    /// assert_eq!(description.referenced_type_descriptions.len(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn type_description(&self, registry: &MessageRegistry<S>) -> Result<TypeDescription> {
+        let srv_type_name = |suffix: &str| {
+            format!(
+                "{}/srv/{}{}",
+                self.path().package(),
+                self.path().name(),
+                suffix
+            )
+        };
+        let request_name = srv_type_name("_Request");
+        let response_name = srv_type_name("_Response");
+        let event_name = srv_type_name("_Event");
+
+        let builtin_event_info;
+        let event_info_path =
+            MessagePath::try_from(SERVICE_EVENT_INFO.0).expect("Invalid builtin message path");
+        let event_info = match registry.msg(&event_info_path) {
+            Some(msg) => msg,
+            None => {
+                builtin_event_info = Msg::new(event_info_path, SERVICE_EVENT_INFO.1)
+                    .expect("Invalid builtin message definition");
+                &builtin_event_info
+            }
+        };
+
+        let mut referenced = vec![
+            describe(request_name.clone(), self.request()),
+            describe(response_name.clone(), self.response()),
+            IndividualTypeDescription::nested(
+                event_name.clone(),
+                &[
+                    (
+                        "info",
+                        FIELD_TYPE_NESTED_TYPE,
+                        0,
+                        msg_type_name(event_info.path()),
+                    ),
+                    (
+                        "request",
+                        FIELD_TYPE_NESTED_TYPE + FIELD_TYPE_BOUNDED_SEQUENCE_OFFSET,
+                        1,
+                        request_name.clone(),
+                    ),
+                    (
+                        "response",
+                        FIELD_TYPE_NESTED_TYPE + FIELD_TYPE_BOUNDED_SEQUENCE_OFFSET,
+                        1,
+                        response_name.clone(),
+                    ),
+                ],
+            ),
+            describe(msg_type_name(event_info.path()), event_info),
+        ];
+        collect_referenced(self.request(), registry, &mut referenced)?;
+        collect_referenced(self.response(), registry, &mut referenced)?;
+        collect_referenced(event_info, registry, &mut referenced)?;
+
+        Ok(TypeDescription {
+            type_description: IndividualTypeDescription::nested(
+                srv_type_name(""),
+                &[
+                    ("request_message", FIELD_TYPE_NESTED_TYPE, 0, request_name),
+                    ("response_message", FIELD_TYPE_NESTED_TYPE, 0, response_name),
+                    ("event_message", FIELD_TYPE_NESTED_TYPE, 0, event_name),
+                ],
+            ),
+            referenced_type_descriptions: sorted(referenced),
+        })
+    }
+
+    /// Returns the `RIHS01_` prefixed type hash of this service, see [Self::type_description()].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if some dependency is missing in the registry, or if the
+    /// dependencies form a cycle.
+    pub fn type_hash(&self, registry: &MessageRegistry<S>) -> Result<String> {
+        Ok(self.type_description(registry)?.type_hash())
+    }
+}
+
+fn msg_type_name(path: &MessagePath) -> String {
+    format!("{}/msg/{}", path.package(), path.name())
+}
+
+/// Adds the descriptions of all messages that `msg` depends upon to `referenced`.
+///
+/// The ROS1 `time` and `duration` types are described as their `builtin_interfaces` counterparts.
+fn collect_referenced<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    msg: &Msg<S>,
+    registry: &MessageRegistry<S>,
+    referenced: &mut Vec<IndividualTypeDescription>,
+) -> Result<()> {
+    let dependencies = registry.resolve(msg)?;
+    let builtin_time = std::iter::once(msg)
+        .chain(dependencies.iter().copied())
+        .flat_map(Msg::fields)
+        .filter_map(|field| match field.datatype() {
+            DataType::Time => Some(MessagePath::new("builtin_interfaces", "Time")),
+            DataType::Duration => Some(MessagePath::new("builtin_interfaces", "Duration")),
+            _ => None,
+        })
+        .map(|path| {
+            let path = path?;
+            registry
+                .msg(&path)
+                .ok_or_else(|| Error::MessageDependencyMissing {
+                    package: path.package().into(),
+                    name: path.name().into(),
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for dependency in dependencies.into_iter().chain(builtin_time) {
+        let type_name = msg_type_name(dependency.path());
+        if !referenced.iter().any(|d| d.type_name == type_name) {
+            referenced.push(describe(type_name, dependency));
+        }
+    }
+
+    Ok(())
+}
+
+fn sorted(mut descriptions: Vec<IndividualTypeDescription>) -> Vec<IndividualTypeDescription> {
+    descriptions.sort_by(|a, b| a.type_name.cmp(&b.type_name));
+    descriptions.dedup_by(|a, b| a.type_name == b.type_name);
+    descriptions
+}
+
+fn describe<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    type_name: String,
+    msg: &Msg<S>,
+) -> IndividualTypeDescription {
+    let mut fields: Vec<_> = msg
+        .fields()
+        .iter()
+        .filter(|field| !field.is_constant())
+        .map(|field| FieldDescription {
+            name: field.name().into(),
+            field_type: field_type(field, msg.path()),
+            default_value: field.default_literal().unwrap_or_default().into(),
+        })
+        .collect();
+    if fields.is_empty() {
+        fields.push(FieldDescription {
+            name: EMPTY_STRUCTURE_MEMBER.into(),
+            field_type: FieldType {
+                type_id: FIELD_TYPE_UINT8,
+                capacity: 0,
+                string_capacity: 0,
+                nested_type_name: String::new(),
+            },
+            default_value: String::new(),
+        });
+    }
+
+    IndividualTypeDescription { type_name, fields }
+}
+
+fn field_type<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    field: &FieldInfo<S>,
+    path: &MessagePath,
+) -> FieldType {
+    let mut string_capacity = 0;
+    let mut nested_type_name = String::new();
+    let type_id = match field.datatype() {
+        DataType::Bool => FIELD_TYPE_BOOLEAN,
+        DataType::I8(I8Variant::Int8) => FIELD_TYPE_INT8,
+        DataType::I8(I8Variant::Byte) => FIELD_TYPE_BYTE,
+        DataType::I16 => FIELD_TYPE_INT16,
+        DataType::I32 => FIELD_TYPE_INT32,
+        DataType::I64 => FIELD_TYPE_INT64,
+        // `char` is an alias of `uint8` in ROS 2
+        DataType::U8(U8Variant::Uint8 | U8Variant::Char) => FIELD_TYPE_UINT8,
+        DataType::U16 => FIELD_TYPE_UINT16,
+        DataType::U32 => FIELD_TYPE_UINT32,
+        DataType::U64 => FIELD_TYPE_UINT64,
+        DataType::F32 => FIELD_TYPE_FLOAT,
+        DataType::F64 => FIELD_TYPE_DOUBLE,
+        DataType::String => FIELD_TYPE_STRING,
+        DataType::BoundedString(bound) => {
+            string_capacity = *bound as u64;
+            FIELD_TYPE_BOUNDED_STRING
+        }
+        DataType::WString => FIELD_TYPE_WSTRING,
+        DataType::BoundedWString(bound) => {
+            string_capacity = *bound as u64;
+            FIELD_TYPE_BOUNDED_WSTRING
+        }
+        DataType::WChar => FIELD_TYPE_WCHAR,
+        DataType::Time => {
+            nested_type_name = "builtin_interfaces/msg/Time".into();
+            FIELD_TYPE_NESTED_TYPE
+        }
+        DataType::Duration => {
+            nested_type_name = "builtin_interfaces/msg/Duration".into();
+            FIELD_TYPE_NESTED_TYPE
+        }
+        DataType::LocalMessage(name) => {
+            nested_type_name = msg_type_name(&path.peer(name));
+            FIELD_TYPE_NESTED_TYPE
+        }
+        DataType::GlobalMessage(message) => {
+            nested_type_name = msg_type_name(message);
+            FIELD_TYPE_NESTED_TYPE
+        }
+    };
+    let (offset, capacity) = match field.case() {
+        FieldCase::Array(length) => (FIELD_TYPE_ARRAY_OFFSET, *length as u64),
+        FieldCase::BoundedVector(bound) => (FIELD_TYPE_BOUNDED_SEQUENCE_OFFSET, *bound as u64),
+        FieldCase::Vector => (FIELD_TYPE_UNBOUNDED_SEQUENCE_OFFSET, 0),
+        FieldCase::Unit | FieldCase::Const(_) | FieldCase::Default(_) => (0, 0),
+    };
+
+    FieldType {
+        type_id: type_id + offset,
+        capacity,
+        string_capacity,
+        nested_type_name,
+    }
+}
@@ -1,7 +1,6 @@
 use crate::{Duration, Time};
-use itertools::Itertools;
+use indexmap::IndexMap;
 use serde_derive::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::fmt::{Display, Formatter};
@@ -10,12 +9,32 @@ use std::iter::FromIterator;
 
 use derive_where::derive_where;
 
-/// Represents an arbitrary ROS message.
-pub type MessageValue<S> = HashMap<String, Value<S>, S>; //  = RandomState
+/// Represents an arbitrary ROS message, its fields keep the order in which they were inserted,
+/// which is the order of the message definition for decoded messages.
+pub type MessageValue<S> = IndexMap<String, Value<S>, S>; //  = RandomState
 
 /// Represents an arbitrary ROS message or value in it.
+///
+/// Values are equal if they have the same content, so a [Value::PrimitiveArray] equals a
+/// [Value::Array] of the same elements. This makes messages decoded with and without
+/// [DecodeOptions::primitive_arrays](crate::dynamic::DecodeOptions::primitive_arrays) equal.
+///
+/// # Examples
+///
+/// ```
+/// # type Value = ros2_message::Value<std::hash::RandomState>;
+/// # use ros2_message::PrimitiveArray;
+/// assert_eq!(
+///     Value::PrimitiveArray(PrimitiveArray::U8(vec![1, 2])),
+///     Value::Array(vec![Value::U8(1), Value::U8(2)]),
+/// );
+/// assert_ne!(
+///     Value::PrimitiveArray(PrimitiveArray::U8(vec![1, 2])),
+///     Value::Array(vec![Value::I8(1), Value::I8(2)]),
+/// );
+/// ```
 #[derive(Serialize, Deserialize)]
-#[derive_where(Clone, PartialEq, Debug)]
+#[derive_where(Clone, Debug)]
 pub enum Value<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState> {
     //  = RandomState
     /// Represents `bool`.
@@ -51,9 +70,45 @@ pub enum Value<S: BuildHasher + Default + Clone + core::fmt::Debug = RandomState
     /// For example: `float32[64]`, `geometry_msgs/Point[]`.
     #[serde(bound(deserialize = "", serialize = ""))]
     Array(Vec<Value<S>>),
+    /// Represents an array or sequence of a primitive type that is stored as a typed vector.
+    ///
+    /// For example: `uint8[]`, `float32[3]`.
+    PrimitiveArray(PrimitiveArray),
     /// Represents an embedded message.
     #[serde(bound(deserialize = "", serialize = ""))]
-    Message(HashMap<String, Value<S>, S>),
+    Message(MessageValue<S>),
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> PartialEq for Value<S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::I8(a), Value::I8(b)) => a == b,
+            (Value::I16(a), Value::I16(b)) => a == b,
+            (Value::I32(a), Value::I32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::U8(a), Value::U8(b)) => a == b,
+            (Value::U16(a), Value::U16(b)) => a == b,
+            (Value::U32(a), Value::U32(b)) => a == b,
+            (Value::U64(a), Value::U64(b)) => a == b,
+            (Value::F32(a), Value::F32(b)) => a == b,
+            (Value::F64(a), Value::F64(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Time(a), Value::Time(b)) => a == b,
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::PrimitiveArray(a), Value::PrimitiveArray(b)) => a == b,
+            (Value::Array(a), Value::PrimitiveArray(b))
+            | (Value::PrimitiveArray(b), Value::Array(a)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .enumerate()
+                        .all(|(index, a)| b.get::<S>(index).is_some_and(|b| *a == b))
+            }
+            (Value::Message(a), Value::Message(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
 impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
@@ -81,8 +136,16 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
                 }
                 Ok(())
             }
+            Value::PrimitiveArray(items) => {
+                for item in items.to_values::<S>() {
+                    writeln!(f)?;
+                    write!(f, "{:indent$}- ", "", indent = indentation)?;
+                    item.fmt_indented(indentation + step, step, f)?;
+                }
+                Ok(())
+            }
             Value::Message(items) => {
-                for (key, item) in items.iter().sorted_by(|a, b| Ord::cmp(&a.0, &b.0)) {
+                for (key, item) in items {
                     writeln!(f)?;
                     write!(f, "{:indent$}{}: ", "", key, indent = indentation)?;
                     item.fmt_indented(indentation + step, step, f)?;
@@ -358,15 +421,20 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
 
     /// Returns a reference to the content if `Value` is an array.
     ///
+    /// This is `None` for a [Value::PrimitiveArray], which has no [Value]s to borrow. Use
+    /// [Self::as_primitive_array()] or [Self::try_into_vec()] for those.
+    ///
     /// # Examples
     ///
     /// ```
     /// # type Value = ros2_message::Value<std::hash::RandomState>;
+    /// # use ros2_message::PrimitiveArray;
     /// assert_eq!(
     ///     Value::Array(vec![1u32.into(), 2u32.into(), 3u32.into()]).as_slice(),
     ///     Some(&[Value::U32(1), Value::U32(2), Value::U32(3)][..]),
     /// );
     /// assert!(Value::U32(12).as_slice().is_none());
+    /// assert!(Value::PrimitiveArray(PrimitiveArray::U32(vec![1])).as_slice().is_none());
     /// ```
     pub fn as_slice(&self) -> Option<&[Value<S>]> {
         if let Value::Array(value) = self {
@@ -389,7 +457,136 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
     /// assert!(Value::U32(12).try_into_vec().is_none());
     /// ```
     pub fn try_into_vec(self) -> Option<Vec<Value<S>>> {
-        if let Value::Array(value) = self {
+        match self {
+            Value::Array(value) => Some(value),
+            Value::PrimitiveArray(value) => Some(value.to_values()),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the content if `Value` is a primitive array.
+    pub fn as_primitive_array(&self) -> Option<&PrimitiveArray> {
+        if let Value::PrimitiveArray(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `bool`.
+    pub fn as_bool_slice(&self) -> Option<&[bool]> {
+        if let Value::PrimitiveArray(PrimitiveArray::Bool(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `i8`.
+    pub fn as_i8_slice(&self) -> Option<&[i8]> {
+        if let Value::PrimitiveArray(PrimitiveArray::I8(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `i16`.
+    pub fn as_i16_slice(&self) -> Option<&[i16]> {
+        if let Value::PrimitiveArray(PrimitiveArray::I16(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `i32`.
+    pub fn as_i32_slice(&self) -> Option<&[i32]> {
+        if let Value::PrimitiveArray(PrimitiveArray::I32(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `i64`.
+    pub fn as_i64_slice(&self) -> Option<&[i64]> {
+        if let Value::PrimitiveArray(PrimitiveArray::I64(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `u8`, like the `data` of a
+    /// `sensor_msgs/Image`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Value = ros2_message::Value<std::hash::RandomState>;
+    /// # use ros2_message::PrimitiveArray;
+    /// let value = Value::PrimitiveArray(PrimitiveArray::U8(vec![1, 2, 3]));
+    /// assert_eq!(value.as_bytes(), Some(&[1u8, 2, 3][..]));
+    /// assert!(Value::Array(vec![Value::U8(1)]).as_bytes().is_none());
+    /// ```
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Value::PrimitiveArray(PrimitiveArray::U8(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `u16`.
+    pub fn as_u16_slice(&self) -> Option<&[u16]> {
+        if let Value::PrimitiveArray(PrimitiveArray::U16(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `u32`.
+    pub fn as_u32_slice(&self) -> Option<&[u32]> {
+        if let Value::PrimitiveArray(PrimitiveArray::U32(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `u64`.
+    pub fn as_u64_slice(&self) -> Option<&[u64]> {
+        if let Value::PrimitiveArray(PrimitiveArray::U64(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `f32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Value = ros2_message::Value<std::hash::RandomState>;
+    /// # use ros2_message::PrimitiveArray;
+    /// let value = Value::PrimitiveArray(PrimitiveArray::F32(vec![0.5, 1.5]));
+    /// assert_eq!(value.as_f32_slice(), Some(&[0.5f32, 1.5][..]));
+    /// assert!(value.as_f64_slice().is_none());
+    /// ```
+    pub fn as_f32_slice(&self) -> Option<&[f32]> {
+        if let Value::PrimitiveArray(PrimitiveArray::F32(value)) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the content if `Value` is a primitive array of `f64`.
+    pub fn as_f64_slice(&self) -> Option<&[f64]> {
+        if let Value::PrimitiveArray(PrimitiveArray::F64(value)) = self {
             Some(value)
         } else {
             None
@@ -402,8 +599,8 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
     ///
     /// ```
     /// # type Value = ros2_message::Value<std::hash::RandomState>;
-    /// # use std::collections::HashMap;
-    /// let mut data = HashMap::<String, Value>::new();
+    /// # use ros2_message::MessageValue;
+    /// let mut data = MessageValue::new();
     /// data.insert("foo".into(), true.into());
     /// data.insert("bar".into(), false.into());
     /// assert_eq!(Value::Message(data.clone()).as_map(), Some(&data));
@@ -423,8 +620,8 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
     ///
     /// ```
     /// # type Value = ros2_message::Value<std::hash::RandomState>;
-    /// # use std::collections::HashMap;
-    /// let mut data = HashMap::<String, Value>::new();
+    /// # use ros2_message::MessageValue;
+    /// let mut data = MessageValue::new();
     /// data.insert("foo".into(), Value::I32(12));
     /// data.insert("bar".into(), Value::Bool(true));
     /// assert_eq!(Value::Message(data.clone()).try_into_map(), Some(data));
@@ -438,32 +635,154 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
         }
     }
 
-    pub(crate) fn to_random_state(self) -> Value<RandomState> {
+    /// Returns the `sec` and `nanosec` fields of the `builtin_interfaces/Time` or
+    /// `builtin_interfaces/Duration` message that a [Value::Time] or [Value::Duration] stands in
+    /// for. Values out of the range of the message fields keep their own type instead of
+    /// wrapping around, so they do not match the message definition.
+    pub(crate) fn builtin_time_fields(&self) -> Option<(Value<S>, Value<S>)> {
         match self {
-            Value::Bool(v) => Value::Bool(v),
-            Value::I8(v) => Value::I8(v),
-            Value::I16(v) => Value::I16(v),
-            Value::I32(v) => Value::I32(v),
-            Value::I64(v) => Value::I64(v),
-            Value::U8(v) => Value::U8(v),
-            Value::U16(v) => Value::U16(v),
-            Value::U32(v) => Value::U32(v),
-            Value::U64(v) => Value::U64(v),
-            Value::F32(v) => Value::F32(v),
-            Value::F64(v) => Value::F64(v),
-            Value::String(v) => Value::String(v),
-            Value::Time(v) => Value::Time(v),
-            Value::Duration(v) => Value::Duration(v),
-            Value::Array(v) => Value::Array(v.into_iter().map(Self::to_random_state).collect()),
-            Value::Message(v) => Value::Message(
-                v.into_iter()
-                    .map(|(k, v)| (k, v.to_random_state()))
-                    .collect(),
-            ),
+            Value::Time(t) => Some((
+                i32::try_from(t.sec).map_or(Value::U32(t.sec), Value::I32),
+                Value::U32(t.nsec),
+            )),
+            Value::Duration(d) => Some((
+                Value::I32(d.sec),
+                u32::try_from(d.nsec).map_or(Value::I32(d.nsec), Value::U32),
+            )),
+            _ => None,
         }
     }
 }
 
+/// An array or sequence of a primitive type, stored as a vector of that type.
+///
+/// Decoding large arrays like the `data` of a `sensor_msgs/Image` this way avoids creating a
+/// [Value] per element, see
+/// [DecodeOptions::primitive_arrays](crate::dynamic::DecodeOptions::primitive_arrays).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum PrimitiveArray {
+    /// Represents `bool[]`.
+    Bool(Vec<bool>),
+    /// Represents `int8[]` or `byte[]`.
+    I8(Vec<i8>),
+    /// Represents `int16[]`.
+    I16(Vec<i16>),
+    /// Represents `int32[]`.
+    I32(Vec<i32>),
+    /// Represents `int64[]`.
+    I64(Vec<i64>),
+    /// Represents `uint8[]` or `char[]`.
+    U8(Vec<u8>),
+    /// Represents `uint16[]` or `wchar[]`.
+    U16(Vec<u16>),
+    /// Represents `uint32[]`.
+    U32(Vec<u32>),
+    /// Represents `uint64[]`.
+    U64(Vec<u64>),
+    /// Represents `float32[]`.
+    F32(Vec<f32>),
+    /// Represents `float64[]`.
+    F64(Vec<f64>),
+}
+
+impl PrimitiveArray {
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        match self {
+            PrimitiveArray::Bool(v) => v.len(),
+            PrimitiveArray::I8(v) => v.len(),
+            PrimitiveArray::I16(v) => v.len(),
+            PrimitiveArray::I32(v) => v.len(),
+            PrimitiveArray::I64(v) => v.len(),
+            PrimitiveArray::U8(v) => v.len(),
+            PrimitiveArray::U16(v) => v.len(),
+            PrimitiveArray::U32(v) => v.len(),
+            PrimitiveArray::U64(v) => v.len(),
+            PrimitiveArray::F32(v) => v.len(),
+            PrimitiveArray::F64(v) => v.len(),
+        }
+    }
+
+    /// Returns `true` if the array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at `index` as an individual value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Value = ros2_message::Value<std::hash::RandomState>;
+    /// # use ros2_message::PrimitiveArray;
+    /// let array = PrimitiveArray::F32(vec![0.5, 1.5]);
+    /// assert_eq!(array.get(1), Some(Value::F32(1.5)));
+    /// assert_eq!(array.get::<std::hash::RandomState>(2), None);
+    /// ```
+    pub fn get<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &self,
+        index: usize,
+    ) -> Option<Value<S>> {
+        match self {
+            PrimitiveArray::Bool(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::I8(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::I16(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::I32(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::I64(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::U8(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::U16(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::U32(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::U64(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::F32(v) => v.get(index).map(|v| (*v).into()),
+            PrimitiveArray::F64(v) => v.get(index).map(|v| (*v).into()),
+        }
+    }
+
+    /// Converts the elements to individual values, like they are decoded without
+    /// [DecodeOptions::primitive_arrays](crate::dynamic::DecodeOptions::primitive_arrays).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Value = ros2_message::Value<std::hash::RandomState>;
+    /// # use ros2_message::PrimitiveArray;
+    /// assert_eq!(
+    ///     PrimitiveArray::I16(vec![-1, 2]).to_values(),
+    ///     vec![Value::I16(-1), Value::I16(2)],
+    /// );
+    /// ```
+    pub fn to_values<S: BuildHasher + Default + Clone + core::fmt::Debug>(&self) -> Vec<Value<S>> {
+        fn convert<
+            S: BuildHasher + Default + Clone + core::fmt::Debug,
+            T: Copy + Into<Value<S>>,
+        >(
+            values: &[T],
+        ) -> Vec<Value<S>> {
+            values.iter().map(|v| (*v).into()).collect()
+        }
+
+        match self {
+            PrimitiveArray::Bool(v) => convert(v),
+            PrimitiveArray::I8(v) => convert(v),
+            PrimitiveArray::I16(v) => convert(v),
+            PrimitiveArray::I32(v) => convert(v),
+            PrimitiveArray::I64(v) => convert(v),
+            PrimitiveArray::U8(v) => convert(v),
+            PrimitiveArray::U16(v) => convert(v),
+            PrimitiveArray::U32(v) => convert(v),
+            PrimitiveArray::U64(v) => convert(v),
+            PrimitiveArray::F32(v) => convert(v),
+            PrimitiveArray::F64(v) => convert(v),
+        }
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<PrimitiveArray> for Value<S> {
+    fn from(v: PrimitiveArray) -> Self {
+        Self::PrimitiveArray(v)
+    }
+}
+
 impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Display for Value<S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.fmt_indented(0, 2, f)
@@ -570,10 +889,8 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug, T: Into<Value<S>>, con
     }
 }
 
-impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<HashMap<String, Value<S>, S>>
-    for Value<S>
-{
-    fn from(v: HashMap<String, Value<S>, S>) -> Self {
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> From<MessageValue<S>> for Value<S> {
+    fn from(v: MessageValue<S>) -> Self {
         Self::Message(v)
     }
 }
@@ -722,9 +1039,7 @@ impl<S: BuildHasher + Default + Clone + core::fmt::Debug, T: TryFrom<Value<S>>,
     }
 }
 
-impl<S: BuildHasher + Default + Clone + core::fmt::Debug> TryFrom<Value<S>>
-    for HashMap<String, Value<S>, S>
-{
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> TryFrom<Value<S>> for MessageValue<S> {
     type Error = ();
 
     fn try_from(value: Value<S>) -> Result<Self, Self::Error> {
@@ -0,0 +1,347 @@
+use crate::{Error, MessageValue, Result, Value};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::hash::BuildHasher;
+
+/// A path selecting values inside a decoded message, like `pose.position.x`.
+///
+/// Fields of messages are separated by dots and elements of arrays are selected in brackets:
+///
+/// * `points[3]` selects the fourth element and `points[-1]` the last one
+/// * `points[1:3]` selects the elements at index 1 and 2, both bounds are optional and may be
+///   negative like `points[-2:]`
+/// * `points[*]` selects all elements and `*` all fields of a message
+///
+/// Paths with wildcards or slices may match multiple values, see [Self::query()].
+/// The `sec` and `nanosec` fields of [Value::Time] and [Value::Duration] can be selected just
+/// like the fields of the messages they are decoded from.
+///
+/// # Examples
+///
+/// ```
+/// # type Value = ros2_message::Value<std::hash::RandomState>;
+/// # use ros2_message::ValuePath;
+/// # use std::convert::TryFrom;
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let point = |x: f64| Value::from_iter([("x", x)]);
+/// let message = Value::from_iter([("points", Value::Array(vec![point(1.0), point(2.0), point(3.0)]))]);
+///
+/// let path = ValuePath::try_from("points[-1].x")?;
+/// assert_eq!(path.get(&message).as_deref(), Some(&Value::F64(3.0)));
+///
+/// let path = ValuePath::try_from("points[:2].x")?;
+/// assert_eq!(path.query(&message).len(), 2);
+///
+/// let err = ValuePath::try_from("points[1.x").unwrap_err();
+/// assert_eq!(err.to_string(), "field path `points[1.x` is invalid, expected `]` at byte 8 in segment `[1`");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ValuePath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Segment {
+    Field(String),
+    AnyField,
+    Index(isize),
+    Slice(Option<isize>, Option<isize>),
+    AnyIndex,
+}
+
+impl ValuePath {
+    /// Returns the first value matched by the path.
+    ///
+    /// Values are borrowed, except for elements of a [Value::PrimitiveArray] and the fields of
+    /// [Value::Time] and [Value::Duration], which do not exist as a [Value] beforehand.
+    pub fn get<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &self,
+        value: &'a Value<S>,
+    ) -> Option<Cow<'a, Value<S>>> {
+        self.query(value).into_iter().next()
+    }
+
+    /// Returns all values matched by the path, in the order of the array elements and message
+    /// fields.
+    pub fn query<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &self,
+        value: &'a Value<S>,
+    ) -> Vec<Cow<'a, Value<S>>> {
+        let mut matches = vec![Cow::Borrowed(value)];
+        for segment in &self.segments {
+            matches = matches
+                .into_iter()
+                .flat_map(|value| segment.select(value))
+                .collect();
+        }
+        matches
+    }
+
+    /// Same as [Self::get()], but starts at the fields of a message like the ones returned by
+    /// [DynamicMsg::decode()](crate::dynamic::DynamicMsg::decode()).
+    pub fn get_in<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &self,
+        message: &'a MessageValue<S>,
+    ) -> Option<Cow<'a, Value<S>>> {
+        self.query_in(message).into_iter().next()
+    }
+
+    /// Same as [Self::query()], but starts at the fields of a message like the ones returned by
+    /// [DynamicMsg::decode()](crate::dynamic::DynamicMsg::decode()).
+    pub fn query_in<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &self,
+        message: &'a MessageValue<S>,
+    ) -> Vec<Cow<'a, Value<S>>> {
+        let Some((first, rest)) = self.segments.split_first() else {
+            return vec![];
+        };
+        let mut matches: Vec<Cow<'a, Value<S>>> = match first {
+            Segment::Field(name) => message.get(name).map(Cow::Borrowed).into_iter().collect(),
+            Segment::AnyField => message.values().map(Cow::Borrowed).collect(),
+            _ => vec![],
+        };
+        for segment in rest {
+            matches = matches
+                .into_iter()
+                .flat_map(|value| segment.select(value))
+                .collect();
+        }
+        matches
+    }
+
+    fn parse(path: &str) -> Result<Self> {
+        let invalid = |start: usize, end: usize, reason: &str| Error::InvalidFieldPath {
+            path: path.to_owned(),
+            reason: format!("{reason} in segment `{}`", &path[start..end]),
+        };
+
+        let mut segments = vec![];
+        let bytes = path.as_bytes();
+        let mut pos = 0;
+        while pos < bytes.len() || segments.is_empty() {
+            let start = pos;
+            if bytes.get(pos) == Some(&b'[') {
+                let Some(len) = path[pos..].find(']') else {
+                    let end = path[pos..].find('.').map_or(path.len(), |i| pos + i);
+                    return Err(invalid(start, end, &format!("expected `]` at byte {end}")));
+                };
+                let end = pos + len + 1;
+                let selector = &path[pos + 1..end - 1];
+                let index = |s: &str| -> Result<Option<isize>> {
+                    match s.trim() {
+                        "" => Ok(None),
+                        s => s.parse().map(Some).map_err(|_| {
+                            invalid(start, end, &format!("`{s}` is not a valid index"))
+                        }),
+                    }
+                };
+                let segment = match selector.trim() {
+                    "*" => Segment::AnyIndex,
+                    s => match s.split_once(':') {
+                        Some((from, to)) => Segment::Slice(index(from)?, index(to)?),
+                        None => match index(s)? {
+                            Some(i) => Segment::Index(i),
+                            None => return Err(invalid(start, end, "expected an index")),
+                        },
+                    },
+                };
+                segments.push(segment);
+                pos = end;
+            } else {
+                // Fields follow a dot, except for the first one
+                if !segments.is_empty() {
+                    if bytes.get(pos) != Some(&b'.') {
+                        // The segment starts with the unexpected character, which may be
+                        // longer than a byte
+                        let len = path[pos..].chars().next().map_or(0, char::len_utf8);
+                        let end = next_boundary(path, pos + len);
+                        return Err(invalid(
+                            start,
+                            end,
+                            &format!("expected `.` or `[` at byte {pos}"),
+                        ));
+                    }
+                    pos += 1;
+                }
+                let end = next_boundary(path, pos);
+                let name = &path[pos..end];
+                let segment = match name {
+                    "" => {
+                        return Err(invalid(
+                            start,
+                            end,
+                            &format!("expected a field name at byte {pos}"),
+                        ))
+                    }
+                    "*" => Segment::AnyField,
+                    name if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                        Segment::Field(name.to_owned())
+                    }
+                    _ => {
+                        return Err(invalid(
+                            start,
+                            end,
+                            "field names may only contain letters, digits and `_`",
+                        ))
+                    }
+                };
+                segments.push(segment);
+                pos = end;
+            }
+        }
+
+        Ok(ValuePath { segments })
+    }
+}
+
+// Position of the next `.` or `[` from `pos` on
+fn next_boundary(path: &str, pos: usize) -> usize {
+    let pos = pos.min(path.len());
+    path[pos..].find(['.', '[']).map_or(path.len(), |i| pos + i)
+}
+
+impl Segment {
+    fn select<'a, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        &self,
+        value: Cow<'a, Value<S>>,
+    ) -> Vec<Cow<'a, Value<S>>> {
+        match (self, value) {
+            (Segment::Field(name), Cow::Borrowed(Value::Message(fields))) => {
+                fields.get(name).map(Cow::Borrowed).into_iter().collect()
+            }
+            (Segment::AnyField, Cow::Borrowed(Value::Message(fields))) => {
+                fields.values().map(Cow::Borrowed).collect()
+            }
+            (Segment::Field(_) | Segment::AnyField, value) => {
+                // Owned values only come from primitive arrays and have no fields
+                let Some((sec, nanosec)) = value.builtin_time_fields() else {
+                    return vec![];
+                };
+                match self {
+                    Segment::Field(name) if name == "sec" => vec![Cow::Owned(sec)],
+                    Segment::Field(name) if name == "nanosec" => vec![Cow::Owned(nanosec)],
+                    Segment::AnyField => vec![Cow::Owned(sec), Cow::Owned(nanosec)],
+                    _ => vec![],
+                }
+            }
+            (_, Cow::Borrowed(Value::Array(items))) => self
+                .indices(items.len())
+                .map(|i| Cow::Borrowed(&items[i]))
+                .collect(),
+            (_, Cow::Borrowed(Value::PrimitiveArray(items))) => self
+                .indices(items.len())
+                .filter_map(|i| items.get(i).map(Cow::Owned))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // Indices of the array elements selected by the segment
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize> {
+        let resolve = |i: isize| {
+            if i < 0 {
+                len.checked_sub(i.unsigned_abs())
+            } else {
+                Some(i as usize)
+            }
+        };
+        match self {
+            Segment::Index(i) => match resolve(*i) {
+                Some(i) if i < len => i..i + 1,
+                _ => 0..0,
+            },
+            Segment::Slice(from, to) => {
+                let clamp = |i: Option<isize>, default| match i {
+                    None => default,
+                    Some(i) => resolve(i).unwrap_or(0).min(len),
+                };
+                clamp(*from, 0)..clamp(*to, len)
+            }
+            Segment::AnyIndex => 0..len,
+            Segment::Field(_) | Segment::AnyField => 0..0,
+        }
+    }
+}
+
+impl Display for ValuePath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Field(name) if i == 0 => write!(f, "{name}")?,
+                Segment::Field(name) => write!(f, ".{name}")?,
+                Segment::AnyField if i == 0 => write!(f, "*")?,
+                Segment::AnyField => write!(f, ".*")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Slice(from, to) => {
+                    write!(f, "[")?;
+                    if let Some(from) = from {
+                        write!(f, "{from}")?;
+                    }
+                    write!(f, ":")?;
+                    if let Some(to) = to {
+                        write!(f, "{to}")?;
+                    }
+                    write!(f, "]")?;
+                }
+                Segment::AnyIndex => write!(f, "[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ValuePath {
+    type Error = Error;
+
+    fn try_from(value: &'a str) -> Result<Self> {
+        Self::parse(value)
+    }
+}
+
+impl std::str::FromStr for ValuePath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> Value<S> {
+    /// Returns the first value matched by a path like `pose.position.x`, see [ValuePath] for
+    /// the syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidFieldPath] if the path can not be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # type Value = ros2_message::Value<std::hash::RandomState>;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let position = Value::from_iter([("x", 1.5), ("y", 2.5)]);
+    /// let message = Value::from_iter([("pose", Value::from_iter([("position", position)]))]);
+    ///
+    /// assert_eq!(message.get_path("pose.position.x")?.as_deref(), Some(&Value::F64(1.5)));
+    /// assert!(message.get_path("pose.orientation")?.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_path(&self, path: &str) -> Result<Option<Cow<'_, Value<S>>>> {
+        Ok(ValuePath::parse(path)?.get(self))
+    }
+
+    /// Returns all values matched by a path like `transforms[*].header.frame_id`, see
+    /// [ValuePath] for the syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidFieldPath] if the path can not be parsed.
+    pub fn query(&self, path: &str) -> Result<Vec<Cow<'_, Value<S>>>> {
+        Ok(ValuePath::parse(path)?.query(self))
+    }
+}
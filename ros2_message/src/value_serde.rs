@@ -0,0 +1,654 @@
+use crate::error::{Error, Result};
+use crate::{Duration, MessageValue, PrimitiveArray, Time, Value};
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde::ser::{self, Serialize};
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+
+/// Deserializes a type from a [Value], like the messages returned by
+/// [DynamicMsg::decode()](crate::dynamic::DynamicMsg::decode).
+///
+/// Messages are deserialized as maps from field names to values, arrays as sequences and
+/// [Time] and [Duration] as maps with `sec` and `nsec` entries. Strings deserialize enums with
+/// unit variants and messages with a single field enums with other variants.
+/// `Value` and `&Value` also implement [Deserializer](serde::Deserializer) themselves, the
+/// latter allows borrowing strings from the value.
+///
+/// # Errors
+///
+/// Returns [Error::Deserialization] if the value does not match the type.
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::{from_value, Value};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let value: Value = [("x", Value::F64(1.0)), ("y", Value::F64(2.0))].into_iter().collect();
+/// let point: Point = from_value(value).unwrap();
+///
+/// assert_eq!((point.x, point.y), (1.0, 2.0));
+/// ```
+pub fn from_value<T: DeserializeOwned, S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    value: Value<S>,
+) -> Result<T> {
+    T::deserialize(value)
+}
+
+/// Converts any type implementing [Serialize] into a [Value], which can then be encoded with
+/// [DynamicMsg::encode()](crate::dynamic::DynamicMsg::encode).
+///
+/// Structs and maps with string keys become messages, sequences and tuples arrays, and numbers
+/// the value of their exact type, so the types of the struct have to match the types of the
+/// message definition. Bytes become a [PrimitiveArray::U8] and structs named `Time` or
+/// `Duration` with just the fields `sec` and `nsec` of the right types become [Value::Time] and
+/// [Value::Duration]. Unit variants of enums become strings and other variants messages with a
+/// single field named like the variant.
+///
+/// # Errors
+///
+/// Returns [Error::Serialization] for values without a representation, like `None`, `()`
+/// or maps with keys that are not strings.
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::{to_value, Value};
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Point {
+///     x: f64,
+///     y: f64,
+/// }
+///
+/// let value: Value = to_value(&Point { x: 1.0, y: 2.0 }).unwrap();
+///
+/// assert_eq!(value.get_path("y").unwrap().as_deref(), Some(&Value::F64(2.0)));
+/// ```
+pub fn to_value<S: BuildHasher + Default + Clone + core::fmt::Debug, T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<Value<S>> {
+    value.serialize(ValueSerializer(PhantomData))
+}
+
+fn visit_seq<'de, V, I>(visitor: V, iter: I) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+    I: Iterator,
+    I::Item: IntoDeserializer<'de, Error>,
+{
+    let mut seq = SeqDeserializer::new(iter);
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_map<'de, V, I, K, T>(visitor: V, iter: I) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+    I: Iterator<Item = (K, T)>,
+    K: IntoDeserializer<'de, Error>,
+    T: IntoDeserializer<'de, Error>,
+{
+    let mut map = MapDeserializer::new(iter);
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+macro_rules! visit_primitive_array {
+    ($array:expr, $visitor:expr, $values:ident => $iter:expr) => {
+        match $array {
+            PrimitiveArray::Bool($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::I8($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::I16($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::I32($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::I64($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::U8($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::U16($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::U32($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::U64($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::F32($values) => visit_seq($visitor, $iter),
+            PrimitiveArray::F64($values) => visit_seq($visitor, $iter),
+        }
+    };
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> de::Deserializer<'de> for Value<S> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::I8(v) => visitor.visit_i8(v),
+            Value::I16(v) => visitor.visit_i16(v),
+            Value::I32(v) => visitor.visit_i32(v),
+            Value::I64(v) => visitor.visit_i64(v),
+            Value::U8(v) => visitor.visit_u8(v),
+            Value::U16(v) => visitor.visit_u16(v),
+            Value::U32(v) => visitor.visit_u32(v),
+            Value::U64(v) => visitor.visit_u64(v),
+            Value::F32(v) => visitor.visit_f32(v),
+            Value::F64(v) => visitor.visit_f64(v),
+            Value::String(v) => visitor.visit_string(v),
+            Value::Time(v) => visit_map(visitor, [("sec", v.sec), ("nsec", v.nsec)].into_iter()),
+            Value::Duration(v) => {
+                visit_map(visitor, [("sec", v.sec), ("nsec", v.nsec)].into_iter())
+            }
+            Value::Array(v) => visit_seq(visitor, v.into_iter()),
+            Value::PrimitiveArray(v) => {
+                visit_primitive_array!(v, visitor, values => values.into_iter())
+            }
+            Value::Message(v) => visit_map(visitor, v.into_iter()),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::PrimitiveArray(PrimitiveArray::U8(v)) => visitor.visit_byte_buf(v),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Message(v) if v.len() == 1 => visitor.visit_enum(MapAccessDeserializer::new(
+                MapDeserializer::new(v.into_iter()),
+            )),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> IntoDeserializer<'de, Error>
+    for Value<S>
+{
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> de::Deserializer<'de>
+    for &'de Value<S>
+{
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::String(v) => visitor.visit_borrowed_str(v),
+            Value::Array(v) => visit_seq(visitor, v.iter()),
+            Value::PrimitiveArray(v) => {
+                visit_primitive_array!(v, visitor, values => values.iter().copied())
+            }
+            Value::Message(v) => visit_map(visitor, message_entries(v)),
+            // Everything else is cheap to copy
+            value => value.clone().deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::PrimitiveArray(PrimitiveArray::U8(v)) => visitor.visit_borrowed_bytes(v),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+            Value::Message(v) if v.len() == 1 => visitor.visit_enum(MapAccessDeserializer::new(
+                MapDeserializer::new(message_entries(v)),
+            )),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de, S: BuildHasher + Default + Clone + core::fmt::Debug> IntoDeserializer<'de, Error>
+    for &'de Value<S>
+{
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn message_entries<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    message: &MessageValue<S>,
+) -> impl Iterator<Item = (&str, &Value<S>)> {
+    message.iter().map(|(name, value)| (name.as_str(), value))
+}
+
+/// Serializes any value into a [Value] without a message definition, see [to_value()]
+struct ValueSerializer<S>(PhantomData<S>);
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::Serializer for ValueSerializer<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+    type SerializeSeq = ArraySerializer<S>;
+    type SerializeTuple = ArraySerializer<S>;
+    type SerializeTupleStruct = ArraySerializer<S>;
+    type SerializeTupleVariant = ArraySerializer<S>;
+    type SerializeMap = MessageSerializer<S>;
+    type SerializeStruct = MessageSerializer<S>;
+    type SerializeStructVariant = MessageSerializer<S>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value<S>> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value<S>> {
+        Ok(Value::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value<S>> {
+        Ok(Value::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value<S>> {
+        Ok(Value::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value<S>> {
+        Ok(Value::I64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value<S>> {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Value::I64(v)),
+            Err(_) => Err(ser::Error::custom(format!("{v} does not fit into `int64`"))),
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value<S>> {
+        Ok(Value::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value<S>> {
+        Ok(Value::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value<S>> {
+        Ok(Value::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value<S>> {
+        Ok(Value::U64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value<S>> {
+        match u64::try_from(v) {
+            Ok(v) => Ok(Value::U64(v)),
+            Err(_) => Err(ser::Error::custom(format!(
+                "{v} does not fit into `uint64`"
+            ))),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value<S>> {
+        Ok(Value::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value<S>> {
+        Ok(Value::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value<S>> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value<S>> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value<S>> {
+        Ok(Value::PrimitiveArray(PrimitiveArray::U8(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Value<S>> {
+        Err(ser::Error::custom(
+            "`None` can not be represented as a value",
+        ))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value<S>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value<S>> {
+        Err(ser::Error::custom("`()` can not be represented as a value"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Value<S>> {
+        Err(ser::Error::custom(format!(
+            "the unit struct `{name}` can not be represented as a value"
+        )))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value<S>> {
+        Ok(Value::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value<S>> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value<S>> {
+        let mut message = MessageValue::with_capacity_and_hasher(1, S::default());
+        message.insert(variant.to_owned(), to_value(value)?);
+        Ok(Value::Message(message))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArraySerializer<S>> {
+        Ok(ArraySerializer {
+            values: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArraySerializer<S>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ArraySerializer<S>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ArraySerializer<S>> {
+        Ok(ArraySerializer {
+            values: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MessageSerializer<S>> {
+        Ok(MessageSerializer {
+            message: MessageValue::with_capacity_and_hasher(len.unwrap_or_default(), S::default()),
+            name: None,
+            variant: None,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<MessageSerializer<S>> {
+        Ok(MessageSerializer {
+            name: Some(name),
+            ..self.serialize_map(Some(len))?
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<MessageSerializer<S>> {
+        Ok(MessageSerializer {
+            variant: Some(variant),
+            ..self.serialize_map(Some(len))?
+        })
+    }
+}
+
+/// Wraps the value of an enum variant into a message with a single field named like the variant
+fn variant_value<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+    variant: Option<&'static str>,
+    value: Value<S>,
+) -> Value<S> {
+    match variant {
+        Some(variant) => [(variant, value)].into_iter().collect(),
+        None => value,
+    }
+}
+
+/// Collects the elements of sequences and tuples into a [Value::Array]
+struct ArraySerializer<S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    values: Vec<Value<S>>,
+    variant: Option<&'static str>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeSeq for ArraySerializer<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(variant_value(self.variant, Value::Array(self.values)))
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeTuple
+    for ArraySerializer<S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeTupleStruct
+    for ArraySerializer<S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeTupleVariant
+    for ArraySerializer<S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Collects the fields of structs and entries of maps into a [Value::Message]
+struct MessageSerializer<S: BuildHasher + Default + Clone + core::fmt::Debug> {
+    message: MessageValue<S>,
+    /// Name of the serialized struct
+    name: Option<&'static str>,
+    variant: Option<&'static str>,
+    /// Key of the map entry whose value is serialized next
+    key: Option<String>,
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> MessageSerializer<S> {
+    fn finish(self) -> Value<S> {
+        let fields = (self.message.get("sec"), self.message.get("nsec"));
+        let value = match (self.name, fields, self.message.len()) {
+            (Some("Time"), (Some(Value::U32(sec)), Some(Value::U32(nsec))), 2) => {
+                Value::Time(Time {
+                    sec: *sec,
+                    nsec: *nsec,
+                })
+            }
+            (Some("Duration"), (Some(Value::I32(sec)), Some(Value::I32(nsec))), 2) => {
+                Value::Duration(Duration {
+                    sec: *sec,
+                    nsec: *nsec,
+                })
+            }
+            _ => Value::Message(self.message),
+        };
+        variant_value(self.variant, value)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeMap
+    for MessageSerializer<S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match to_value::<S, _>(key) {
+            Ok(Value::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("map keys must be strings")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let Some(key) = self.key.take() else {
+            return Err(ser::Error::custom("a value was serialized before its key"));
+        };
+        self.message.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(self.finish())
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeStruct
+    for MessageSerializer<S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.message.insert(key.to_owned(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(self.finish())
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ser::SerializeStructVariant
+    for MessageSerializer<S>
+{
+    type Ok = Value<S>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value<S>> {
+        Ok(self.finish())
+    }
+}
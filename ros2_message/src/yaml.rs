@@ -0,0 +1,480 @@
+use crate::{MessageValue, Value};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+use std::hash::BuildHasher;
+
+/// Options that change how values are written by [ToYaml].
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::YamlOptions;
+///
+/// let options = YamlOptions {
+///     truncate_length: Some(128),
+///     ..Default::default()
+/// };
+/// assert_eq!(YamlOptions::default().truncate_length, None);
+/// assert!(!YamlOptions::default().separator);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct YamlOptions {
+    /// Shortens strings and arrays longer than the given length like
+    /// `ros2 topic echo --truncate-length` does. Strings keep their first characters followed
+    /// by `...`, arrays keep their first elements followed by a `'...'` element.
+    pub truncate_length: Option<usize>,
+    /// Writes a `---` line after the value, which separates consecutive messages in the output
+    /// of `ros2 topic echo`.
+    pub separator: bool,
+}
+
+/// Writes decoded values as YAML exactly like `ros2 topic echo` prints messages.
+///
+/// Messages are written as block mappings with their fields in the order of the message, arrays
+/// as block sequences and [Time](crate::Time) and [Duration](crate::Duration) as mappings with
+/// `sec` and `nanosec` fields like `builtin_interfaces/Time`. Scalars are quoted and floats are
+/// formatted the way PyYAML does it, so the output can be compared to the output of the ROS 2
+/// command line tools byte by byte.
+///
+/// # Examples
+///
+/// ```
+/// use ros2_message::{ToYaml, Time, Value};
+///
+/// let value: Value = [
+///     ("stamp", Value::Time(Time { sec: 1, nsec: 5 })),
+///     ("ranges", Value::Array(vec![Value::F32(0.5), Value::F32(f32::NAN)])),
+///     ("frame_id", Value::String("laser".into())),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// assert_eq!(
+///     value.to_yaml(),
+///     "stamp:\n  sec: 1\n  nanosec: 5\nranges:\n- 0.5\n- .nan\nframe_id: laser\n"
+/// );
+/// ```
+pub trait ToYaml {
+    /// Writes the value as YAML with the provided options.
+    fn write_yaml(&self, w: &mut dyn Write, options: &YamlOptions) -> fmt::Result;
+
+    /// Returns the value as YAML with the default options.
+    fn to_yaml(&self) -> String {
+        self.to_yaml_with_options(&YamlOptions::default())
+    }
+
+    /// Returns the value as YAML with the provided options.
+    fn to_yaml_with_options(&self, options: &YamlOptions) -> String {
+        let mut yaml = String::new();
+        self.write_yaml(&mut yaml, options)
+            .expect("Writing to a string does not fail");
+        yaml
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ToYaml for Value<S> {
+    fn write_yaml(&self, w: &mut dyn Write, options: &YamlOptions) -> fmt::Result {
+        YamlWriter { w }.document(&Node::new(self, options), options)
+    }
+}
+
+impl<S: BuildHasher + Default + Clone + core::fmt::Debug> ToYaml for MessageValue<S> {
+    fn write_yaml(&self, w: &mut dyn Write, options: &YamlOptions) -> fmt::Result {
+        YamlWriter { w }.document(&Node::fields(self, options), options)
+    }
+}
+
+/// A value prepared for writing, with truncation already applied
+enum Node<'a> {
+    /// Numbers and booleans, which never need quotes
+    Plain(String),
+    Str(Cow<'a, str>),
+    Seq(Vec<Node<'a>>),
+    Map(Vec<(&'a str, Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+    fn new<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        value: &'a Value<S>,
+        options: &YamlOptions,
+    ) -> Self {
+        match value {
+            Value::Bool(v) => Node::Plain(v.to_string()),
+            Value::I8(v) => Node::Plain(v.to_string()),
+            Value::I16(v) => Node::Plain(v.to_string()),
+            Value::I32(v) => Node::Plain(v.to_string()),
+            Value::I64(v) => Node::Plain(v.to_string()),
+            Value::U8(v) => Node::Plain(v.to_string()),
+            Value::U16(v) => Node::Plain(v.to_string()),
+            Value::U32(v) => Node::Plain(v.to_string()),
+            Value::U64(v) => Node::Plain(v.to_string()),
+            // Python only knows doubles, so `float32` values are printed with all their digits
+            Value::F32(v) => Node::Plain(float(f64::from(*v))),
+            Value::F64(v) => Node::Plain(float(*v)),
+            Value::String(v) => match options.truncate_length {
+                Some(length) if v.chars().count() > length => {
+                    let end = v.char_indices().nth(length).map_or(v.len(), |(i, _)| i);
+                    Node::Str(Cow::Owned(format!("{}...", &v[..end])))
+                }
+                _ => Node::Str(Cow::Borrowed(v)),
+            },
+            Value::Time(v) => Node::Map(vec![
+                ("sec", Node::Plain(v.sec.to_string())),
+                ("nanosec", Node::Plain(v.nsec.to_string())),
+            ]),
+            Value::Duration(v) => Node::Map(vec![
+                ("sec", Node::Plain(v.sec.to_string())),
+                ("nanosec", Node::Plain(v.nsec.to_string())),
+            ]),
+            Value::Array(items) => Node::seq(items.iter().map(|v| Node::new(v, options)), options),
+            Value::PrimitiveArray(items) => Node::seq(
+                items
+                    .to_values::<S>()
+                    .iter()
+                    .map(|v| Node::new(v, options).into_owned()),
+                options,
+            ),
+            Value::Message(fields) => Node::fields(fields, options),
+        }
+    }
+
+    fn fields<S: BuildHasher + Default + Clone + core::fmt::Debug>(
+        fields: &'a MessageValue<S>,
+        options: &YamlOptions,
+    ) -> Self {
+        Node::Map(
+            fields
+                .iter()
+                .map(|(k, v)| (k.as_str(), Node::new(v, options)))
+                .collect(),
+        )
+    }
+
+    fn seq(items: impl ExactSizeIterator<Item = Node<'a>>, options: &YamlOptions) -> Self {
+        match options.truncate_length {
+            Some(length) if items.len() > length => Node::Seq(
+                items
+                    .take(length)
+                    .chain([Node::Str(Cow::Borrowed("..."))])
+                    .collect(),
+            ),
+            _ => Node::Seq(items.collect()),
+        }
+    }
+
+    /// Detaches the node from the value, which is needed for temporary values
+    fn into_owned<'b>(self) -> Node<'b> {
+        match self {
+            Node::Plain(v) => Node::Plain(v),
+            Node::Str(v) => Node::Str(Cow::Owned(v.into_owned())),
+            Node::Seq(_) | Node::Map(_) => unreachable!("primitive arrays only contain scalars"),
+        }
+    }
+}
+
+/// Formats a float like Python's `repr()`, with the changes PyYAML applies to it
+fn float(v: f64) -> String {
+    if v.is_nan() {
+        return ".nan".to_owned();
+    } else if v.is_infinite() {
+        return if v > 0.0 { ".inf" } else { "-.inf" }.to_owned();
+    } else if v == 0.0 {
+        return if v.is_sign_negative() { "-0.0" } else { "0.0" }.to_owned();
+    }
+
+    // The shortest digits that read back to the same value, which is what Python prints too
+    let scientific = format!("{:e}", v.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let digits = mantissa.replace('.', "");
+    let sign = if v < 0.0 { "-" } else { "" };
+    let decimal_point = exponent + 1;
+
+    if !(-3..=16).contains(&decimal_point) {
+        // PyYAML adds `.0` to `1e+16`, so that YAML 1.1 parsers read it as a float
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() { "0" } else { rest };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        format!("{sign}{first}.{rest}e{exponent_sign}{:02}", exponent.abs())
+    } else if decimal_point <= 0 {
+        let zeros = "0".repeat(decimal_point.unsigned_abs() as usize);
+        format!("{sign}0.{zeros}{digits}")
+    } else if decimal_point as usize >= digits.len() {
+        let zeros = "0".repeat(decimal_point as usize - digits.len());
+        format!("{sign}{digits}{zeros}.0")
+    } else {
+        let (integer, fraction) = digits.split_at(decimal_point as usize);
+        format!("{sign}{integer}.{fraction}")
+    }
+}
+
+struct YamlWriter<'a> {
+    w: &'a mut dyn Write,
+}
+
+impl YamlWriter<'_> {
+    fn document(&mut self, node: &Node, options: &YamlOptions) -> fmt::Result {
+        match node {
+            Node::Map(fields) if !fields.is_empty() => self.mapping(fields, 0)?,
+            Node::Seq(items) if !items.is_empty() => self.sequence(items, 0)?,
+            node => {
+                // A plain scalar does not show where it ends, so PyYAML ends the document
+                if self.scalar(node, 2)? == Style::Plain {
+                    self.w.write_str("\n...")?;
+                }
+            }
+        }
+        self.w.write_char('\n')?;
+        if options.separator {
+            self.w.write_str("---\n")?;
+        }
+        Ok(())
+    }
+
+    fn mapping(&mut self, fields: &[(&str, Node)], indent: usize) -> fmt::Result {
+        for (index, (key, value)) in fields.iter().enumerate() {
+            if index > 0 {
+                self.newline(indent)?;
+            }
+            self.string(key, indent + 2, true)?;
+            self.w.write_char(':')?;
+            match value {
+                Node::Map(fields) if !fields.is_empty() => {
+                    self.newline(indent + 2)?;
+                    self.mapping(fields, indent + 2)?;
+                }
+                // Sequences in mappings are not indented
+                Node::Seq(items) if !items.is_empty() => {
+                    self.newline(indent)?;
+                    self.sequence(items, indent)?;
+                }
+                value => {
+                    self.w.write_char(' ')?;
+                    self.scalar(value, indent + 2)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sequence(&mut self, items: &[Node], indent: usize) -> fmt::Result {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.newline(indent)?;
+            }
+            self.w.write_str("- ")?;
+            match item {
+                Node::Map(fields) if !fields.is_empty() => self.mapping(fields, indent + 2)?,
+                Node::Seq(items) if !items.is_empty() => self.sequence(items, indent + 2)?,
+                item => {
+                    self.scalar(item, indent + 2)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a scalar or an empty collection, `indent` is used by strings with line breaks
+    fn scalar(&mut self, node: &Node, indent: usize) -> Result<Style, fmt::Error> {
+        match node {
+            Node::Plain(v) => self.w.write_str(v).map(|()| Style::Plain),
+            Node::Str(v) => self.string(v, indent, false),
+            Node::Seq(_) => self.w.write_str("[]").map(|()| Style::Flow),
+            Node::Map(_) => self.w.write_str("{}").map(|()| Style::Flow),
+        }
+    }
+
+    fn string(&mut self, v: &str, indent: usize, key: bool) -> Result<Style, fmt::Error> {
+        let style = Style::of(v, key);
+        match style {
+            Style::Plain | Style::Flow => self.w.write_str(v)?,
+            Style::SingleQuoted => self.single_quoted(v, indent)?,
+            Style::DoubleQuoted => self.double_quoted(v)?,
+        }
+        Ok(style)
+    }
+
+    /// Writes a string in single quotes, line breaks are written twice like PyYAML does
+    fn single_quoted(&mut self, v: &str, indent: usize) -> fmt::Result {
+        self.w.write_char('\'')?;
+        let mut chars = v.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => self.w.write_str("''")?,
+                c if is_break(c) => {
+                    if c == '\n' {
+                        self.w.write_char('\n')?;
+                    }
+                    self.w.write_char(c)?;
+                    while let Some(c) = chars.next_if(|c| is_break(*c)) {
+                        self.w.write_char(c)?;
+                    }
+                    write!(self.w, "{:indent$}", "")?;
+                }
+                c => self.w.write_char(c)?,
+            }
+        }
+        self.w.write_char('\'')
+    }
+
+    fn double_quoted(&mut self, v: &str) -> fmt::Result {
+        self.w.write_char('"')?;
+        for c in v.chars() {
+            let escape = match c {
+                '"' => "\"",
+                '\\' => "\\",
+                '\u{85}' => "N",
+                '\u{2028}' => "L",
+                '\u{2029}' => "P",
+                '\u{feff}' => "uFEFF",
+                ' '..='~' | '\u{a0}'..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' => {
+                    self.w.write_char(c)?;
+                    continue;
+                }
+                '\0' => "0",
+                '\x07' => "a",
+                '\x08' => "b",
+                '\t' => "t",
+                '\n' => "n",
+                '\x0b' => "v",
+                '\x0c' => "f",
+                '\r' => "r",
+                '\x1b' => "e",
+                c => {
+                    match c as u32 {
+                        n @ ..=0xff => write!(self.w, "\\x{n:02X}")?,
+                        n @ ..=0xffff => write!(self.w, "\\u{n:04X}")?,
+                        n => write!(self.w, "\\U{n:08X}")?,
+                    }
+                    continue;
+                }
+            };
+            self.w.write_char('\\')?;
+            self.w.write_str(escape)?;
+        }
+        self.w.write_char('"')
+    }
+
+    fn newline(&mut self, indent: usize) -> fmt::Result {
+        write!(self.w, "\n{:indent$}", "")
+    }
+}
+
+fn is_break(c: char) -> bool {
+    matches!(c, '\n' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_space_or_break(c: char) -> bool {
+    matches!(c, '\0' | ' ' | '\t' | '\r') || is_break(c)
+}
+
+/// How a scalar is written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Style {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    /// `[]` and `{}`
+    Flow,
+}
+
+impl Style {
+    /// Picks the style PyYAML picks for a string in block context, see `analyze_scalar()` and
+    /// `choose_scalar_style()` of its emitter.
+    fn of(v: &str, key: bool) -> Self {
+        let chars: Vec<char> = v.chars().collect();
+        let Some(&last) = chars.last() else {
+            return Style::SingleQuoted;
+        };
+
+        let mut block_indicators = v.starts_with("---") || v.starts_with("...");
+        let mut line_breaks = false;
+        let mut special_characters = false;
+        let mut break_space = false;
+        let mut space_break = false;
+        let mut previous_space = false;
+        let mut previous_break = false;
+        let mut preceded_by_whitespace = true;
+
+        for (index, &c) in chars.iter().enumerate() {
+            let followed_by_whitespace = chars
+                .get(index + 1)
+                .is_none_or(|next| is_space_or_break(*next));
+            if index == 0 {
+                block_indicators |= "#,[]{}&*!|>'\"%@`".contains(c)
+                    || (matches!(c, '?' | ':' | '-') && followed_by_whitespace);
+            } else {
+                block_indicators |=
+                    (c == ':' && followed_by_whitespace) || (c == '#' && preceded_by_whitespace);
+            }
+
+            line_breaks |= is_break(c);
+            if !(c == '\n' || (' '..='~').contains(&c)) {
+                let unicode = (c == '\u{85}'
+                    || ('\u{a0}'..='\u{d7ff}').contains(&c)
+                    || ('\u{e000}'..='\u{fffd}').contains(&c)
+                    || ('\u{10000}'..'\u{10ffff}').contains(&c))
+                    && c != '\u{feff}';
+                special_characters |= !unicode;
+            }
+
+            if c == ' ' {
+                break_space |= previous_break;
+                (previous_space, previous_break) = (true, false);
+            } else if is_break(c) {
+                space_break |= previous_space;
+                (previous_space, previous_break) = (false, true);
+            } else {
+                (previous_space, previous_break) = (false, false);
+            }
+            preceded_by_whitespace = is_space_or_break(c);
+        }
+
+        let edge_whitespace = |c: char| c == ' ' || is_break(c);
+        let plain = !(edge_whitespace(chars[0])
+            || edge_whitespace(last)
+            || break_space
+            || space_break
+            || special_characters
+            || line_breaks
+            || block_indicators);
+        let single_quoted = !(break_space || space_break || special_characters);
+
+        if plain && !is_implicit(v) {
+            Style::Plain
+        } else if single_quoted && !(key && line_breaks) {
+            Style::SingleQuoted
+        } else {
+            Style::DoubleQuoted
+        }
+    }
+}
+
+/// Returns true if YAML 1.1 reads the plain scalar as something else than a string
+fn is_implicit(v: &str) -> bool {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(concat!(
+            // bool
+            "^(?:yes|Yes|YES|no|No|NO|true|True|TRUE|false|False|FALSE|on|On|ON|off|Off|OFF",
+            // float
+            r"|[-+]?[0-9][0-9_]*\.[0-9_]*(?:[eE][-+][0-9]+)?",
+            r"|\.[0-9][0-9_]*(?:[eE][-+][0-9]+)?",
+            r"|[-+]?[0-9][0-9_]*(?::[0-5]?[0-9])+\.[0-9_]*",
+            r"|[-+]?\.(?:inf|Inf|INF)|\.(?:nan|NaN|NAN)",
+            // int
+            r"|[-+]?0b[0-1_]+|[-+]?0[0-7_]+|[-+]?(?:0|[1-9][0-9_]*)|[-+]?0x[0-9a-fA-F_]+",
+            r"|[-+]?[1-9][0-9_]*(?::[0-5]?[0-9])+",
+            // merge, null and value
+            r"|<<|~|null|Null|NULL|=",
+            // timestamp
+            r"|[0-9]{4}-[0-9]{2}-[0-9]{2}",
+            r"|[0-9]{4}-[0-9]{1,2}-[0-9]{1,2}(?:[Tt]|[ \t]+)[0-9]{1,2}:[0-9]{2}:[0-9]{2}",
+            r"(?:\.[0-9]*)?(?:[ \t]*(?:Z|[-+][0-9]{1,2}(?::[0-9]{2})?))?",
+            ")$"
+        ))
+        .unwrap();
+    }
+    RE.is_match(v)
+}